@@ -0,0 +1,89 @@
+//! 压缩输出（`--compress`）：gzip 与 zstd
+//!
+//! 整个游戏的映射表有几十 MB，压缩后放进仓库与游戏包都省空间。压缩过的文件在原文件名后追加
+//! `.gz` / `.zst`（如 `battle_s.json.gz`）；读取时按这个扩展名自动解压，其余扩展名照原样读取。
+//! 不按文件开头的魔数判断：bincode 映射表的开头可能恰好与魔数相同。
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+
+/// zstd 的压缩级别（与 zstd 命令行工具的默认值相同）
+const ZSTD_LEVEL: i32 = 3;
+
+/// 压缩格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// gzip，扩展名 `.gz`
+    Gzip,
+    /// zstd，扩展名 `.zst`，压缩率与速度都更好
+    Zstd,
+}
+
+impl Compression {
+    /// 追加在原文件名后的扩展名（不含 `.`）
+    pub fn extension(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+        }
+    }
+
+    /// 按扩展名判断文件是否压缩过
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "gz" => Some(Compression::Gzip),
+            "zst" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// 压缩
+    pub fn compress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            Compression::Zstd => zstd::encode_all(data, ZSTD_LEVEL),
+        }
+    }
+
+    /// 解压
+    pub fn decompress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Compression::Gzip => {
+                let mut out = Vec::new();
+                GzDecoder::new(data).read_to_end(&mut out)?;
+                Ok(out)
+            }
+            Compression::Zstd => zstd::decode_all(data),
+        }
+    }
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gzip" | "gz" => Ok(Compression::Gzip),
+            "zstd" | "zst" => Ok(Compression::Zstd),
+            other => Err(format!("未知的压缩格式 {}（可选 gzip / zstd）", other)),
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        })
+    }
+}
@@ -0,0 +1,85 @@
+//! 字符串所在的上下文：外层的函数、类、方法、调用表达式与 JSX 元素
+//!
+//! 每一层对应一个名字，例如 `Battle`（类）、`start`（方法）、`showDialog(...)`（调用）、
+//! `<Button>`（JSX 元素），由外到内用 ` > ` 连接，如 `Battle > start > showDialog(...)`。
+//! 无法给出名字的层（匿名函数、计算属性名等）直接跳过。
+
+use swc_core::ecma::ast::{
+    Callee, Expr, JSXElementName, JSXObject, MemberProp, Pat, PropName, VarDeclarator,
+};
+
+/// 上下文各层之间的分隔符
+pub(crate) const SEPARATOR: &str = " > ";
+
+/// 取表达式的名字：只支持标识符、`this` 与由它们组成的成员访问链（如 `ui.dialog.show`）
+pub(crate) fn expr_name(e: &Expr) -> Option<String> {
+    match e {
+        Expr::Ident(i) => Some(i.sym.to_string()),
+        Expr::This(_) => Some("this".to_string()),
+        Expr::Member(m) => {
+            let obj = expr_name(&m.obj)?;
+            match &m.prop {
+                MemberProp::Ident(p) => Some(format!("{}.{}", obj, p.sym)),
+                MemberProp::PrivateName(p) => Some(format!("{}.#{}", obj, p.name)),
+                MemberProp::Computed(_) => None,
+            }
+        }
+        Expr::Paren(p) => expr_name(&p.expr),
+        _ => None,
+    }
+}
+
+/// 被调用函数的名字，如 `showDialog`、`i18n.t`
+pub(crate) fn callee_name(callee: &Callee) -> Option<String> {
+    match callee {
+        Callee::Expr(e) => expr_name(e),
+        Callee::Import(_) => Some("import".to_string()),
+        Callee::Super(_) => Some("super".to_string()),
+    }
+}
+
+/// 调用表达式的上下文名，如 `showDialog(...)`
+pub(crate) fn call_name(callee: &Callee) -> Option<String> {
+    callee_name(callee).map(|name| format!("{}(...)", name))
+}
+
+/// 方法或属性的名字（计算属性名没有静态名字）
+pub(crate) fn prop_name(key: &PropName) -> Option<String> {
+    match key {
+        PropName::Ident(i) => Some(i.sym.to_string()),
+        PropName::Str(s) => s.value.as_str().map(str::to_string),
+        PropName::Num(n) => Some(n.value.to_string()),
+        PropName::Computed(_) | PropName::BigInt(_) => None,
+    }
+}
+
+/// 以函数或类为初始值的变量声明的名字，如 `const onClick = () => ...`
+pub(crate) fn declarator_name(n: &VarDeclarator) -> Option<String> {
+    let is_fn = n
+        .init
+        .as_deref()
+        .is_some_and(|e| matches!(e, Expr::Arrow(_) | Expr::Fn(_) | Expr::Class(_)));
+    match &n.name {
+        Pat::Ident(i) if is_fn => Some(i.id.sym.to_string()),
+        _ => None,
+    }
+}
+
+fn jsx_object_name(obj: &JSXObject) -> String {
+    match obj {
+        JSXObject::Ident(i) => i.sym.to_string(),
+        JSXObject::JSXMemberExpr(m) => format!("{}.{}", jsx_object_name(&m.obj), m.prop.sym),
+    }
+}
+
+/// JSX 元素的上下文名，如 `<Button>`、`<Dialog.Title>`
+pub(crate) fn jsx_name(name: &JSXElementName) -> String {
+    let name = match name {
+        JSXElementName::Ident(i) => i.sym.to_string(),
+        JSXElementName::JSXMemberExpr(m) => {
+            format!("{}.{}", jsx_object_name(&m.obj), m.prop.sym)
+        }
+        JSXElementName::JSXNamespacedName(n) => format!("{}:{}", n.ns.sym, n.name.sym),
+    };
+    format!("<{}>", name)
+}
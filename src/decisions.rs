@@ -0,0 +1,109 @@
+//! 字符串的取舍记录（`review` 子命令）：哪些字符串要替换、哪些保持原样
+//!
+//! 每条记录以 [`string_hash`](crate::string_hash)（文件路径 + 字符串内容的 xxhash64）为标识，
+//! 同一个文件中内容相同的字符串共用一条记录；源码中字符串的位置变了，记录仍然有效。
+//! 文件格式：
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "decisions": {
+//!     "3f9a0c...": { "file": "src/battle.ts", "text": "id-001", "include": false }
+//!   }
+//! }
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::{Map, Value};
+
+use crate::{JsonStyle, to_canonical_json};
+
+/// 取舍记录文件的格式版本
+pub const DECISIONS_VERSION: u64 = 1;
+
+/// 一个字符串的取舍
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decision {
+    /// 来源文件（`/` 分隔）
+    pub file: String,
+    /// 字符串内容，只用于阅读与排查
+    pub text: String,
+    /// 是否替换
+    pub include: bool,
+}
+
+/// 全部取舍记录，按标识排列
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Decisions {
+    pub records: BTreeMap<String, Decision>,
+}
+
+impl Decisions {
+    /// 解析取舍记录文件
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let value: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        let version = value.get("version").and_then(Value::as_u64);
+        if version != Some(DECISIONS_VERSION) {
+            return Err(format!(
+                "不支持的格式版本 {}（应为 {}）",
+                version.map_or_else(|| "（缺失）".to_string(), |v| v.to_string()),
+                DECISIONS_VERSION
+            ));
+        }
+        let Some(decisions) = value.get("decisions").and_then(Value::as_object) else {
+            return Err("缺少 \"decisions\" 对象".to_string());
+        };
+        let mut records = BTreeMap::new();
+        for (hash, record) in decisions {
+            let field = |name: &str| record.get(name).and_then(Value::as_str);
+            let (Some(file), Some(text), Some(include)) = (
+                field("file"),
+                field("text"),
+                record.get("include").and_then(Value::as_bool),
+            ) else {
+                return Err(format!(
+                    "记录 {} 应为 {{\"file\", \"text\", \"include\"}}",
+                    hash
+                ));
+            };
+            records.insert(
+                hash.clone(),
+                Decision {
+                    file: file.to_string(),
+                    text: text.to_string(),
+                    include,
+                },
+            );
+        }
+        Ok(Self { records })
+    }
+
+    /// 写成 JSON（与映射表相同的确定性格式）
+    pub fn to_json(&self) -> String {
+        let decisions = self
+            .records
+            .iter()
+            .map(|(hash, d)| {
+                let mut obj = Map::new();
+                obj.insert("file".to_string(), Value::String(d.file.clone()));
+                obj.insert("text".to_string(), Value::String(d.text.clone()));
+                obj.insert("include".to_string(), Value::Bool(d.include));
+                (hash.clone(), Value::Object(obj))
+            })
+            .collect();
+        let mut root = Map::new();
+        root.insert("version".to_string(), Value::from(DECISIONS_VERSION));
+        root.insert("decisions".to_string(), Value::Object(decisions));
+        to_canonical_json(&Value::Object(root), JsonStyle::default())
+    }
+
+    /// 被排除（不替换）的字符串的标识，用于 [`ExtractOptions::excluded`](crate::ExtractOptions::excluded)
+    pub fn excluded(&self) -> BTreeSet<String> {
+        self.records
+            .iter()
+            .filter(|(_, d)| !d.include)
+            .map(|(hash, _)| hash.clone())
+            .collect()
+    }
+}
@@ -0,0 +1,314 @@
+//! 比较两张映射表（`check` 与 `diff` 子命令）：按 key 列出新增、删除与原文变化的字符串。
+//!
+//! 原文相同、只有附加信息（位置、出现次数、上下文等）不同的记录单独列出，
+//! `check` 把它们也算作映射表过期，`diff` 只在统计中给出。
+//! 结果按 key 的自然顺序排列（`"2"` 在 `"10"` 前面）。
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use serde_json::{Map, Value};
+
+use crate::entry_text;
+
+/// 原文发生变化的一条记录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedString {
+    pub key: String,
+    /// 旧映射表中的原文
+    pub old: String,
+    /// 新映射表中的原文
+    pub new: String,
+}
+
+/// 两张映射表的差异
+#[derive(Debug, Clone, Default)]
+pub struct MappingDiff {
+    /// 只在新映射表中出现的记录（key 与原文）
+    pub added: Vec<(String, String)>,
+    /// 只在旧映射表中出现的记录（key 与原文）
+    pub removed: Vec<(String, String)>,
+    /// 同一个 key 的原文变了
+    pub changed: Vec<ChangedString>,
+    /// 原文相同、附加信息不同的 key
+    pub metadata: Vec<String>,
+}
+
+impl MappingDiff {
+    /// 两张映射表完全一致
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.metadata.is_empty()
+    }
+}
+
+/// 按 key 比较旧映射表与新映射表
+///
+/// 记录可以是纯字符串或 `{"text": ...}` 对象，两种形式之间只按原文比较。
+pub fn diff_mappings(old: &Map<String, Value>, new: &Map<String, Value>) -> MappingDiff {
+    let mut diff = MappingDiff::default();
+    for (key, value) in new {
+        let text = entry_text(value).unwrap_or_default();
+        let Some(old_value) = old.get(key) else {
+            diff.added.push((key.clone(), text.to_string()));
+            continue;
+        };
+        let old_text = entry_text(old_value).unwrap_or_default();
+        if old_text != text {
+            diff.changed.push(ChangedString {
+                key: key.clone(),
+                old: old_text.to_string(),
+                new: text.to_string(),
+            });
+        } else if old_value != value {
+            diff.metadata.push(key.clone());
+        }
+    }
+    for (key, value) in old {
+        if !new.contains_key(key) {
+            let text = entry_text(value).unwrap_or_default();
+            diff.removed.push((key.clone(), text.to_string()));
+        }
+    }
+    diff.added.sort_by(|a, b| natural_cmp(&a.0, &b.0));
+    diff.removed.sort_by(|a, b| natural_cmp(&a.0, &b.0));
+    diff.changed.sort_by(|a, b| natural_cmp(&a.key, &b.key));
+    diff.metadata.sort_by(|a, b| natural_cmp(a, b));
+    diff
+}
+
+/// key 的自然顺序：连续的数字按数值比较，`"2"` 排在 `"10"` 前面，`"a.2"` 排在 `"a.10"` 前面
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (Some(x), Some(y)) = (a.chars().next(), b.chars().next()) else {
+            return a.len().cmp(&b.len());
+        };
+        if x.is_ascii_digit() && y.is_ascii_digit() {
+            let end_a = a.find(|c: char| !c.is_ascii_digit()).unwrap_or(a.len());
+            let end_b = b.find(|c: char| !c.is_ascii_digit()).unwrap_or(b.len());
+            let (num_a, num_b) = (
+                a[..end_a].trim_start_matches('0'),
+                b[..end_b].trim_start_matches('0'),
+            );
+            let order = num_a
+                .len()
+                .cmp(&num_b.len())
+                .then_with(|| num_a.cmp(num_b))
+                .then_with(|| end_a.cmp(&end_b));
+            if order != Ordering::Equal {
+                return order;
+            }
+            (a, b) = (&a[end_a..], &b[end_b..]);
+        } else {
+            if x != y {
+                return x.cmp(&y);
+            }
+            (a, b) = (&a[x.len_utf8()..], &b[y.len_utf8()..]);
+        }
+    }
+}
+
+/// 两个序列之间的一步编辑，记录元素在旧序列 / 新序列中的下标
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeqEdit {
+    /// 两边都有（旧下标，新下标）
+    Same(usize, usize),
+    /// 只在旧序列中
+    Removed(usize),
+    /// 只在新序列中
+    Added(usize),
+}
+
+/// 按顺序比较两个序列（Myers 差分算法），得到编辑步数最少的一组编辑
+///
+/// 先去掉共同的开头与结尾，只对中间部分求差分；耗费的时间与空间随差异的多少增长，
+/// 只改了少量字符串的两个大文件也能很快比较完。
+pub fn diff_sequences<T: PartialEq>(old: &[T], new: &[T]) -> Vec<SeqEdit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (a, b) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    let mut edits: Vec<SeqEdit> = (0..prefix).map(|i| SeqEdit::Same(i, i)).collect();
+    edits.extend(myers(a, b).into_iter().map(|edit| match edit {
+        SeqEdit::Same(i, j) => SeqEdit::Same(prefix + i, prefix + j),
+        SeqEdit::Removed(i) => SeqEdit::Removed(prefix + i),
+        SeqEdit::Added(j) => SeqEdit::Added(prefix + j),
+    }));
+    let (old_tail, new_tail) = (old.len() - suffix, new.len() - suffix);
+    edits.extend((0..suffix).map(|i| SeqEdit::Same(old_tail + i, new_tail + i)));
+    edits
+}
+
+/// Myers 差分：第 d 轮记录每条对角线 k = x - y 上走到的最远 x，找到终点后沿记录回溯
+fn myers<T: PartialEq>(a: &[T], b: &[T]) -> Vec<SeqEdit> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = n + m;
+    let offset = max as usize;
+    let mut v = vec![0isize; 2 * offset + 2];
+    // trace[d][k + d]：第 d 轮结束时对角线 k 上的最远 x
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    'search: for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let at = |k: isize| (k + max) as usize;
+            let mut x = if k == -d || (k != d && v[at(k - 1)] < v[at(k + 1)]) {
+                v[at(k + 1)]
+            } else {
+                v[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[at(k)] = x;
+            if x >= n && y >= m {
+                trace.push(v[at(-d)..=at(d)].to_vec());
+                break 'search;
+            }
+        }
+        trace.push(v[(offset as isize - d) as usize..=offset + d as usize].to_vec());
+    }
+
+    // 从终点倒着走回起点
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (1..trace.len() as isize).rev() {
+        let prev = &trace[d as usize - 1];
+        let get = |k: isize| prev[(k + d - 1) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && get(k - 1) < get(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = get(prev_k);
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            edits.push(SeqEdit::Same(x as usize, y as usize));
+        }
+        if x == prev_x {
+            edits.push(SeqEdit::Added(prev_y as usize));
+        } else {
+            edits.push(SeqEdit::Removed(prev_x as usize));
+        }
+        (x, y) = (prev_x, prev_y);
+    }
+    while x > 0 && y > 0 {
+        x -= 1;
+        y -= 1;
+        edits.push(SeqEdit::Same(x as usize, y as usize));
+    }
+    edits.reverse();
+    edits
+}
+
+/// 不考虑顺序比较两个序列（按多重集合）：多出来的出现记为新增，少掉的记为删除
+///
+/// 同一个元素在两边都出现时，按出现顺序依次配对，多余的出现才算差异。
+pub fn diff_multisets<T: Eq + Hash>(old: &[T], new: &[T]) -> Vec<SeqEdit> {
+    let mut counts: HashMap<&T, isize> = HashMap::new();
+    for item in new {
+        *counts.entry(item).or_default() += 1;
+    }
+    let mut edits = Vec::new();
+    for (i, item) in old.iter().enumerate() {
+        match counts.get_mut(item) {
+            Some(n) if *n > 0 => *n -= 1,
+            _ => edits.push(SeqEdit::Removed(i)),
+        }
+    }
+    // 剩下的计数是新序列中没有配对的出现，取最后几次
+    let mut added = Vec::new();
+    for (j, item) in new.iter().enumerate().rev() {
+        if let Some(n) = counts.get_mut(item).filter(|n| **n > 0) {
+            *n -= 1;
+            added.push(SeqEdit::Added(j));
+        }
+    }
+    edits.extend(added.into_iter().rev());
+    edits
+}
+
+/// 差分中的一块改动及其前后的上下文（unified diff 中的一个 `@@` 块）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// 块在旧序列中的起始下标
+    pub old_start: usize,
+    /// 块在旧序列中的元素个数
+    pub old_len: usize,
+    /// 块在新序列中的起始下标
+    pub new_start: usize,
+    /// 块在新序列中的元素个数
+    pub new_len: usize,
+    /// 块内的编辑，上下文为 [`SeqEdit::Same`]
+    pub edits: Vec<SeqEdit>,
+}
+
+/// 把 [`diff_sequences`] 的结果分成块：每处改动前后各带 `context` 个相同的元素，
+/// 上下文重叠或相接的改动合为一块。没有改动时返回空列表。
+pub fn group_hunks(edits: &[SeqEdit], context: usize) -> Vec<Hunk> {
+    // 每步编辑之前在旧 / 新序列中的位置
+    let mut positions = Vec::with_capacity(edits.len());
+    let (mut old, mut new) = (0, 0);
+    for edit in edits {
+        positions.push((old, new));
+        match edit {
+            SeqEdit::Same(..) => (old, new) = (old + 1, new + 1),
+            SeqEdit::Removed(_) => old += 1,
+            SeqEdit::Added(_) => new += 1,
+        }
+    }
+
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (i, edit) in edits.iter().enumerate() {
+        if matches!(edit, SeqEdit::Same(..)) {
+            continue;
+        }
+        let (start, end) = (
+            i.saturating_sub(context),
+            (i + context + 1).min(edits.len()),
+        );
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let edits = edits[start..end].to_vec();
+            let (old_start, new_start) = positions[start];
+            let old_len = edits
+                .iter()
+                .filter(|e| !matches!(e, SeqEdit::Added(_)))
+                .count();
+            let new_len = edits
+                .iter()
+                .filter(|e| !matches!(e, SeqEdit::Removed(_)))
+                .count();
+            Hunk {
+                old_start,
+                old_len,
+                new_start,
+                new_len,
+                edits,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,111 @@
+//! 注释指令：用注释局部关闭字符串替换
+//!
+//!   - `// sb-dice-ignore-file`（或块注释）：整个文件都不替换
+//!   - `// sb-dice-ignore-next-line`：下一行中的字符串不替换
+//!   - `/* sb-dice-ignore */ "x"`：紧跟在注释后面的这一个字符串不替换
+//!
+//! 指令后面可以跟 `:` 或空白再写说明，例如 `// sb-dice-ignore-next-line: 事件名`。
+//!
+//! 另外，`// i18n: ...` 与 `/* note: ... */` 是写给译者的说明，记录到映射表中紧随其后的字符串上：
+//! 从注释后的第一个 token 开始，到这一行结束之间的字符串都带上这条说明。
+
+use std::collections::{BTreeMap, HashSet};
+
+use swc_core::common::comments::{Comment, SingleThreadedComments};
+use swc_core::common::{BytePos, SourceMap};
+
+/// 从注释中收集到的指令
+#[derive(Debug, Default)]
+pub(crate) struct Directives {
+    /// 整个文件都不替换
+    pub ignore_file: bool,
+    /// 不替换的行号（从 1 开始）
+    pub ignored_lines: HashSet<usize>,
+    /// 带有 `sb-dice-ignore` 前置注释的位置（即注释后第一个 token 的起始位置）
+    pub ignored_positions: HashSet<BytePos>,
+    /// 写给译者的说明：注释后第一个 token 的起始位置 -> (该 token 所在的行号, 说明)
+    pub notes: BTreeMap<BytePos, (usize, String)>,
+}
+
+/// 说明注释的前缀
+const NOTE_PREFIXES: [&str; 2] = ["i18n", "note"];
+
+/// 取注释中的指令名（第一个以空白或 `:` 分隔的词）
+fn directive_name(cmt: &Comment) -> &str {
+    cmt.text
+        .trim()
+        .split(|c: char| c.is_whitespace() || c == ':')
+        .next()
+        .unwrap_or_default()
+}
+
+/// 取说明注释（`i18n:` 或 `note:` 开头）的内容；多行的块注释去掉每行开头的 `*`，以空格连接
+fn note_text(cmt: &Comment) -> Option<String> {
+    let (name, rest) = cmt
+        .text
+        .trim_start_matches(|c: char| c == '*' || c.is_whitespace())
+        .split_once(':')?;
+    if !NOTE_PREFIXES.contains(&name.trim()) {
+        return None;
+    }
+    let note = rest
+        .lines()
+        .map(|line| line.trim().trim_start_matches('*').trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!note.is_empty()).then_some(note)
+}
+
+impl Directives {
+    /// 扫描所有注释，收集指令
+    pub fn collect(cm: &SourceMap, comments: &SingleThreadedComments) -> Self {
+        let mut directives = Self::default();
+        let (leading, trailing) = comments.borrow_all();
+
+        for (&pos, cmts) in leading.iter() {
+            for cmt in cmts {
+                if directive_name(cmt) == "sb-dice-ignore" {
+                    directives.ignored_positions.insert(pos);
+                }
+                if let Some(note) = note_text(cmt) {
+                    let line = cm.lookup_char_pos(pos).line;
+                    directives
+                        .notes
+                        .entry(pos)
+                        .and_modify(|(_, text)| {
+                            text.push(' ');
+                            text.push_str(&note);
+                        })
+                        .or_insert((line, note));
+                }
+                directives.apply(cm, cmt);
+            }
+        }
+        for cmts in trailing.values() {
+            for cmt in cmts {
+                directives.apply(cm, cmt);
+            }
+        }
+
+        directives
+    }
+
+    /// 第 `line` 行中位于 `pos` 的字符串的说明：同一行中在它之前、离它最近的那条说明
+    pub fn note(&self, pos: BytePos, line: usize) -> Option<&str> {
+        let (_, (note_line, note)) = self.notes.range(..=pos).next_back()?;
+        (*note_line == line).then_some(note.as_str())
+    }
+
+    /// 处理文件级与行级指令
+    fn apply(&mut self, cm: &SourceMap, cmt: &Comment) {
+        match directive_name(cmt) {
+            "sb-dice-ignore-file" => self.ignore_file = true,
+            "sb-dice-ignore-next-line" => {
+                let line = cm.lookup_char_pos(cmt.span.hi).line;
+                self.ignored_lines.insert(line + 1);
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! 近似重复的字符串（`dupes` 子命令）：找出原文只有空白、标点或大小写不同的记录，
+//! 在交给译者之前合并映射表中的重复项。
+//!
+//! 比较时只保留字母、数字与文字（含中日韩文字）并转为小写，
+//! 如 `"你好！"`、`" 你好"` 与 `"你好"`，`"Attack"` 与 `"attack."` 分别归为一组。
+
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use crate::diff::natural_cmp;
+use crate::entry_text;
+
+/// 一组近似重复的记录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DupeGroup {
+    /// 组内记录共同的比较形式（见 [`dupe_key`]）
+    pub normalized: String,
+    /// 各记录的 key 与原文，按 key 的自然顺序排列
+    pub entries: Vec<(String, String)>,
+}
+
+/// 比较用的形式：去掉空白、标点与符号，字母转为小写
+pub fn dupe_key(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// 按比较形式给映射表中的记录分组，返回含两条以上记录的组
+///
+/// 原文完全相同的记录也归为一组。只含空白与标点的字符串（比较形式为空）不参与分组。
+/// 各组按第一个 key 的自然顺序排列。
+pub fn find_dupes(map: &Map<String, Value>) -> Vec<DupeGroup> {
+    let mut groups: HashMap<String, Vec<(String, String)>> = HashMap::new();
+    for (key, value) in map {
+        let Some(original) = entry_text(value) else {
+            continue;
+        };
+        let normalized = dupe_key(original);
+        if normalized.is_empty() {
+            continue;
+        }
+        groups
+            .entry(normalized)
+            .or_default()
+            .push((key.clone(), original.to_string()));
+    }
+
+    let mut dupes: Vec<DupeGroup> = groups
+        .into_iter()
+        .filter(|(_, entries)| entries.len() > 1)
+        .map(|(normalized, mut entries)| {
+            entries.sort_by(|a, b| natural_cmp(&a.0, &b.0));
+            DupeGroup {
+                normalized,
+                entries,
+            }
+        })
+        .collect();
+    dupes.sort_by(|a, b| natural_cmp(&a.entries[0].0, &b.entries[0].0));
+    dupes
+}
@@ -0,0 +1,106 @@
+//! 源文件编码（`--encoding`）
+//!
+//! 老的游戏脚本常用 GBK 或 Shift_JIS 保存。读取时解码为 UTF-8 文本处理，写出替换后（或还原后）的代码时
+//! 再编码回原来的编码，文件的编码保持不变；映射表总是 UTF-8。
+//!
+//! 编码名按 WHATWG Encoding 标准的标签识别（`gbk`、`gb18030`、`shift_jis`、`euc-kr`、`big5` ...），
+//! `-` 与 `_` 通用。`auto` 时合法的 UTF-8 按 UTF-8 读取，否则按内容猜测。
+
+use std::fmt;
+use std::str::FromStr;
+
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
+
+/// 源文件编码
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SourceEncoding {
+    /// UTF-8（默认），BOM 原样保留
+    #[default]
+    Utf8,
+    /// 合法的 UTF-8 按 UTF-8 读取，否则按内容猜测
+    Auto,
+    /// 指定的编码
+    Fixed(&'static Encoding),
+}
+
+impl SourceEncoding {
+    /// 解码为文本，返回文本与实际使用的编码（写出时用它编码回去）
+    pub fn decode(self, data: &[u8]) -> Result<(String, &'static Encoding), String> {
+        let encoding = match self {
+            SourceEncoding::Utf8 => UTF_8,
+            SourceEncoding::Fixed(encoding) => encoding,
+            SourceEncoding::Auto if std::str::from_utf8(data).is_ok() => UTF_8,
+            SourceEncoding::Auto => {
+                let mut detector = EncodingDetector::new();
+                detector.feed(data, true);
+                detector.guess(None, true)
+            }
+        };
+        if encoding == UTF_8 {
+            let text = String::from_utf8(data.to_vec())
+                .map_err(|e| format!("不是有效的 UTF-8 文本（{}），可用 --encoding 指定编码", e))?;
+            return Ok((text, UTF_8));
+        }
+        encoding
+            .decode_without_bom_handling_and_without_replacement(data)
+            .map(|text| (text.into_owned(), encoding))
+            .ok_or_else(|| format!("不是有效的 {} 文本", encoding.name()))
+    }
+}
+
+/// 把文本编码为 `encoding`；含有该编码无法表示的字符（如译文中的生僻字）时报错
+pub fn encode_text(text: &str, encoding: &'static Encoding) -> Result<Vec<u8>, String> {
+    if encoding == UTF_8 {
+        return Ok(text.as_bytes().to_vec());
+    }
+    let (bytes, _, had_errors) = encoding.encode(text);
+    if !had_errors {
+        return Ok(bytes.into_owned());
+    }
+    let mut buf = [0; 4];
+    let bad = text
+        .chars()
+        .find(|c| encoding.encode(c.encode_utf8(&mut buf)).2)
+        .unwrap_or(char::REPLACEMENT_CHARACTER);
+    Err(format!(
+        "含有无法用 {} 表示的字符 {:?}（U+{:04X}）",
+        encoding.name(),
+        bad,
+        u32::from(bad)
+    ))
+}
+
+impl FromStr for SourceEncoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => return Ok(SourceEncoding::Auto),
+            "utf-8" | "utf8" => return Ok(SourceEncoding::Utf8),
+            _ => {}
+        }
+        let encoding = Encoding::for_label(s.as_bytes())
+            .or_else(|| Encoding::for_label(s.replace('-', "_").as_bytes()))
+            .ok_or_else(|| format!("未知的编码 {}（如 utf-8 / gbk / shift-jis / auto）", s))?;
+        // UTF-16 等编码按标准只能写出 UTF-8，无法保持文件的编码
+        if encoding.output_encoding() != encoding {
+            return Err(format!("不支持 {} 编码", encoding.name()));
+        }
+        Ok(if encoding == UTF_8 {
+            SourceEncoding::Utf8
+        } else {
+            SourceEncoding::Fixed(encoding)
+        })
+    }
+}
+
+impl fmt::Display for SourceEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceEncoding::Utf8 => f.write_str("utf-8"),
+            SourceEncoding::Auto => f.write_str("auto"),
+            SourceEncoding::Fixed(encoding) => f.write_str(encoding.name()),
+        }
+    }
+}
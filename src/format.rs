@@ -0,0 +1,283 @@
+//! 映射表的输出格式
+//!
+//! 默认输出 JSON 映射表（`restore` / `merge` 使用的格式）；其他格式用于交给现成的翻译工具
+//! 或游戏中的加载器，可以与 JSON 同时输出。
+
+mod binary;
+mod canonical;
+pub(crate) mod csv;
+mod dts;
+mod fluent;
+mod i18next;
+mod po;
+mod sqlite;
+mod xliff;
+
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::{Map, Value};
+
+use crate::meta::{META_KEY, MapMeta};
+use crate::{Entry, Error};
+
+pub use canonical::{JsonStyle, to_canonical_json};
+
+/// 映射表格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapFormat {
+    /// JSON 映射表 `{"0": "原始0", ...}`
+    Json,
+    /// gettext 翻译目录（msgctxt 为 key，msgid 为原始字符串）
+    Po,
+    /// XLIFF 2.0 文档（unit id 为 key，位置与上下文写在 note 中）
+    Xliff,
+    /// 逗号分隔的表格（key、original、file、line、context、translation）
+    Csv,
+    /// 制表符分隔的表格，列与 CSV 相同
+    Tsv,
+    /// Mozilla Fluent 文件（key 转换为合法的消息 id）
+    Fluent,
+    /// i18next 资源文件：按命名空间与 key 分隔符嵌套的 JSON
+    I18next,
+    /// TypeScript 类型声明：全部 key 的联合类型与 const enum，原文写在 JSDoc 中
+    Dts,
+    /// MessagePack：内容与 JSON 映射表相同的二进制编码
+    Msgpack,
+    /// bincode：key -> 原文的扁平映射（`BTreeMap<String, String>`）
+    Bincode,
+    /// SQLite 数据库：strings 表，列与 CSV 相同
+    Sqlite,
+}
+
+/// JSON 映射表的结构
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MapSchema {
+    /// 扁平形式（默认）：没有附加信息的记录就是原始字符串本身
+    #[default]
+    V1,
+    /// 每条记录都是对象，带有出现次数 `count`、来源文件 `files` 与第一次出现的行号 `first_line`
+    V2,
+    /// 原文组成的 JSON 数组 `["原始0", "原始1", ...]`，下标即 key，与 DICE 运行时的字符串表一致；
+    /// 只能用于从 0 开始的十进制索引 key，不能写入附加信息与 `$meta`
+    Array,
+}
+
+impl MapSchema {
+    /// 按该结构生成 key -> 记录的映射表
+    ///
+    /// `source` 为条目所在的源文件（v2 的 `files`）；条目自带 `file` 时以条目为准。
+    pub fn mapping(self, entries: &[Entry], source: Option<&str>) -> Map<String, Value> {
+        entries
+            .iter()
+            .map(|entry| {
+                let value = match self {
+                    MapSchema::V1 => entry.to_value(),
+                    MapSchema::V2 => entry.to_value_v2(source),
+                    // 读取数组映射表时得到的就是 key -> 原文
+                    MapSchema::Array => Value::String(entry.original.clone()),
+                };
+                (entry.key.clone(), value)
+            })
+            .collect()
+    }
+}
+
+impl FromStr for MapSchema {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "v1" | "1" => Ok(MapSchema::V1),
+            "v2" | "2" => Ok(MapSchema::V2),
+            "array" => Ok(MapSchema::Array),
+            other => Err(format!(
+                "未知的映射表结构 {}（可选 v1 / v2 / array）",
+                other
+            )),
+        }
+    }
+}
+
+/// 各输出格式的附加设置
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    /// i18next 的命名空间（最外层的 key），默认 `translation`
+    pub i18next_ns: String,
+    /// i18next 的 key 分隔符，key 按它拆成嵌套的对象，默认 `.`
+    pub key_separator: String,
+    /// JSON 映射表的结构，默认 v1
+    pub schema: MapSchema,
+    /// 写入 JSON 映射表的 `"$meta"` 记录，默认不写
+    pub meta: Option<MapMeta>,
+    /// JSON 文本的写法（JSON 映射表与 i18next 资源文件）
+    pub json: JsonStyle,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            i18next_ns: "translation".to_string(),
+            key_separator: ".".to_string(),
+            schema: MapSchema::V1,
+            meta: None,
+            json: JsonStyle::default(),
+        }
+    }
+}
+
+impl MapFormat {
+    /// 输出文件的扩展名
+    pub fn extension(self) -> &'static str {
+        match self {
+            MapFormat::Json => "json",
+            MapFormat::Po => "po",
+            MapFormat::Xliff => "xlf",
+            MapFormat::Csv => "csv",
+            MapFormat::Tsv => "tsv",
+            MapFormat::Fluent => "ftl",
+            // 与默认的 JSON 映射表区分开，两者可以同时输出
+            MapFormat::I18next => "i18next.json",
+            MapFormat::Dts => "d.ts",
+            MapFormat::Msgpack => "msgpack",
+            MapFormat::Bincode => "bin",
+            MapFormat::Sqlite => "sqlite",
+        }
+    }
+
+    /// 把提取出的条目渲染为该格式的文件内容（除二进制格式外都是 UTF-8 文本）
+    ///
+    /// `source` 为条目所在的源文件（用于位置注释等）；条目自带 `file` 时以条目为准。
+    /// 只有二进制格式的编码会失败（[`Error::MapRender`]）。
+    pub fn render(
+        self,
+        entries: &[Entry],
+        source: Option<&str>,
+        opts: &FormatOptions,
+    ) -> Result<Vec<u8>, Error> {
+        let error = |message| Error::MapRender {
+            format: self,
+            message,
+        };
+        let text = match self {
+            MapFormat::Json => to_canonical_json(&json_mapping(entries, source, opts), opts.json),
+            MapFormat::Po => po::render(entries, source),
+            MapFormat::Xliff => xliff::render(entries, source),
+            MapFormat::Csv => csv::render(entries, source, ','),
+            MapFormat::Tsv => csv::render(entries, source, '\t'),
+            MapFormat::Fluent => fluent::render(entries, source),
+            MapFormat::I18next => i18next::render(entries, opts),
+            MapFormat::Dts => dts::render(entries),
+            MapFormat::Msgpack => {
+                return binary::render_msgpack(&json_mapping(entries, source, opts)).map_err(error);
+            }
+            MapFormat::Bincode => return binary::render_bincode(entries).map_err(error),
+            MapFormat::Sqlite => return sqlite::render(entries, source, opts).map_err(error),
+        };
+        Ok(text.into_bytes())
+    }
+
+    /// 读取该格式的映射表，得到 key -> 文本的映射（可直接用于 [`crate::restore_strings`]）
+    ///
+    /// 只支持 JSON、CSV/TSV 与二进制格式；CSV/TSV 中填写了 translation 的行使用译文，否则使用原文。
+    pub fn parse(self, data: &[u8]) -> Result<Map<String, Value>, String> {
+        match self {
+            MapFormat::Msgpack => return binary::parse_msgpack(data),
+            MapFormat::Bincode => return binary::parse_bincode(data),
+            _ => {}
+        }
+        let text =
+            std::str::from_utf8(data).map_err(|e| format!("不是有效的 UTF-8 文本: {}", e))?;
+        match self {
+            MapFormat::Json => match serde_json::from_str::<Value>(text) {
+                Ok(Value::Object(m)) => Ok(m),
+                Ok(Value::Array(items)) => Ok(array_to_map(items)),
+                Ok(_) => Err("顶层必须是对象或数组".to_string()),
+                Err(e) => Err(e.to_string()),
+            },
+            MapFormat::Csv => csv::parse(text, ','),
+            MapFormat::Tsv => csv::parse(text, '\t'),
+            MapFormat::Po
+            | MapFormat::Xliff
+            | MapFormat::Fluent
+            | MapFormat::I18next
+            | MapFormat::Dts
+            | MapFormat::Msgpack
+            | MapFormat::Bincode
+            | MapFormat::Sqlite => Err(format!("不支持读取 {} 格式的映射表", self)),
+        }
+    }
+}
+
+impl FromStr for MapFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(MapFormat::Json),
+            "po" => Ok(MapFormat::Po),
+            "xliff" | "xlf" => Ok(MapFormat::Xliff),
+            "csv" => Ok(MapFormat::Csv),
+            "tsv" => Ok(MapFormat::Tsv),
+            "fluent" | "ftl" => Ok(MapFormat::Fluent),
+            "i18next" => Ok(MapFormat::I18next),
+            "dts" | "d.ts" => Ok(MapFormat::Dts),
+            "msgpack" | "mp" => Ok(MapFormat::Msgpack),
+            "bincode" | "bin" => Ok(MapFormat::Bincode),
+            "sqlite" | "db" => Ok(MapFormat::Sqlite),
+            other => Err(format!(
+                "未知的映射表格式 {}（可选 json / po / xliff / csv / tsv / fluent / i18next / dts / msgpack / bincode / sqlite）",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for MapFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MapFormat::Json => "json",
+            MapFormat::Po => "po",
+            MapFormat::Xliff => "xliff",
+            MapFormat::Csv => "csv",
+            MapFormat::Tsv => "tsv",
+            MapFormat::Fluent => "fluent",
+            MapFormat::I18next => "i18next",
+            MapFormat::Dts => "dts",
+            MapFormat::Msgpack => "msgpack",
+            MapFormat::Bincode => "bincode",
+            MapFormat::Sqlite => "sqlite",
+        };
+        f.write_str(name)
+    }
+}
+
+/// JSON（以及 MessagePack）映射表的内容：按结构生成的记录，以及 `$meta`
+fn json_mapping(entries: &[Entry], source: Option<&str>, opts: &FormatOptions) -> Value {
+    if opts.schema == MapSchema::Array {
+        // 同一个 key 只写一次，下标与 key 一一对应
+        let mut seen = HashSet::new();
+        return Value::Array(
+            entries
+                .iter()
+                .filter(|entry| seen.insert(entry.key.as_str()))
+                .map(|entry| Value::String(entry.original.clone()))
+                .collect(),
+        );
+    }
+    let mut map = opts.schema.mapping(entries, source);
+    if let Some(meta) = &opts.meta {
+        map.insert(META_KEY.to_string(), meta.to_value());
+    }
+    Value::Object(map)
+}
+
+/// 数组形式的映射表转为 key -> 原文，下标即 key
+fn array_to_map(items: Vec<Value>) -> Map<String, Value> {
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| (i.to_string(), item))
+        .collect()
+}
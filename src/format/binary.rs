@@ -0,0 +1,48 @@
+//! 二进制映射表：MessagePack 与 bincode
+//!
+//! 整个游戏的 JSON 映射表有几十 MB，游戏里的加载器解析起来很慢，二进制格式体积更小、解析更快。
+//!
+//! MessagePack 与 JSON 映射表的内容完全相同（包括对象形式的记录、数组结构与 `$meta`），只是编码不同。
+//! bincode 不是自描述的格式，只写 key -> 原文的扁平映射（`BTreeMap<String, String>`），
+//! 加载器按这个类型直接反序列化；附加信息与 `$meta` 不写入。
+
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Value};
+
+use crate::Entry;
+
+/// 编码为 MessagePack（对象按字段名写出，与 JSON 一一对应）
+pub(super) fn render_msgpack(value: &Value) -> Result<Vec<u8>, String> {
+    rmp_serde::to_vec_named(value).map_err(|e| e.to_string())
+}
+
+/// 读取 MessagePack 映射表
+pub(super) fn parse_msgpack(data: &[u8]) -> Result<Map<String, Value>, String> {
+    match rmp_serde::from_slice::<Value>(data) {
+        Ok(Value::Object(map)) => Ok(map),
+        Ok(Value::Array(items)) => Ok(super::array_to_map(items)),
+        Ok(_) => Err("顶层必须是映射或数组".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// 编码为 bincode：key -> 原文
+pub(super) fn render_bincode(entries: &[Entry]) -> Result<Vec<u8>, String> {
+    let map: BTreeMap<&str, &str> = entries
+        .iter()
+        .map(|e| (e.key.as_str(), e.original.as_str()))
+        .collect();
+    bincode::serde::encode_to_vec(&map, bincode::config::standard()).map_err(|e| e.to_string())
+}
+
+/// 读取 bincode 映射表，得到 key -> 文本
+pub(super) fn parse_bincode(data: &[u8]) -> Result<Map<String, Value>, String> {
+    let (map, _): (BTreeMap<String, String>, usize) =
+        bincode::serde::decode_from_slice(data, bincode::config::standard())
+            .map_err(|e| e.to_string())?;
+    Ok(map
+        .into_iter()
+        .map(|(key, text)| (key, Value::String(text)))
+        .collect())
+}
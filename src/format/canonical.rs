@@ -0,0 +1,105 @@
+//! 确定性的 JSON 输出
+//!
+//! 同样的映射表在任何平台、任何一次运行中都输出完全相同的字节，`_s.json` 的 git diff 里只有真正的变化：
+//! 对象的 key 按自然顺序排列（`"2"` 在 `"10"` 前面，见 [`natural_cmp`]），固定两个空格缩进，
+//! 换行总是 `\n`；字符串只转义 JSON 必须转义的字符（控制字符写成小写十六进制的 `\u001f`），
+//! 中文等非 ASCII 字符总是原样写出 UTF-8，只有设置了 [`JsonStyle::escape_non_ascii`]（`--ascii-map`）时
+//! 才全部写成 `\uXXXX`；末尾可选一个换行。
+//!
+//! 不设置这两个选项时，除 key 的顺序外与 `serde_json` 的格式化输出相同。
+
+use std::fmt::Write;
+
+use serde_json::Value;
+
+use crate::diff::natural_cmp;
+
+/// 缩进
+const INDENT: &str = "  ";
+
+/// JSON 文本的写法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JsonStyle {
+    /// 非 ASCII 字符写成 `\uXXXX`（BMP 以外的字符写成代理对），只能处理 ASCII 的工具也能读取
+    pub escape_non_ascii: bool,
+    /// 末尾加一个换行
+    pub trailing_newline: bool,
+}
+
+/// 按 `style` 输出格式化后的 JSON 文本
+pub fn to_canonical_json(value: &Value, style: JsonStyle) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value, 0, style);
+    if style.trailing_newline {
+        out.push('\n');
+    }
+    out
+}
+
+fn write_value(out: &mut String, value: &Value, depth: usize, style: JsonStyle) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => out.push_str(&n.to_string()),
+        Value::String(s) => write_string(out, s, style),
+        Value::Array(items) if items.is_empty() => out.push_str("[]"),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                out.push_str(if i == 0 { "\n" } else { ",\n" });
+                push_indent(out, depth + 1);
+                write_value(out, item, depth + 1, style);
+            }
+            out.push('\n');
+            push_indent(out, depth);
+            out.push(']');
+        }
+        Value::Object(map) if map.is_empty() => out.push_str("{}"),
+        Value::Object(map) => {
+            let mut fields: Vec<(&String, &Value)> = map.iter().collect();
+            fields.sort_by(|a, b| natural_cmp(a.0, b.0));
+            out.push('{');
+            for (i, (key, item)) in fields.into_iter().enumerate() {
+                out.push_str(if i == 0 { "\n" } else { ",\n" });
+                push_indent(out, depth + 1);
+                write_string(out, key, style);
+                out.push_str(": ");
+                write_value(out, item, depth + 1, style);
+            }
+            out.push('\n');
+            push_indent(out, depth);
+            out.push('}');
+        }
+    }
+}
+
+fn push_indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push_str(INDENT);
+    }
+}
+
+/// 写出带引号的字符串
+fn write_string(out: &mut String, s: &str, style: JsonStyle) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            c if c < ' ' || (style.escape_non_ascii && !c.is_ascii()) => {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    // 写入 String 不会失败
+                    let _ = write!(out, "\\u{:04x}", unit);
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
@@ -0,0 +1,132 @@
+//! CSV / TSV 输出与读取
+//!
+//! 列依次为 key、original、file、line、context、translation（留空给译者填写）。
+//! 引号规则按 RFC 4180：含分隔符、引号或换行的字段用双引号包起来，内部的引号写两遍。
+//! CSV 开头写 UTF-8 BOM，Excel 才能正确识别中文。
+//!
+//! 读取时按表头找列，填写了 translation 的行使用译文，否则使用原文，
+//! 结果是 `restore` 可以直接使用的映射表。
+
+use serde_json::{Map, Value};
+
+use crate::Entry;
+
+/// 表头
+const HEADER: [&str; 6] = ["key", "original", "file", "line", "context", "translation"];
+
+/// 按需给字段加引号
+fn quote(field: &str, delimiter: char) -> String {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// 写出一行
+pub(crate) fn write_row(out: &mut String, fields: &[&str], delimiter: char) {
+    let row: Vec<String> = fields.iter().map(|f| quote(f, delimiter)).collect();
+    out.push_str(&row.join(&delimiter.to_string()));
+    out.push_str("\r\n");
+}
+
+/// 渲染表格
+pub(super) fn render(entries: &[Entry], source: Option<&str>, delimiter: char) -> String {
+    let mut out = String::new();
+    if delimiter == ',' {
+        out.push('\u{feff}');
+    }
+    write_row(&mut out, &HEADER, delimiter);
+
+    for entry in entries {
+        let file = entry.file.as_deref().or(source).unwrap_or_default();
+        let lines: Vec<String> = entry.locations.iter().map(|l| l.line.to_string()).collect();
+        let context = entry.context.as_deref().unwrap_or_default();
+        write_row(
+            &mut out,
+            &[
+                &entry.key,
+                &entry.original,
+                file,
+                &lines.join(" "),
+                context,
+                "",
+            ],
+            delimiter,
+        );
+    }
+    out
+}
+
+/// 拆分为记录（每条记录是若干字段），支持引号内的分隔符、换行与 `""` 转义
+fn parse_records(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text
+        .strip_prefix('\u{feff}')
+        .unwrap_or(text)
+        .chars()
+        .peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => in_quotes = true,
+            c if c == delimiter => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    // 跳过空行
+    records.retain(|r| r.iter().any(|f| !f.is_empty()));
+    records
+}
+
+/// 读取填写好的表格，生成 key -> 译文（未填写时为原文）的映射表
+pub(super) fn parse(text: &str, delimiter: char) -> Result<Map<String, Value>, String> {
+    let mut records = parse_records(text, delimiter).into_iter();
+    let header = records.next().ok_or("表格为空，缺少表头")?;
+    let column = |name: &str| header.iter().position(|h| h.trim() == name);
+
+    let key_col = column("key").ok_or("表头中缺少 key 列")?;
+    let original_col = column("original");
+    let translation_col = column("translation");
+    if original_col.is_none() && translation_col.is_none() {
+        return Err("表头中缺少 original 或 translation 列".to_string());
+    }
+
+    let mut map = Map::new();
+    for (i, record) in records.enumerate() {
+        let get = |col: Option<usize>| col.and_then(|c| record.get(c)).map(String::as_str);
+        let Some(key) = get(Some(key_col)).filter(|k| !k.is_empty()) else {
+            return Err(format!("第 {} 条记录缺少 key", i + 1));
+        };
+        let text = get(translation_col)
+            .filter(|t| !t.is_empty())
+            .or(get(original_col))
+            .unwrap_or_default();
+        map.insert(key.to_string(), Value::String(text.to_string()));
+    }
+    Ok(map)
+}
@@ -0,0 +1,86 @@
+//! TypeScript 类型声明（`.d.ts`）输出
+//!
+//! 声明全部 key 的字符串字面量联合类型 `StringKey` 与同名成员的 `const enum StringKeys`，
+//! 原文写在 JSDoc 注释中，下游按 key 查表的代码可以得到补全与编译期检查。
+//! 枚举成员名必须是标识符，key 会被转换为 `K0`、`battle_12` 这样的名字（重复时追加序号）。
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use serde_json::Value;
+
+use crate::Entry;
+
+/// 把 key 转换为合法的枚举成员名
+fn member_name(key: &str) -> String {
+    let mut name: String = key
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == '$' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !name.starts_with(|c: char| c.is_alphabetic() || c == '_' || c == '$') {
+        name.insert(0, 'K');
+    }
+    name
+}
+
+/// 写出 JSDoc 注释，多行原文逐行写出
+fn write_doc(out: &mut String, text: &str, indent: &str) {
+    // 避免原文中的 `*/` 提前结束注释
+    let text = text.replace("*/", "*\\/");
+    let mut lines = text.lines();
+    match (lines.next(), lines.next()) {
+        (Some(line), None) => {
+            let _ = writeln!(out, "{}/** {} */", indent, line);
+        }
+        _ => {
+            let _ = writeln!(out, "{}/**", indent);
+            for line in text.lines() {
+                let _ = writeln!(out, "{} * {}", indent, line);
+            }
+            let _ = writeln!(out, "{} */", indent);
+        }
+    }
+}
+
+/// 渲染类型声明
+pub(super) fn render(entries: &[Entry]) -> String {
+    let mut out = String::from("// 由 sb_dice 生成，请勿手动修改\n\n");
+
+    out.push_str("/** 映射表中的全部 key */\n");
+    if entries.is_empty() {
+        out.push_str("export type StringKey = never;\n");
+    } else {
+        out.push_str("export type StringKey =\n");
+        for entry in entries {
+            write_doc(&mut out, &entry.original, "  ");
+            let _ = writeln!(out, "  | {}", Value::String(entry.key.clone()));
+        }
+        out.pop();
+        out.push_str(";\n");
+    }
+
+    out.push_str("\n/** 映射表中的全部 key（枚举形式，成员值即 key） */\n");
+    out.push_str("export declare const enum StringKeys {\n");
+    let mut used = HashSet::new();
+    for entry in entries {
+        // 重复的成员名追加序号
+        let base = member_name(&entry.key);
+        let mut name = base.clone();
+        let mut n = 2;
+        while !used.insert(name.clone()) {
+            name = format!("{}_{}", base, n);
+            n += 1;
+        }
+
+        write_doc(&mut out, &entry.original, "  ");
+        let _ = writeln!(out, "  {} = {},", name, Value::String(entry.key.clone()));
+    }
+    out.push_str("}\n");
+    out
+}
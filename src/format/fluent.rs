@@ -0,0 +1,106 @@
+//! Mozilla Fluent（`.ftl`）输出
+//!
+//! Fluent 的消息 id 只能由字母、数字、`-` 与 `_` 组成且以字母开头，
+//! 所以 key 会被转换为 `s-0`、`battle-12` 这样的 id（重复时追加序号），原始 key 写在注释里。
+//! 原文中的 `{` `}` 会被转义为 placeable；多行、首尾带空白或为空的字符串整个写成
+//! 字符串字面量 placeable（`{"..."}`），避免被 Fluent 的缩进与空白规则改变。
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::Entry;
+
+/// 把 key 转换为合法的 Fluent 消息 id
+fn slugify(key: &str) -> String {
+    let mut id: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    if !id.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        id.insert_str(0, "s-");
+    }
+    id
+}
+
+/// 是否可以直接写成普通文本
+fn is_plain(s: &str) -> bool {
+    !s.is_empty() && !s.contains(['\n', '\r']) && s.trim() == s
+}
+
+/// 转义为 Fluent 文本：`{` 与 `}` 写成 placeable
+fn escape_text(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '{' => out.push_str("{\"{\"}"),
+            '}' => out.push_str("{\"}\"}"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// 转义为 Fluent 字符串字面量的内容（不含两侧引号）
+fn escape_literal(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04X}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// 渲染 FTL 文件
+pub(super) fn render(entries: &[Entry], source: Option<&str>) -> String {
+    let mut out = String::from("### 由 sb_dice 生成\n");
+    let mut used = HashSet::new();
+
+    for entry in entries {
+        out.push('\n');
+
+        // 重复的 id 追加序号
+        let base = slugify(&entry.key);
+        let mut id = base.clone();
+        let mut n = 2;
+        while !used.insert(id.clone()) {
+            id = format!("{}-{}", base, n);
+            n += 1;
+        }
+
+        let _ = writeln!(out, "# key: {}", entry.key);
+        if let Some(file) = entry.file.as_deref().or(source) {
+            let lines: Vec<String> = entry.locations.iter().map(|l| l.line.to_string()).collect();
+            if lines.is_empty() {
+                let _ = writeln!(out, "# {}", file);
+            } else {
+                let _ = writeln!(out, "# {}:{}", file, lines.join(","));
+            }
+        }
+        if let Some(context) = &entry.context {
+            let _ = writeln!(out, "# {}", context);
+        }
+        if let Some(note) = &entry.note {
+            let _ = writeln!(out, "# {}", note);
+        }
+
+        if is_plain(&entry.original) {
+            let _ = writeln!(out, "{} = {}", id, escape_text(&entry.original));
+        } else {
+            let _ = writeln!(out, "{} = {{\"{}\"}}", id, escape_literal(&entry.original));
+        }
+    }
+
+    out
+}
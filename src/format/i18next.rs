@@ -0,0 +1,50 @@
+//! i18next 资源文件输出
+//!
+//! 结构为 `{ "<命名空间>": { ... } }`，key 按分隔符拆成嵌套的对象，
+//! 例如命名空间为 `translation`、分隔符为 `.` 时，`battle.12` 写在
+//! `{"translation": {"battle": {"12": "原始"}}}` 中。
+//! 某一层已经是字符串（如同时存在 `a` 与 `a.b`）时，剩余部分不再拆分，直接作为这一层的 key。
+
+use serde_json::{Map, Value};
+
+use super::{FormatOptions, to_canonical_json};
+use crate::Entry;
+
+/// 把值按 key 的各段插入嵌套对象
+fn insert_nested(map: &mut Map<String, Value>, parts: &[&str], sep: &str, value: Value) {
+    let [first, rest @ ..] = parts else {
+        return;
+    };
+    if rest.is_empty() {
+        map.insert(first.to_string(), value);
+        return;
+    }
+    let child = map
+        .entry(first.to_string())
+        .or_insert_with(|| Value::Object(Map::new()));
+    match child {
+        Value::Object(child) => insert_nested(child, rest, sep, value),
+        // 与已有的字符串冲突：剩余部分整体作为 key
+        _ => {
+            map.insert(parts.join(sep), value);
+        }
+    }
+}
+
+/// 渲染 i18next 资源文件
+pub(super) fn render(entries: &[Entry], opts: &FormatOptions) -> String {
+    let mut resources = Map::new();
+    for entry in entries {
+        let value = Value::String(entry.original.clone());
+        if opts.key_separator.is_empty() {
+            resources.insert(entry.key.clone(), value);
+        } else {
+            let parts: Vec<&str> = entry.key.split(opts.key_separator.as_str()).collect();
+            insert_nested(&mut resources, &parts, &opts.key_separator, value);
+        }
+    }
+
+    let mut root = Map::new();
+    root.insert(opts.i18next_ns.clone(), Value::Object(resources));
+    to_canonical_json(&Value::Object(root), opts.json)
+}
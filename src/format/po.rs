@@ -0,0 +1,81 @@
+//! gettext `.po` 输出
+//!
+//! 每个条目对应一条消息：`msgctxt` 为 key（保证相同原文的不同出现也能分别翻译），
+//! `msgid` 为原始字符串，`msgstr` 留空；位置写在 `#:` 注释中，上下文写在 `#.` 注释中。
+//! Poedit、Weblate 等工具可以直接打开。
+
+use std::fmt::Write;
+
+use crate::Entry;
+
+/// 转义为 PO 字符串字面量的内容（不含两侧引号）
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// 写出一个关键字及其字符串；多行字符串按 gettext 的习惯逐行拆开
+fn write_field(out: &mut String, keyword: &str, value: &str) {
+    let lines: Vec<&str> = value.split_inclusive('\n').collect();
+    if lines.len() <= 1 {
+        let _ = writeln!(out, "{} \"{}\"", keyword, escape(value));
+        return;
+    }
+    let _ = writeln!(out, "{} \"\"", keyword);
+    for line in lines {
+        let _ = writeln!(out, "\"{}\"", escape(line));
+    }
+}
+
+/// 渲染 PO 文件
+pub(super) fn render(entries: &[Entry], source: Option<&str>) -> String {
+    let mut out = String::new();
+
+    // 文件头
+    out.push_str("msgid \"\"\n");
+    out.push_str("msgstr \"\"\n");
+    out.push_str("\"Content-Type: text/plain; charset=UTF-8\\n\"\n");
+    out.push_str("\"Content-Transfer-Encoding: 8bit\\n\"\n");
+    out.push_str("\"X-Generator: sb_dice\\n\"\n");
+
+    for entry in entries {
+        out.push('\n');
+
+        if let Some(context) = &entry.context {
+            let _ = writeln!(out, "#. {}", context);
+        }
+        if let Some(note) = &entry.note {
+            let _ = writeln!(out, "#. {}", note);
+        }
+
+        let file = entry.file.as_deref().or(source);
+        if let Some(file) = file {
+            if entry.locations.is_empty() {
+                let _ = writeln!(out, "#: {}", file);
+            } else {
+                let refs: Vec<String> = entry
+                    .locations
+                    .iter()
+                    .map(|l| format!("{}:{}", file, l.line))
+                    .collect();
+                let _ = writeln!(out, "#: {}", refs.join(" "));
+            }
+        }
+
+        write_field(&mut out, "msgctxt", &entry.key);
+        write_field(&mut out, "msgid", &entry.original);
+        out.push_str("msgstr \"\"\n");
+    }
+
+    out
+}
@@ -0,0 +1,75 @@
+//! SQLite 映射表
+//!
+//! 每个字符串一行，写入 `strings` 表，列与 CSV 相同：key、original、file、line、context 与留空的 translation，
+//! 方便直接用 SQL 查询、与其他版本的数据库联表比较，或交给翻译看板读取（表结构见 [`SCHEMA`]）。
+//!
+//! `line` 为第一次出现的行号；同一个 key 出现多次时（hash 模式下的相同字符串）只写第一次。
+//! 写 `$meta` 时另有一张 `meta` 表，`name` 为 `$meta`，`value` 为 JSON 文本。
+//! 数据库先在内存中建好，再整体序列化为文件内容。
+
+use rusqlite::{Connection, DatabaseName, params};
+
+use super::FormatOptions;
+use crate::Entry;
+use crate::meta::META_KEY;
+
+/// 建表语句
+const SCHEMA: &str = "
+CREATE TABLE strings (
+    key         TEXT PRIMARY KEY,
+    original    TEXT NOT NULL,
+    file        TEXT,
+    line        INTEGER NOT NULL,
+    context     TEXT,
+    translation TEXT
+);
+CREATE INDEX strings_file ON strings (file);
+";
+
+/// 渲染数据库文件的内容
+///
+/// 内存数据库只有 SQLite 本身出错（如内存不足）时才会失败。
+pub(super) fn render(
+    entries: &[Entry],
+    source: Option<&str>,
+    opts: &FormatOptions,
+) -> Result<Vec<u8>, String> {
+    build(entries, source, opts).map_err(|e| e.to_string())
+}
+
+/// 在内存中建好数据库并序列化
+fn build(
+    entries: &[Entry],
+    source: Option<&str>,
+    opts: &FormatOptions,
+) -> rusqlite::Result<Vec<u8>> {
+    let mut conn = Connection::open_in_memory()?;
+    conn.execute_batch(SCHEMA)?;
+
+    let tx = conn.transaction()?;
+    {
+        let mut insert = tx.prepare(
+            "INSERT OR IGNORE INTO strings (key, original, file, line, context) VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for entry in entries {
+            insert.execute(params![
+                entry.key,
+                entry.original,
+                entry.file.as_deref().or(source),
+                entry.first_line as i64,
+                entry.context,
+            ])?;
+        }
+    }
+    if let Some(meta) = &opts.meta {
+        tx.execute_batch("CREATE TABLE meta (name TEXT PRIMARY KEY, value TEXT NOT NULL);")?;
+        tx.execute(
+            "INSERT INTO meta (name, value) VALUES (?1, ?2)",
+            params![META_KEY, meta.to_value().to_string()],
+        )?;
+    }
+    tx.commit()?;
+
+    let data = conn.serialize(DatabaseName::Main)?;
+    Ok(data.to_vec())
+}
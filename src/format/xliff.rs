@@ -0,0 +1,110 @@
+//! XLIFF 2.0 输出
+//!
+//! 每个源文件对应一个 `<file>`，每个条目对应一个 `<unit>`（id 为 key），
+//! 原始字符串放在 `<segment state="initial"><source>` 中；位置与上下文写成 `<note>`。
+//! 合并映射表中的条目按各自的 `file` 分组。
+
+use std::fmt::Write;
+
+use crate::Entry;
+
+/// 源语言（DICE 的原文是简体中文）
+const SOURCE_LANG: &str = "zh-CN";
+
+/// 转义 XML 文本与属性值
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// 写出一个 `<unit>`
+fn write_unit(out: &mut String, entry: &Entry, file: Option<&str>) {
+    let _ = writeln!(out, "    <unit id=\"{}\">", escape(&entry.key));
+
+    let mut notes = Vec::new();
+    if let Some(file) = file {
+        if entry.locations.is_empty() {
+            notes.push(("location", file.to_string()));
+        }
+        for l in &entry.locations {
+            notes.push(("location", format!("{}:{}:{}", file, l.line, l.column)));
+        }
+    }
+    if let Some(context) = &entry.context {
+        notes.push(("context", context.clone()));
+    }
+    if let Some(note) = &entry.note {
+        notes.push(("description", note.clone()));
+    }
+    if !notes.is_empty() {
+        out.push_str("      <notes>\n");
+        for (category, text) in notes {
+            let _ = writeln!(
+                out,
+                "        <note category=\"{}\">{}</note>",
+                category,
+                escape(&text)
+            );
+        }
+        out.push_str("      </notes>\n");
+    }
+
+    out.push_str("      <segment state=\"initial\">\n");
+    let _ = writeln!(out, "        <source>{}</source>", escape(&entry.original));
+    out.push_str("      </segment>\n");
+    out.push_str("    </unit>\n");
+}
+
+/// 渲染 XLIFF 文档
+pub(super) fn render(entries: &[Entry], source: Option<&str>) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        out,
+        "<xliff xmlns=\"urn:oasis:names:tc:xliff:document:2.0\" version=\"2.0\" srcLang=\"{}\">",
+        SOURCE_LANG
+    );
+
+    // 按来源文件分组，保持条目原有顺序
+    let mut groups: Vec<(Option<&str>, Vec<&Entry>)> = Vec::new();
+    for entry in entries {
+        let file = entry.file.as_deref().or(source);
+        match groups.iter_mut().find(|(f, _)| *f == file) {
+            Some((_, group)) => group.push(entry),
+            None => groups.push((file, vec![entry])),
+        }
+    }
+
+    for (i, (file, group)) in groups.iter().enumerate() {
+        match file {
+            Some(file) => {
+                let _ = writeln!(
+                    out,
+                    "  <file id=\"f{}\" original=\"{}\">",
+                    i + 1,
+                    escape(file)
+                );
+            }
+            None => {
+                let _ = writeln!(out, "  <file id=\"f{}\">", i + 1);
+            }
+        }
+        for entry in group {
+            write_unit(&mut out, entry, *file);
+        }
+        out.push_str("  </file>\n");
+    }
+
+    out.push_str("</xliff>\n");
+    out
+}
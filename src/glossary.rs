@@ -0,0 +1,136 @@
+//! 术语表（`glossary` 子命令）：统计映射表原文中反复出现的中日韩词语，按出现次数导出 CSV，
+//! 翻译开始之前先统一术语的译法。
+//!
+//! 不依赖词典，按字切分：每段连续的中日韩文字取长度在 `min_len..=max_len` 之间的所有子串计数，
+//! 保留出现次数不少于 `min_count` 的；每次出现都在同一个更长的词里的子串不单独列出
+//! （如"魔法师"与"魔法"都出现 5 次时只保留"魔法师"）。
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::{Map, Value};
+
+use crate::format::csv::write_row;
+use crate::text::is_cjk_char;
+use crate::{entry_text, natural_cmp};
+
+/// 术语表的表头
+const HEADER: [&str; 4] = ["term", "count", "example", "translation"];
+
+/// 术语的筛选条件
+#[derive(Debug, Clone, Copy)]
+pub struct GlossaryOptions {
+    /// 至少出现的次数
+    pub min_count: usize,
+    /// 最短的字数
+    pub min_len: usize,
+    /// 最长的字数
+    pub max_len: usize,
+}
+
+impl Default for GlossaryOptions {
+    fn default() -> Self {
+        Self {
+            min_count: 2,
+            min_len: 2,
+            max_len: 6,
+        }
+    }
+}
+
+/// 术语表中的一条
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Term {
+    /// 词语
+    pub term: String,
+    /// 出现次数（dedupe 映射表中按记录的出现次数计）
+    pub count: usize,
+    /// 含有该词的最短的原文，给译者参考用法
+    pub example: String,
+}
+
+/// 统计一组映射表中反复出现的词语，按出现次数从多到少排列（次数相同时长的在前）
+pub fn build_glossary<'a>(
+    maps: impl IntoIterator<Item = &'a Map<String, Value>>,
+    opts: &GlossaryOptions,
+) -> Vec<Term> {
+    let min_len = opts.min_len.max(1);
+    let max_len = opts.max_len.max(min_len);
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut examples: HashMap<String, &str> = HashMap::new();
+
+    for map in maps {
+        let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+        entries.sort_by(|a, b| natural_cmp(a.0, b.0));
+        for (_, value) in entries {
+            let Some(original) = entry_text(value) else {
+                continue;
+            };
+            let times = value
+                .get("count")
+                .and_then(Value::as_u64)
+                .map_or(1, |c| c as usize);
+            // 多统计长 max_len + 1 的子串，用来判断 max_len 长的词是否总是出现在更长的词里
+            for run in original.split(|c: char| !is_cjk_char(c)) {
+                let chars: Vec<char> = run.chars().collect();
+                for len in min_len..=(max_len + 1).min(chars.len()) {
+                    for gram in chars.windows(len) {
+                        let gram: String = gram.iter().collect();
+                        *counts.entry(gram.clone()).or_default() += times;
+                        let example = examples.entry(gram).or_insert(original);
+                        if original.chars().count() < example.chars().count() {
+                            *example = original;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // 长一个字的词出现次数与子串相同，说明子串每次都出现在这个词里
+    let mut covered = HashSet::new();
+    for (gram, &count) in &counts {
+        let chars: Vec<char> = gram.chars().collect();
+        if chars.len() <= min_len {
+            continue;
+        }
+        for part in [&chars[1..], &chars[..chars.len() - 1]] {
+            let part: String = part.iter().collect();
+            if counts.get(&part) == Some(&count) {
+                covered.insert(part);
+            }
+        }
+    }
+
+    let mut terms: Vec<Term> = counts
+        .iter()
+        .filter(|&(gram, &count)| {
+            count >= opts.min_count && gram.chars().count() <= max_len && !covered.contains(gram)
+        })
+        .map(|(gram, &count)| Term {
+            term: gram.clone(),
+            count,
+            example: examples[gram].to_string(),
+        })
+        .collect();
+    terms.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| b.term.chars().count().cmp(&a.term.chars().count()))
+            .then_with(|| a.term.cmp(&b.term))
+    });
+    terms
+}
+
+/// 写成 CSV：列依次为 term、count、example、translation（留空给译者填写），开头带 UTF-8 BOM
+pub fn render_glossary(terms: &[Term]) -> String {
+    let mut out = String::from('\u{feff}');
+    write_row(&mut out, &HEADER, ',');
+    for term in terms {
+        write_row(
+            &mut out,
+            &[&term.term, &term.count.to_string(), &term.example, ""],
+            ',',
+        );
+    }
+    out
+}
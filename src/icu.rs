@@ -0,0 +1,291 @@
+//! ICU MessageFormat 语法检查（`--validate icu`）
+//!
+//! 只检查语法，不格式化消息：括号是否配对、参数名与类型是否合法，`plural` / `select` 的分支是否完整
+//! （分支名合法、不重复、带有 `other`）。引号规则与 ICU 4.8 之后的默认规则一致：`''` 是一个单引号，
+//! `'` 后面紧跟 `{`、`}`（复数分支中还有 `#`）时开始一段引用的原文，到下一个 `'` 为止，其余的 `'` 就是字符本身。
+
+use std::collections::HashSet;
+use std::fmt;
+
+/// 复数分支可用的关键字
+const PLURAL_KEYWORDS: [&str; 6] = ["zero", "one", "two", "few", "many", "other"];
+
+/// 简单参数可用的类型
+const SIMPLE_TYPES: [&str; 6] = ["number", "date", "time", "spellout", "ordinal", "duration"];
+
+/// 语法错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IcuError {
+    /// 出错的位置（按字符计，从 1 开始）
+    pub column: usize,
+    /// 错误说明
+    pub message: String,
+}
+
+impl fmt::Display for IcuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "第 {} 个字符：{}", self.column, self.message)
+    }
+}
+
+impl std::error::Error for IcuError {}
+
+/// 按 ICU MessageFormat 的语法检查字符串
+pub fn validate_icu(s: &str) -> Result<(), IcuError> {
+    let mut parser = Parser {
+        chars: s.chars().collect(),
+        pos: 0,
+    };
+    parser.message(0, false)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn error_at(&self, pos: usize, message: impl Into<String>) -> IcuError {
+        IcuError {
+            column: pos + 1,
+            message: message.into(),
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// 连续的字母、数字与 `_`
+    fn identifier(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// 消息文本，直到结尾或（嵌套时）配对的 `}` 为止；`}` 留给调用方
+    fn message(&mut self, depth: usize, in_plural: bool) -> Result<(), IcuError> {
+        loop {
+            match self.peek() {
+                None if depth == 0 => return Ok(()),
+                None => return Err(self.error_at(self.pos, "分支没有闭合，缺少 }")),
+                Some('}') if depth == 0 => return Err(self.error_at(self.pos, "多余的 }")),
+                Some('}') => return Ok(()),
+                Some('{') => self.argument()?,
+                Some('\'') => self.quote(in_plural),
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    /// `'` 开头的引用：`''` 或到下一个 `'` 为止的原文；不构成引用时就是一个单引号
+    fn quote(&mut self, in_plural: bool) {
+        match self.chars.get(self.pos + 1) {
+            Some('\'') => self.pos += 2,
+            Some('{' | '}') => self.skip_quoted(),
+            Some('#') if in_plural => self.skip_quoted(),
+            _ => self.pos += 1,
+        }
+    }
+
+    /// 跳过引用的原文（没有结束的 `'` 时到消息结尾为止）
+    fn skip_quoted(&mut self) {
+        self.pos += 1;
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            if c == '\'' {
+                return;
+            }
+        }
+    }
+
+    /// `{name}`、`{name, type[, style]}` 或 `{name, plural|select|selectordinal, ...}`
+    fn argument(&mut self) -> Result<(), IcuError> {
+        let start = self.pos;
+        self.pos += 1;
+        self.skip_ws();
+        if self.peek().is_none() {
+            return Err(self.error_at(start, "参数没有闭合，缺少 }"));
+        }
+        self.argument_name()?;
+        self.skip_ws();
+        match self.peek() {
+            Some('}') => {
+                self.pos += 1;
+                return Ok(());
+            }
+            Some(',') => self.pos += 1,
+            None => return Err(self.error_at(start, "参数没有闭合，缺少 }")),
+            Some(_) => return Err(self.error_at(self.pos, "参数名后面应为 , 或 }")),
+        }
+        self.skip_ws();
+        let type_pos = self.pos;
+        let kind = self.identifier();
+        self.skip_ws();
+        match kind.as_str() {
+            "" => Err(self.error_at(type_pos, "缺少参数类型")),
+            "plural" | "selectordinal" | "select" => {
+                if self.peek() != Some(',') {
+                    return Err(self.error_at(self.pos, format!("{} 后面缺少 ,", kind)));
+                }
+                self.pos += 1;
+                self.branches(start, &kind)
+            }
+            "choice" => {
+                if self.peek() != Some(',') {
+                    return Err(self.error_at(self.pos, "choice 后面缺少 ,"));
+                }
+                self.pos += 1;
+                self.style(start)
+            }
+            kind if SIMPLE_TYPES.contains(&kind) => match self.peek() {
+                Some('}') => {
+                    self.pos += 1;
+                    Ok(())
+                }
+                Some(',') => {
+                    self.pos += 1;
+                    self.style(start)
+                }
+                None => Err(self.error_at(start, "参数没有闭合，缺少 }")),
+                Some(_) => Err(self.error_at(self.pos, "参数类型后面应为 , 或 }")),
+            },
+            kind => Err(self.error_at(
+                type_pos,
+                format!(
+                    "未知的参数类型 {}（可用 number、date、time、spellout、ordinal、duration、plural、select、selectordinal、choice）",
+                    kind
+                ),
+            )),
+        }
+    }
+
+    /// 参数名：非负整数（不能以 0 开头）或标识符
+    fn argument_name(&mut self) -> Result<(), IcuError> {
+        let start = self.pos;
+        let name = self.identifier();
+        if name.is_empty() {
+            return Err(self.error_at(start, "缺少参数名"));
+        }
+        if name.starts_with(|c: char| c.is_ascii_digit()) {
+            if !name.chars().all(|c| c.is_ascii_digit()) {
+                return Err(self.error_at(start, format!("无效的参数名 {}", name)));
+            }
+            if name.len() > 1 && name.starts_with('0') {
+                return Err(self.error_at(start, format!("参数编号 {} 不能以 0 开头", name)));
+            }
+        }
+        Ok(())
+    }
+
+    /// 简单参数与 choice 的样式：到配对的 `}` 为止，并消耗这个 `}`
+    fn style(&mut self, start: usize) -> Result<(), IcuError> {
+        let mut depth = 0;
+        loop {
+            match self.peek() {
+                None => return Err(self.error_at(start, "参数没有闭合，缺少 }")),
+                Some('}') if depth == 0 => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                Some('}') => depth -= 1,
+                Some('{') => depth += 1,
+                Some('\'') => {
+                    if self.chars.get(self.pos + 1) == Some(&'\'') {
+                        self.pos += 1;
+                    } else {
+                        self.skip_quoted();
+                        continue;
+                    }
+                }
+                Some(_) => {}
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// `plural` / `selectordinal` / `select` 的分支，直到参数的 `}`（并消耗它）
+    fn branches(&mut self, start: usize, kind: &str) -> Result<(), IcuError> {
+        let plural = kind != "select";
+        self.skip_ws();
+        if plural && self.chars[self.pos..].starts_with(&['o', 'f', 'f', 's', 'e', 't', ':']) {
+            self.pos += "offset:".len();
+            self.skip_ws();
+            let number_pos = self.pos;
+            if self.identifier().parse::<u64>().is_err() {
+                return Err(self.error_at(number_pos, "offset: 后面应为非负整数"));
+            }
+        }
+
+        let mut seen = HashSet::new();
+        loop {
+            self.skip_ws();
+            let selector_pos = self.pos;
+            let selector = match self.peek() {
+                None => return Err(self.error_at(start, "参数没有闭合，缺少 }")),
+                Some('}') => {
+                    if seen.is_empty() {
+                        return Err(self.error_at(start, format!("{} 中没有任何分支", kind)));
+                    }
+                    if !seen.contains("other") {
+                        return Err(self.error_at(start, format!("{} 缺少 other 分支", kind)));
+                    }
+                    self.pos += 1;
+                    return Ok(());
+                }
+                Some('=') if plural => {
+                    self.pos += 1;
+                    let value = self.number();
+                    if value.parse::<f64>().is_err() {
+                        return Err(self.error_at(selector_pos, "= 后面应为数字"));
+                    }
+                    format!("={}", value)
+                }
+                Some(_) => self.identifier(),
+            };
+            if selector.is_empty() {
+                return Err(self.error_at(selector_pos, "应为分支名或 }"));
+            }
+            if plural && !selector.starts_with('=') && !PLURAL_KEYWORDS.contains(&selector.as_str())
+            {
+                return Err(self.error_at(
+                    selector_pos,
+                    format!(
+                        "无效的 {} 分支 {}（可用 zero、one、two、few、many、other 或 =N）",
+                        kind, selector
+                    ),
+                ));
+            }
+            if !seen.insert(selector.clone()) {
+                return Err(self.error_at(selector_pos, format!("重复的分支 {}", selector)));
+            }
+            self.skip_ws();
+            if self.peek() != Some('{') {
+                return Err(self.error_at(self.pos, format!("分支 {} 后面缺少 {{", selector)));
+            }
+            self.pos += 1;
+            self.message(1, plural)?;
+            self.pos += 1;
+        }
+    }
+
+    /// `=N` 中的数字（可带负号与小数点）
+    fn number(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || c == '-' || c == '.')
+        {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+}
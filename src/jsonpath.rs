@@ -0,0 +1,162 @@
+//! JSON 数据文件中的字符串选择器（`--json-paths`）
+//!
+//! 支持 JSONPath 的一个子集，足以选出对话、物品说明等文本所在的位置：
+//!
+//!   - `$`：根，选择器总是以它开头
+//!   - `.name` / `['name']` / `["name"]`：对象的成员
+//!   - `[0]`：数组的元素
+//!   - `.*` / `[*]`：任意成员或元素
+//!   - `..name` / `..*`：任意深度下的成员（如 `$..text` 选出所有 `text` 字段）
+//!
+//! 选择器只对字符串值起作用：`$.dialog[*]` 选出 `dialog` 数组中的字符串，其中的对象不会展开，
+//! 要选出对象中的字段需写成 `$.dialog[*].text` 或 `$.dialog..text`。
+
+use std::fmt;
+use std::str::FromStr;
+
+/// 值在 JSON 文档中的一级位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathStep {
+    /// 对象的成员名
+    Key(String),
+    /// 数组的下标（从 0 开始）
+    Index(usize),
+}
+
+/// 一级选择：成员名、下标或通配
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Selector {
+    Name(String),
+    Index(usize),
+    Wildcard,
+}
+
+impl Selector {
+    fn matches(&self, step: &PathStep) -> bool {
+        match (self, step) {
+            (Selector::Wildcard, _) => true,
+            (Selector::Name(name), PathStep::Key(key)) => name == key,
+            (Selector::Index(i), PathStep::Index(n)) => i == n,
+            _ => false,
+        }
+    }
+}
+
+/// 选择器的一段：直接子级，或任意深度的后代（`..`）
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Child(Selector),
+    Descendant(Selector),
+}
+
+/// 解析后的选择器，如 `$.dialog[*].text`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPath {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+impl JsonPath {
+    /// 位于 `path` 的值是否被选中
+    pub fn matches(&self, path: &[PathStep]) -> bool {
+        matches_from(&self.segments, path)
+    }
+}
+
+fn matches_from(segments: &[Segment], path: &[PathStep]) -> bool {
+    let Some((first, rest)) = segments.split_first() else {
+        return path.is_empty();
+    };
+    match first {
+        Segment::Child(sel) => path
+            .split_first()
+            .is_some_and(|(step, tail)| sel.matches(step) && matches_from(rest, tail)),
+        Segment::Descendant(sel) => {
+            (0..path.len()).any(|i| sel.matches(&path[i]) && matches_from(rest, &path[i + 1..]))
+        }
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |message: &str| format!("无效的 JSON 路径 {}：{}", s, message);
+        let Some(mut rest) = s.trim().strip_prefix('$') else {
+            return Err(error("必须以 $ 开头"));
+        };
+        let mut segments = Vec::new();
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix("..") {
+                let (sel, tail) =
+                    parse_name_or_bracket(after).ok_or_else(|| error(".. 后面缺少成员名"))?;
+                segments.push(Segment::Descendant(sel));
+                rest = tail;
+            } else if let Some(after) = rest.strip_prefix('.') {
+                let (sel, tail) = parse_name(after).ok_or_else(|| error(". 后面缺少成员名"))?;
+                segments.push(Segment::Child(sel));
+                rest = tail;
+            } else if rest.starts_with('[') {
+                let (sel, tail) = parse_bracket(rest).map_err(|m| error(&m))?;
+                segments.push(Segment::Child(sel));
+                rest = tail;
+            } else {
+                return Err(error(&format!("无法识别 {}", rest)));
+            }
+        }
+        Ok(Self {
+            source: s.trim().to_string(),
+            segments,
+        })
+    }
+}
+
+/// `.` 之后的成员名或 `*`，到下一个 `.` 或 `[` 为止
+fn parse_name(s: &str) -> Option<(Selector, &str)> {
+    let end = s.find(['.', '[']).unwrap_or(s.len());
+    match &s[..end] {
+        "" => None,
+        "*" => Some((Selector::Wildcard, &s[end..])),
+        name => Some((Selector::Name(name.to_string()), &s[end..])),
+    }
+}
+
+/// `..` 之后可以是成员名，也可以是 `[...]`
+fn parse_name_or_bracket(s: &str) -> Option<(Selector, &str)> {
+    if s.starts_with('[') {
+        parse_bracket(s).ok()
+    } else {
+        parse_name(s)
+    }
+}
+
+/// `[*]`、`[0]`、`['name']` 或 `["name"]`
+fn parse_bracket(s: &str) -> Result<(Selector, &str), String> {
+    let inner = &s[1..];
+    if let Some(q @ ('\'' | '"')) = inner.chars().next() {
+        let close = inner[1..]
+            .find(q)
+            .ok_or_else(|| "引号没有闭合".to_string())?;
+        let name = &inner[1..1 + close];
+        let tail = inner[close + 2..]
+            .strip_prefix(']')
+            .ok_or_else(|| "缺少 ]".to_string())?;
+        return Ok((Selector::Name(name.to_string()), tail));
+    }
+    let close = inner.find(']').ok_or_else(|| "缺少 ]".to_string())?;
+    let sel = match inner[..close].trim() {
+        "*" => Selector::Wildcard,
+        index => Selector::Index(
+            index
+                .parse()
+                .map_err(|_| format!("[{}] 不是下标、* 或带引号的成员名", index))?,
+        ),
+    };
+    Ok((sel, &inner[close + 1..]))
+}
@@ -0,0 +1,2745 @@
+//! sb_dice 的核心库：解析 TypeScript（或 JavaScript）源码，把普通字符串字面量（不包括模板字符串的 quasis）
+//! 替换为按顺序递增的索引字符串 "0","1",...，并生成映射表；也提供按映射表还原的反向操作。
+//!
+//! CLI（`src/main.rs`）只是这里公开 API 的一层薄包装，其他 Rust 工具或 build script
+//! 可以直接调用 [`extract_strings`] / [`restore_strings`] / [`apply_translations`]，不必再去调用二进制。
+//!
+//! 可以用 `// sb-dice-ignore-file`、`// sb-dice-ignore-next-line` 与 `/* sb-dice-ignore */`
+//! 注释局部关闭替换，见 `directive` 模块。
+//!
+//! 备注：默认保留注释（注释经由 `SingleThreadedComments` 从 lexer 传到 emitter），
+//!      设置 [`ExtractOptions::strip_comments`] 时去掉。
+//!      文件开头的 `#!` 行（Node 脚本的 shebang）由 lexer 读作 `Module::shebang`，codegen 时原样写回第一行，
+//!      其中的内容不会被当作字符串替换。
+
+pub mod compress;
+mod context;
+pub mod decisions;
+pub mod diff;
+mod directive;
+pub mod dupes;
+pub mod encoding;
+pub mod format;
+pub mod glossary;
+pub mod icu;
+pub mod jsonpath;
+pub mod loader;
+pub mod markup;
+pub mod merge;
+pub mod meta;
+pub mod placeholder;
+pub mod renumber;
+pub mod split;
+pub mod stats;
+pub mod text;
+pub mod tsconfig;
+
+pub use compress::Compression;
+pub use decisions::{Decision, Decisions};
+pub use diff::{
+    ChangedString, Hunk, MappingDiff, SeqEdit, diff_mappings, diff_multisets, diff_sequences,
+    group_hunks, natural_cmp,
+};
+pub use dupes::{DupeGroup, find_dupes};
+pub use encoding::{SourceEncoding, encode_text};
+pub use format::{FormatOptions, JsonStyle, MapFormat, MapSchema, to_canonical_json};
+pub use glossary::{GlossaryOptions, Term, build_glossary, render_glossary};
+pub use icu::{IcuError, validate_icu};
+pub use jsonpath::JsonPath;
+pub use loader::render_loader;
+pub use merge::{MergeOptions, MergeResult, merge_mappings, merge_mappings_with};
+pub use meta::{
+    LINK_PREFIX, META_KEY, META_VERSION, MapMeta, content_hash, keys_hash, link_comment, split_link,
+};
+pub use placeholder::{PlaceholderIssue, check_placeholders};
+pub use renumber::{Renumbered, renumber_mapping};
+pub use split::{SplitMode, chunk_name, chunk_record, split_entries};
+pub use stats::StringStats;
+pub use swc_core::ecma::ast::EsVersion;
+pub use tsconfig::TsConfig;
+
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use std::string::FromUtf8Error;
+
+use swc_core::common::comments::{Comments, SingleThreadedComments};
+use swc_core::common::source_map::SourceMapGenConfig;
+use swc_core::common::{BytePos, FileName, LineCol, SourceMap, Span, Spanned, sync::Lrc};
+use swc_core::ecma::ast::{
+    ArrowExpr, BinExpr, BinaryOp, BlockStmtOrExpr, CallExpr, Callee, ClassDecl, ClassExpr,
+    ClassMethod, ComputedPropName, Constructor, Decorator, ExportAll, Expr, ExprOrSpread, FnDecl,
+    FnExpr, Function, Ident, IdentName, ImportDecl, ImportNamedSpecifier, ImportSpecifier,
+    JSXAttrValue, JSXElement, JSXElementChild, JSXExpr, JSXExprContainer, JSXText, Lit, MemberExpr,
+    MemberProp, MethodProp, Module, ModuleDecl, ModuleItem, NamedExport, Number, PrivateMethod,
+    Prop, PropName, PropOrSpread, Stmt, Str, SuperProp, SwitchCase, TaggedTpl, TplElement,
+    TsEnumMember, TsExternalModuleRef, TsModuleName, TsType, VarDeclarator,
+};
+use swc_core::ecma::codegen::{Config, Emitter, text_writer::JsWriter};
+use swc_core::ecma::parser::{EsSyntax, Parser, StringInput, Syntax, TsSyntax, lexer::Lexer};
+use swc_core::ecma::visit::{Visit, VisitMut, VisitMutWith, VisitWith};
+
+use directive::Directives;
+use jsonpath::PathStep;
+use markup::{Document, Markup};
+use regex::Regex;
+use xxhash_rust::xxh64::xxh64;
+
+use serde_json::Map;
+use serde_json::Value;
+
+/// 判断调用是否为 `require("x")` 或动态 `import("x")`
+fn is_module_call(n: &CallExpr) -> bool {
+    match &n.callee {
+        Callee::Import(_) => true,
+        Callee::Expr(e) => matches!(&**e, Expr::Ident(i) if &*i.sym == "require"),
+        Callee::Super(_) => false,
+    }
+}
+
+/// 若语句是指令（如 `"use strict";`），返回其中的字符串
+///
+/// 带括号的 `("use strict");` 不是指令，解析后是 `Expr::Paren`，这里自然不会匹配。
+fn directive_str(stmt: &Stmt) -> Option<&Str> {
+    match stmt {
+        Stmt::Expr(e) => match &*e.expr {
+            Expr::Lit(Lit::Str(s)) => Some(s),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// 计算属性名是否就是一个字符串字面量（`obj["hp"]`、`{ ["hp"]: 10 }`）
+fn is_str_key(n: &ComputedPropName) -> bool {
+    matches!(&*n.expr, Expr::Lit(Lit::Str(_)))
+}
+
+/// 表达式是否就是一个字符串字面量
+fn is_str_lit(e: &Expr) -> bool {
+    matches!(e, Expr::Lit(Lit::Str(_)))
+}
+
+/// 操作数中字符串字面量的起始位置
+fn str_positions(operands: &[&Expr]) -> Vec<BytePos> {
+    operands
+        .iter()
+        .filter_map(|e| match e {
+            Expr::Lit(Lit::Str(s)) => Some(s.span.lo),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `+` 链的各个操作数（括号中的不展开），同时记下链中间的 `+` 表达式
+fn concat_operands<'e>(n: &'e BinExpr, operands: &mut Vec<&'e Expr>, inner: &mut HashSet<Span>) {
+    for side in [&n.left, &n.right] {
+        match &**side {
+            Expr::Bin(b) if b.op == BinaryOp::Add => {
+                inner.insert(b.span);
+                concat_operands(b, operands, inner);
+            }
+            e => operands.push(e),
+        }
+    }
+}
+
+/// `[...].join("")` 中数组的各个元素
+fn join_elements(n: &CallExpr) -> Option<Vec<&Expr>> {
+    let Callee::Expr(callee) = &n.callee else {
+        return None;
+    };
+    let Expr::Member(member) = &**callee else {
+        return None;
+    };
+    let (Expr::Array(array), MemberProp::Ident(prop), [arg]) =
+        (&*member.obj, &member.prop, &n.args[..])
+    else {
+        return None;
+    };
+    let joins_empty =
+        matches!(&*arg.expr, Expr::Lit(Lit::Str(sep)) if sep.value.as_str() == Some(""));
+    if &*prop.sym != "join" || arg.spread.is_some() || !joins_empty {
+        return None;
+    }
+    Some(array.elems.iter().flatten().map(|e| &*e.expr).collect())
+}
+
+/// key 作为 wrap_call 的参数：与十进制索引写法一致的 key 写成数字，其余写成字符串
+fn wrap_arg(key: &str, span: Span) -> Expr {
+    match key.parse::<u32>() {
+        Ok(n) if n.to_string() == key => Expr::Lit(Lit::Num(Number {
+            span,
+            value: n.into(),
+            raw: None,
+        })),
+        _ => Expr::Lit(Lit::Str(Str {
+            span,
+            value: key.into(),
+            raw: None,
+        })),
+    }
+}
+
+/// wrap_arg 在源码中的写法（preserve_format 模式下使用）
+fn wrap_arg_text(key: &str, quote: char) -> String {
+    match key.parse::<u32>() {
+        Ok(n) if n.to_string() == key => key.to_string(),
+        _ => text::quote_js(key, quote, false),
+    }
+}
+
+/// 生成 `S(0)` / `i18n.t("battle.3")` 这样的查表调用
+fn wrap_call(name: &str, key: &str, span: Span) -> Expr {
+    let mut parts = name.split('.');
+    let first = parts.next().unwrap_or_default();
+    let mut callee = Expr::Ident(Ident::new_no_ctxt(first.into(), span));
+    for part in parts {
+        callee = Expr::Member(MemberExpr {
+            span,
+            obj: Box::new(callee),
+            prop: MemberProp::Ident(IdentName::new(part.into(), span)),
+        });
+    }
+    Expr::Call(CallExpr {
+        span,
+        ctxt: Default::default(),
+        callee: Callee::Expr(Box::new(callee)),
+        args: vec![ExprOrSpread {
+            spread: None,
+            expr: Box::new(wrap_arg(key, span)),
+        }],
+        type_args: None,
+    })
+}
+
+/// 若表达式是 wrap_call 生成的查表调用，返回其中的 key
+fn unwrap_call(e: &Expr, name: &str) -> Option<String> {
+    let Expr::Call(call) = e else {
+        return None;
+    };
+    if call.args.len() != 1
+        || call.args[0].spread.is_some()
+        || context::callee_name(&call.callee).as_deref() != Some(name)
+    {
+        return None;
+    }
+    match &*call.args[0].expr {
+        Expr::Lit(Lit::Num(n)) if n.value.fract() == 0.0 && n.value >= 0.0 => {
+            Some((n.value as u64).to_string())
+        }
+        Expr::Lit(Lit::Str(s)) => s.value.as_str().map(str::to_string),
+        _ => None,
+    }
+}
+
+/// 替换器：记录计数并收集原始字符串（按顺序）
+struct StringReplacer<'a> {
+    opts: &'a ExtractOptions,
+    cm: &'a SourceMap,
+    /// 原始源码，用于 preserve_format 模式下取原来的引号
+    src: &'a str,
+    /// preserve_format 模式下要应用到原始源码上的文本编辑
+    edits: Vec<Edit>,
+    /// 注释指令（ignore-next-line / ignore）
+    directives: Directives,
+    counter: usize,
+    entries: Vec<Entry>,
+    /// dedupe 模式下：原始字符串 -> entries 中的下标
+    seen: HashMap<String, usize>,
+    /// with_context 模式下：由外到内的上下文名
+    context: Vec<String>,
+    /// strict 模式下遇到的第一个无法无损表示的字符串（行号与原始字面量）
+    invalid: Option<(usize, String)>,
+    /// 指令序言（`"use strict"`、`"use client"` 等）中字符串的位置
+    prologue: HashSet<BytePos>,
+    /// 已生成的 key
+    keys: HashSet<String>,
+    /// 当前位置不能写成查表调用（TS 枚举成员的值必须是常量）
+    no_wrap: bool,
+    /// 当前是否位于 only_calls 列出的函数的参数中
+    in_listed_call: bool,
+    /// 当前是否位于 skip_calls 列出的函数的调用中（嵌套的调用同样跳过）
+    in_skipped_call: bool,
+    /// fold_concat 模式下由拼接合并而来的字面量的位置与原来的段数（见 [`ConcatFolder`]）
+    folded: HashMap<Span, usize>,
+    /// 其中实际替换了的拼接
+    folds: Vec<FoldedConcat>,
+    /// 已替换的字符串的起始位置（判断拼接中的片段是否被替换）
+    recorded: HashSet<BytePos>,
+    /// 正在检查的 `+` 链中间的 `+` 表达式，不再单独检查
+    concat_inner: HashSet<Span>,
+    /// 被拆成片段的句子
+    fragments: Vec<Fragment>,
+}
+
+impl<'a> StringReplacer<'a> {
+    fn new(
+        opts: &'a ExtractOptions,
+        cm: &'a SourceMap,
+        src: &'a str,
+        directives: Directives,
+    ) -> Self {
+        Self {
+            opts,
+            cm,
+            src,
+            edits: Vec::new(),
+            directives,
+            counter: opts.start_index,
+            entries: Vec::new(),
+            seen: HashMap::new(),
+            context: Vec::new(),
+            invalid: None,
+            prologue: HashSet::new(),
+            keys: HashSet::new(),
+            no_wrap: false,
+            in_listed_call: false,
+            in_skipped_call: false,
+            folded: HashMap::new(),
+            folds: Vec::new(),
+            recorded: HashSet::new(),
+            concat_inner: HashSet::new(),
+            fragments: Vec::new(),
+        }
+    }
+}
+
+impl StringReplacer<'_> {
+    /// 取位置在源文件中的字节偏移
+    fn offset(&self, pos: BytePos) -> usize {
+        self.cm.lookup_byte_offset(pos).pos.0 as usize
+    }
+
+    /// 记录一处文本编辑：把 span 覆盖的源码替换为 text
+    fn push_edit(&mut self, span: Span, text: String) {
+        if !self.opts.preserve_format {
+            return;
+        }
+        let lo = self.offset(span.lo);
+        let hi = self.offset(span.hi);
+        self.edits.push(Edit { lo, hi, text });
+    }
+
+    /// 判断该位置是否被注释指令排除
+    fn is_ignored(&self, span: Span) -> bool {
+        if self.directives.ignored_positions.contains(&span.lo) {
+            return true;
+        }
+        if self.directives.ignored_lines.is_empty() {
+            return false;
+        }
+        let line = self.cm.lookup_char_pos(span.lo).line;
+        self.directives.ignored_lines.contains(&line)
+    }
+
+    /// 按过滤条件判断字符串是否需要替换
+    fn should_extract(&self, s: &str) -> bool {
+        if self.in_skipped_call {
+            return false;
+        }
+        if !self.opts.only_calls.is_empty() && !self.in_listed_call {
+            return false;
+        }
+        if self.opts.only_cjk && !text::contains_cjk(s) {
+            return false;
+        }
+        // 长度按字符数计算，而不是字节数
+        let len = s.chars().count();
+        if self.opts.min_len.is_some_and(|min| len < min) {
+            return false;
+        }
+        if self.opts.max_len.is_some_and(|max| len > max) {
+            return false;
+        }
+        if !self.opts.pattern.as_ref().is_none_or(|re| re.is_match(s)) {
+            return false;
+        }
+        self.opts.excluded.is_empty() || !self.opts.excluded.contains(&self.hash(s))
+    }
+
+    /// 记下语句列表开头的指令序言，它们改变的是代码语义，不能替换
+    fn mark_prologue<'s>(&mut self, stmts: impl IntoIterator<Item = &'s Stmt>) {
+        if self.opts.include_directives {
+            return;
+        }
+        let directives = stmts.into_iter().map_while(directive_str);
+        self.prologue.extend(directives.map(|s| s.span.lo));
+    }
+
+    /// 只在 with_context 模式下计算上下文名
+    fn frame(&self, name: impl FnOnce() -> Option<String>) -> Option<String> {
+        if self.opts.with_context { name() } else { None }
+    }
+
+    /// 在（可能存在的）一层上下文中访问子节点
+    fn in_frame(&mut self, frame: Option<String>, visit: impl FnOnce(&mut Self)) {
+        let Some(frame) = frame else {
+            visit(self);
+            return;
+        };
+        self.context.push(frame);
+        visit(self);
+        self.context.pop();
+    }
+
+    /// 取 span 在源文件中的位置
+    fn location(&self, span: Span) -> Location {
+        let loc = self.cm.lookup_char_pos(span.lo);
+        Location {
+            line: loc.line,
+            column: loc.col.0 + 1,
+            start: self.offset(span.lo),
+            end: self.offset(span.hi),
+        }
+    }
+
+    /// 处理无法用 UTF-8 表示的字符串（含有孤立代理项，如 `"\uD800"`）
+    ///
+    /// strict 模式下记下错误并返回 false（不替换）；否则返回 true，
+    /// 映射表中同时记录可读的近似文本与原始字面量，还原时使用原始字面量。
+    fn accept_lossy(&mut self, span: Span, raw: &str) -> bool {
+        if !self.opts.strict {
+            return true;
+        }
+        if self.invalid.is_none() {
+            let line = self.cm.lookup_char_pos(span.lo).line;
+            self.invalid = Some((line, raw.to_string()));
+        }
+        false
+    }
+
+    /// 记录一个原始字符串，返回替换后使用的 key
+    ///
+    /// `raw` 为原始字面量：字符串无法无损转换为 UTF-8 时（`lossy`）或空白被规范化过时总是给出，
+    /// 否则只在 keep_raw 模式下给出。
+    fn record(&mut self, original: String, raw: Option<String>, lossy: bool, span: Span) -> String {
+        self.recorded.insert(span.lo);
+        let location = self.location(span);
+        let recorded = self.opts.with_locations.then_some(location);
+        let note = self
+            .directives
+            .note(span.lo, location.line)
+            .map(str::to_string);
+
+        // dedupe 模式下相同的字符串共用同一个索引；hash 模式下相同字符串的 key 本来就相同。
+        // 近似文本可能相同而原始内容不同，这时按原始字面量区分；
+        // keep_raw 模式下为了逐字节还原，写法不同（如引号、转义）的字面量也分开记录；
+        // 同理，规范化后相同而原来的空白不同的字符串也分开记录
+        if self.opts.dedupe || self.opts.key_mode == KeyMode::Hash {
+            let seen_key = match &raw {
+                Some(raw) => raw.clone(),
+                None => original.clone(),
+            };
+            if let Some(&idx) = self.seen.get(&seen_key) {
+                let entry = &mut self.entries[idx];
+                entry.count = entry.count.map(|c| c + 1);
+                entry.occurrences += 1;
+                entry.locations.extend(recorded);
+                if entry.note.is_none() {
+                    entry.note = note;
+                }
+                return entry.key.clone();
+            }
+            self.seen.insert(seen_key, self.entries.len());
+        }
+
+        let key = match &raw {
+            Some(raw) if lossy => self.next_key(raw, span),
+            _ => self.next_key(&original, span),
+        };
+
+        // 记录原始内容
+        self.entries.push(Entry {
+            key: key.clone(),
+            original,
+            raw,
+            count: self.opts.dedupe.then_some(1),
+            occurrences: 1,
+            file: None,
+            first_line: location.line,
+            locations: recorded.into_iter().collect(),
+            context: (!self.context.is_empty()).then(|| self.context.join(context::SEPARATOR)),
+            note,
+        });
+
+        self.counter += 1;
+        key
+    }
+
+    /// `trim` / `collapse_whitespace` 时规范化字符串中的空白；内容有变化时返回规范化后的文本，
+    /// 这时映射表中要记录原始字面量，还原时才能逐字节复现
+    fn normalize(&self, original: &str) -> Option<String> {
+        if !self.opts.trim && !self.opts.collapse_whitespace {
+            return None;
+        }
+        let normalized =
+            text::normalize_whitespace(original, self.opts.trim, self.opts.collapse_whitespace);
+        (normalized != original).then_some(normalized)
+    }
+
+    /// preserve_format 模式下新字面量使用的引号：指定了 `codegen.quote` 时使用它，
+    /// 否则保留原来的引号（' 或 "）
+    fn quote_char(&self, span: Span) -> char {
+        match self.opts.codegen.quote {
+            Some(quote) => quote.char(),
+            None => self
+                .src
+                .as_bytes()
+                .get(self.offset(span.lo))
+                .map_or('"', |&b| b as char),
+        }
+    }
+
+    /// `import { name } from "from";` 的写法
+    fn import_text(&self, name: &str, from: &str) -> String {
+        let quote = self.opts.codegen.quote.map_or('"', QuoteStyle::char);
+        format!(
+            "import {{ {} }} from {};",
+            name,
+            text::quote_js(from, quote, false)
+        )
+    }
+
+    /// 在指令序言之后插入 `import { name } from "from";`
+    fn insert_import(&mut self, module: &mut Module, name: &str, from: &str) {
+        let idx = module
+            .body
+            .iter()
+            .take_while(|item| matches!(item, ModuleItem::Stmt(s) if directive_str(s).is_some()))
+            .count();
+
+        let text = self.import_text(name, from);
+        match module.body.get(idx) {
+            Some(item) => {
+                let pos = item.span().lo;
+                self.push_edit(Span::new(pos, pos), format!("{}\n", text));
+            }
+            None => {
+                let pos = module.span.hi;
+                self.push_edit(Span::new(pos, pos), format!("\n{}\n", text));
+            }
+        }
+
+        let span = Span::default();
+        let import = ImportDecl {
+            span,
+            specifiers: vec![ImportSpecifier::Named(ImportNamedSpecifier {
+                span,
+                local: Ident::new_no_ctxt(name.into(), span),
+                imported: None,
+                is_type_only: false,
+            })],
+            src: Box::new(Str {
+                span,
+                value: from.into(),
+                raw: None,
+            }),
+            type_only: false,
+            with: None,
+            phase: Default::default(),
+        };
+        module
+            .body
+            .insert(idx, ModuleItem::ModuleDecl(ModuleDecl::Import(import)));
+    }
+
+    /// 替换组件中标签之间的纯文本（`markup_text`），`base` 为文件在 SourceMap 中的起始位置
+    fn extract_markup_texts(&mut self, texts: &[Range<usize>], base: BytePos) {
+        for range in texts {
+            let span = Span::new(
+                base + BytePos(range.start as u32),
+                base + BytePos(range.end as u32),
+            );
+            let original = self.src[range.clone()].to_string();
+            // 含有 `{...}` 表达式或 `{#if}` 等块的文本不能整体替换
+            if original.contains(['{', '}']) || !self.should_extract(&original) {
+                continue;
+            }
+            let key = self.record(original, None, false, span);
+            let text = match &self.opts.wrap_call {
+                Some(name) => format!("{{{}({})}}", name, wrap_arg_text(&key, '"')),
+                None => key,
+            };
+            self.push_edit(span, text);
+        }
+    }
+
+    /// 替换 JSX 元素之间的文本（`jsx_text`）：按 JSX 的规则折叠空白后记录，
+    /// 两端的空白留在原处，中间的文本换成 `{"0"}`（或 `{S(0)}`），渲染结果中的空格不变
+    fn extract_jsx_text(&mut self, n: JSXText, out: &mut Vec<JSXElementChild>) {
+        let collapsed = text::collapse_jsx_text(&n.value);
+        let original = collapsed.trim_matches(' ');
+        let is_space = |c: char| matches!(c, ' ' | '\t' | '\n' | '\r');
+        let raw: &str = &n.raw;
+        let lead = raw.len() - raw.trim_start_matches(is_space).len();
+        let trail = raw.trim_end_matches(is_space).len();
+        let span = Span::new(
+            n.span.lo + BytePos(lead as u32),
+            n.span.lo + BytePos(trail as u32),
+        );
+        if original.is_empty() || !self.should_extract(original) || self.is_ignored(span) {
+            out.push(JSXElementChild::JSXText(n));
+            return;
+        }
+
+        let key = self.record(original.to_string(), None, false, span);
+        let (written, expr) = match &self.opts.wrap_call {
+            Some(name) => (
+                format!("{}({})", name, wrap_arg_text(&key, '"')),
+                wrap_call(name, &key, span),
+            ),
+            None => {
+                let quoted = text::quote_js(
+                    &key,
+                    self.opts.codegen.quote.map_or('"', QuoteStyle::char),
+                    false,
+                );
+                let lit = Str {
+                    span,
+                    value: key.clone().into(),
+                    raw: Some(quoted.clone().into()),
+                };
+                (quoted, Expr::Lit(Lit::Str(lit)))
+            }
+        };
+        self.push_edit(span, format!("{{{}}}", written));
+
+        let whitespace = |range: Range<usize>, lo: BytePos| {
+            let ws = &raw[range];
+            JSXElementChild::JSXText(JSXText {
+                span: Span::new(lo, lo + BytePos(ws.len() as u32)),
+                value: ws.into(),
+                raw: ws.into(),
+            })
+        };
+        if lead > 0 {
+            out.push(whitespace(0..lead, n.span.lo));
+        }
+        out.push(JSXElementChild::JSXExprContainer(JSXExprContainer {
+            span,
+            expr: JSXExpr::Expr(Box::new(expr)),
+        }));
+        if trail < raw.len() {
+            out.push(whitespace(trail..raw.len(), span.hi));
+        }
+    }
+
+    /// 替换 JSON 数据文件中选中的字符串值（见 [`json_strings`]），key 写成 JSON 字符串
+    fn extract_json_strings(&mut self, strs: &[&Str]) {
+        for s in strs {
+            if let Some(key) = self.extract_str(s) {
+                self.push_edit(s.span, Value::String(key).to_string());
+            }
+        }
+    }
+
+    /// 判断字符串字面量是否需要替换，需要时记录到映射表并返回新的 key
+    fn extract_str(&mut self, n: &Str) -> Option<String> {
+        if self.prologue.contains(&n.span.lo) {
+            return None;
+        }
+        // 只针对 Str 节点（模板的 quasis 是 TplElement，由 visit_mut_tpl_element 处理）
+        // 使用字符串的原始值，而不是 Debug 格式（避免生成带转义的双引号）
+        // 含有孤立代理项时 as_str 为 None，此时记录近似文本
+        let (original, lossy) = match n.value.as_str() {
+            Some(s) => (s.to_string(), false),
+            None => (n.value.to_string_lossy().into_owned(), true),
+        };
+        let normalized = self.normalize(&original);
+        let changed = normalized.is_some();
+        let original = normalized.unwrap_or(original);
+        // 只含空白的字符串规范化后为空，没有可翻译的内容
+        if changed && original.is_empty() {
+            return None;
+        }
+        if !self.should_extract(&original) || self.is_ignored(n.span) {
+            return None;
+        }
+        let raw = (lossy || changed || self.opts.keep_raw).then(|| match &n.raw {
+            Some(raw) => raw.to_string(),
+            None => self.src[self.offset(n.span.lo)..self.offset(n.span.hi)].to_string(),
+        });
+        if lossy && !self.accept_lossy(n.span, raw.as_deref().unwrap_or_default()) {
+            return None;
+        }
+
+        if let Some(&parts) = self.folded.get(&n.span) {
+            self.folds.push(FoldedConcat {
+                location: self.location(n.span),
+                parts,
+                text: original.clone(),
+            });
+        }
+
+        // 生成新的字符串值，例如 "0", "1", ...
+        Some(self.record(original, raw, lossy, n.span))
+    }
+
+    /// 访问二元表达式的两侧；skip_comparisons 时跳过用于比较的字符串
+    fn visit_bin_operands(&mut self, n: &mut BinExpr) {
+        if !self.opts.skip_comparisons {
+            n.visit_mut_children_with(self);
+            return;
+        }
+        let (skip_left, skip_right) = match n.op {
+            BinaryOp::EqEq | BinaryOp::NotEq | BinaryOp::EqEqEq | BinaryOp::NotEqEq => {
+                (is_str_lit(&n.left), is_str_lit(&n.right))
+            }
+            BinaryOp::In => (is_str_lit(&n.left), false),
+            _ => (false, false),
+        };
+        if !skip_left {
+            n.left.visit_mut_with(self);
+        }
+        if !skip_right {
+            n.right.visit_mut_with(self);
+        }
+    }
+
+    /// 拼接或 `.join("")` 的片段（`strs` 为其中字符串的位置）有被替换的时，记为被拆开的句子
+    fn check_fragments(&mut self, kind: FragmentKind, span: Span, pieces: usize, strs: &[BytePos]) {
+        let strings = strs.iter().filter(|lo| self.recorded.contains(lo)).count();
+        if pieces < 2 || strings == 0 {
+            return;
+        }
+        let location = self.location(span);
+        self.fragments.push(Fragment {
+            location,
+            kind,
+            strings,
+            source: self.src[location.start..location.end].to_string(),
+        });
+    }
+
+    /// 按 key 模式（或 key 模板）生成下一个 key
+    fn next_key(&mut self, original: &str, span: Span) -> String {
+        let key = match &self.opts.key_template {
+            Some(template) => self.template_key(template, original, span),
+            None => {
+                let key = match self.opts.key_mode {
+                    KeyMode::Index => self.opts.index_format.format(self.counter),
+                    KeyMode::Hash => self.hash(original),
+                };
+                match self.file_stem() {
+                    Some(ns) if self.opts.namespace == Some(KeyNamespace::File) => {
+                        format!("{}.{}", ns, key)
+                    }
+                    _ => key,
+                }
+            }
+        };
+
+        // 模板不含 {n} 时可能生成重复的 key（如同一行的两个字符串），追加序号区分
+        let mut unique = key.clone();
+        let mut n = 2;
+        while !self.keys.insert(unique.clone()) {
+            unique = format!("{}~{}", key, n);
+            n += 1;
+        }
+        unique
+    }
+
+    /// 按 key 模板生成 key
+    fn template_key(&self, template: &KeyTemplate, original: &str, span: Span) -> String {
+        let mut key = String::new();
+        for part in &template.parts {
+            match part {
+                TemplatePart::Literal(text) => key.push_str(text),
+                TemplatePart::File => key.push_str(&self.file_stem().unwrap_or_default()),
+                TemplatePart::Line => key.push_str(&self.location(span).line.to_string()),
+                TemplatePart::Counter => key.push_str(&self.opts.index_format.format(self.counter)),
+                TemplatePart::Hash => key.push_str(&self.hash(original)[..8]),
+            }
+        }
+        key
+    }
+
+    /// 源文件名（不含扩展名）
+    fn file_stem(&self) -> Option<Cow<'_, str>> {
+        self.opts
+            .file_name
+            .as_deref()
+            .and_then(Path::file_stem)
+            .map(|s| s.to_string_lossy())
+    }
+
+    /// 由文件路径与字符串内容计算的 xxhash64（见 [`string_hash`]）
+    fn hash(&self, original: &str) -> String {
+        string_hash(self.opts.file_name.as_deref(), original)
+    }
+}
+
+impl VisitMut for StringReplacer<'_> {
+    // --- 指令序言：模块与函数体开头的 "use strict" 等 ---
+
+    fn visit_mut_module(&mut self, n: &mut Module) {
+        self.mark_prologue(n.body.iter().map_while(|item| match item {
+            ModuleItem::Stmt(stmt) => Some(stmt),
+            ModuleItem::ModuleDecl(_) => None,
+        }));
+        n.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_function(&mut self, n: &mut Function) {
+        if let Some(body) = &n.body {
+            self.mark_prologue(&body.stmts);
+        }
+        n.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_arrow_expr(&mut self, n: &mut ArrowExpr) {
+        if let BlockStmtOrExpr::BlockStmt(body) = &*n.body {
+            self.mark_prologue(&body.stmts);
+        }
+        n.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_ts_type(&mut self, n: &mut TsType) {
+        // 类型位置（字符串字面量类型、keyof、映射类型等）中的字符串默认不替换，
+        // 否则 _r.ts 无法通过类型检查
+        if self.opts.include_types {
+            n.visit_mut_children_with(self);
+        }
+    }
+
+    fn visit_mut_ts_module_name(&mut self, n: &mut TsModuleName) {
+        // `declare module "x"` 的模块名同样属于类型空间
+        if self.opts.include_types {
+            n.visit_mut_children_with(self);
+        }
+    }
+
+    fn visit_mut_import_decl(&mut self, n: &mut ImportDecl) {
+        if !self.opts.skip_imports {
+            n.visit_mut_children_with(self);
+            return;
+        }
+        // 跳过模块路径 src，其余部分照常处理
+        n.specifiers.visit_mut_with(self);
+        n.with.visit_mut_with(self);
+    }
+
+    fn visit_mut_export_all(&mut self, n: &mut ExportAll) {
+        if !self.opts.skip_imports {
+            n.visit_mut_children_with(self);
+        }
+    }
+
+    fn visit_mut_named_export(&mut self, n: &mut NamedExport) {
+        if !self.opts.skip_imports {
+            n.visit_mut_children_with(self);
+            return;
+        }
+        n.specifiers.visit_mut_with(self);
+        n.with.visit_mut_with(self);
+    }
+
+    fn visit_mut_ts_external_module_ref(&mut self, n: &mut TsExternalModuleRef) {
+        // `import x = require("x")`
+        if !self.opts.skip_imports {
+            n.visit_mut_children_with(self);
+        }
+    }
+
+    fn visit_mut_ts_enum_member(&mut self, n: &mut TsEnumMember) {
+        // `enum Color { Red = "red" }`：代码会拿成员值与字面量比较，默认不替换
+        if self.opts.include_enums {
+            // 枚举成员的值必须是常量，不能写成查表调用
+            let outer = std::mem::replace(&mut self.no_wrap, true);
+            n.visit_mut_children_with(self);
+            self.no_wrap = outer;
+        }
+    }
+
+    fn visit_mut_decorator(&mut self, n: &mut Decorator) {
+        // `@Component({ selector: "app-x" })`：装饰器参数是框架元数据，默认不替换
+        if self.opts.include_decorators {
+            n.visit_mut_children_with(self);
+        }
+    }
+
+    fn visit_mut_tagged_tpl(&mut self, n: &mut TaggedTpl) {
+        // sql`...`、gql`...`：模板内容由标签函数解释，替换后查询就坏了，
+        // 静态部分与 `${}` 中的字符串都不替换；include_tags 列出的标签（如 t`...`）除外
+        let included =
+            context::expr_name(&n.tag).is_some_and(|name| self.opts.include_tags.contains(&name));
+        if included {
+            n.visit_mut_children_with(self);
+        } else {
+            // 标签本身（如 styled(Button)`...` 中的调用）照常处理
+            n.tag.visit_mut_with(self);
+        }
+    }
+
+    // --- 比较：typeof x === "string"、kind === "attack"、case "attack":、"hp" in obj ---
+
+    fn visit_mut_bin_expr(&mut self, n: &mut BinExpr) {
+        // `"你有 " + n + " 个金币"`：整条 `+` 链检查一次，中间的 `+` 不再单独检查
+        if n.op != BinaryOp::Add || self.concat_inner.remove(&n.span) {
+            self.visit_bin_operands(n);
+            return;
+        }
+        let mut operands = Vec::new();
+        concat_operands(n, &mut operands, &mut self.concat_inner);
+        let (pieces, strs) = (operands.len(), str_positions(&operands));
+        self.visit_bin_operands(n);
+        self.check_fragments(FragmentKind::Concat, n.span, pieces, &strs);
+    }
+
+    fn visit_mut_switch_case(&mut self, n: &mut SwitchCase) {
+        if !self.opts.skip_comparisons || !n.test.as_deref().is_some_and(is_str_lit) {
+            n.test.visit_mut_with(self);
+        }
+        n.cons.visit_mut_with(self);
+    }
+
+    // --- 属性名：对象字面量、类成员、解构中的字符串 key 与 obj["hp"] ---
+
+    fn visit_mut_prop_name(&mut self, n: &mut PropName) {
+        // 替换后运行时按 key 取值会失败，默认跳过
+        let is_key = match n {
+            PropName::Str(_) => true,
+            PropName::Computed(c) => is_str_key(c),
+            _ => false,
+        };
+        if self.opts.include_keys || !is_key {
+            n.visit_mut_children_with(self);
+        }
+    }
+
+    fn visit_mut_member_prop(&mut self, n: &mut MemberProp) {
+        if matches!(n, MemberProp::Computed(c) if !self.opts.include_keys && is_str_key(c)) {
+            return;
+        }
+        n.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_super_prop(&mut self, n: &mut SuperProp) {
+        if matches!(n, SuperProp::Computed(c) if !self.opts.include_keys && is_str_key(c)) {
+            return;
+        }
+        n.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_call_expr(&mut self, n: &mut CallExpr) {
+        // `["你有 ", n, " 个金币"].join("")`
+        let joined = join_elements(n).map(|elems| (elems.len(), str_positions(&elems)));
+        let frame = self.frame(|| context::call_name(&n.callee));
+        self.in_frame(frame, |this| {
+            // only_calls：只有列出的函数的参数（包括参数中的条件表达式等）才会被替换，
+            // 参数里再嵌套的其他调用不算
+            // skip_calls：列出的函数的整个调用（包括参数里嵌套的调用）都不替换
+            let outer = (this.in_listed_call, this.in_skipped_call);
+            if !this.opts.only_calls.is_empty() || !this.opts.skip_calls.is_empty() {
+                let name = context::callee_name(&n.callee);
+                let listed = |names: &[String]| name.as_ref().is_some_and(|n| names.contains(n));
+                if !this.opts.only_calls.is_empty() {
+                    this.in_listed_call = listed(&this.opts.only_calls);
+                }
+                this.in_skipped_call |= listed(&this.opts.skip_calls);
+            }
+
+            if !this.opts.skip_imports || !is_module_call(n) {
+                n.visit_mut_children_with(this);
+            } else {
+                // require("x") / import("x")：跳过第一个参数（模块路径）
+                n.callee.visit_mut_with(this);
+                n.type_args.visit_mut_with(this);
+                for arg in n.args.iter_mut().skip(1) {
+                    arg.visit_mut_with(this);
+                }
+            }
+
+            (this.in_listed_call, this.in_skipped_call) = outer;
+        });
+        if let Some((pieces, strs)) = joined {
+            self.check_fragments(FragmentKind::Join, n.span, pieces, &strs);
+        }
+    }
+
+    // --- 以下只用于记录上下文（with_context） ---
+
+    fn visit_mut_fn_decl(&mut self, n: &mut FnDecl) {
+        let frame = self.frame(|| Some(n.ident.sym.to_string()));
+        self.in_frame(frame, |this| n.visit_mut_children_with(this));
+    }
+
+    fn visit_mut_fn_expr(&mut self, n: &mut FnExpr) {
+        let frame = self.frame(|| n.ident.as_ref().map(|i| i.sym.to_string()));
+        self.in_frame(frame, |this| n.visit_mut_children_with(this));
+    }
+
+    fn visit_mut_class_decl(&mut self, n: &mut ClassDecl) {
+        let frame = self.frame(|| Some(n.ident.sym.to_string()));
+        self.in_frame(frame, |this| n.visit_mut_children_with(this));
+    }
+
+    fn visit_mut_class_expr(&mut self, n: &mut ClassExpr) {
+        let frame = self.frame(|| n.ident.as_ref().map(|i| i.sym.to_string()));
+        self.in_frame(frame, |this| n.visit_mut_children_with(this));
+    }
+
+    fn visit_mut_var_declarator(&mut self, n: &mut VarDeclarator) {
+        let frame = self.frame(|| context::declarator_name(n));
+        self.in_frame(frame, |this| n.visit_mut_children_with(this));
+    }
+
+    fn visit_mut_class_method(&mut self, n: &mut ClassMethod) {
+        let frame = self.frame(|| context::prop_name(&n.key));
+        self.in_frame(frame, |this| n.visit_mut_children_with(this));
+    }
+
+    fn visit_mut_private_method(&mut self, n: &mut PrivateMethod) {
+        let frame = self.frame(|| Some(format!("#{}", n.key.name)));
+        self.in_frame(frame, |this| n.visit_mut_children_with(this));
+    }
+
+    fn visit_mut_constructor(&mut self, n: &mut Constructor) {
+        if let Some(body) = &n.body {
+            self.mark_prologue(&body.stmts);
+        }
+        let frame = self.frame(|| Some("constructor".to_string()));
+        self.in_frame(frame, |this| n.visit_mut_children_with(this));
+    }
+
+    fn visit_mut_method_prop(&mut self, n: &mut MethodProp) {
+        let frame = self.frame(|| context::prop_name(&n.key));
+        self.in_frame(frame, |this| n.visit_mut_children_with(this));
+    }
+
+    fn visit_mut_jsx_element(&mut self, n: &mut JSXElement) {
+        let frame = self.frame(|| Some(context::jsx_name(&n.opening.name)));
+        self.in_frame(frame, |this| n.visit_mut_children_with(this));
+    }
+
+    fn visit_mut_jsx_element_childs(&mut self, n: &mut Vec<JSXElementChild>) {
+        if !self.opts.jsx_text {
+            n.visit_mut_children_with(self);
+            return;
+        }
+        // 按顺序逐个处理，映射表中的文本与嵌套元素中的字符串保持源码中的先后
+        let mut out = Vec::with_capacity(n.len());
+        for mut child in n.drain(..) {
+            match child {
+                JSXElementChild::JSXText(text) => self.extract_jsx_text(text, &mut out),
+                _ => {
+                    child.visit_mut_with(self);
+                    out.push(child);
+                }
+            }
+        }
+        *n = out;
+    }
+
+    fn visit_mut_str(&mut self, n: &mut Str) {
+        let Some(key) = self.extract_str(n) else {
+            return;
+        };
+
+        // 沿用原来的引号（' 或 "），diff 中只有字面量的内容发生变化
+        let quoted = text::quote_js(&key, self.quote_char(n.span), false);
+        self.push_edit(n.span, quoted.clone());
+
+        n.value = key.into();
+        n.raw = Some(quoted.into());
+    }
+
+    // --- wrap_call：表达式位置的字符串写成查表调用 S(0) ---
+
+    fn visit_mut_expr(&mut self, n: &mut Expr) {
+        let opts = self.opts;
+        let (Some(name), Expr::Lit(Lit::Str(s)), false) = (&opts.wrap_call, &*n, self.no_wrap)
+        else {
+            n.visit_mut_children_with(self);
+            return;
+        };
+        let span = s.span;
+        if let Some(key) = self.extract_str(s) {
+            let arg = wrap_arg_text(&key, self.quote_char(span));
+            self.push_edit(span, format!("{}({})", name, arg));
+            *n = wrap_call(name, &key, span);
+        }
+    }
+
+    fn visit_mut_jsx_attr_value(&mut self, n: &mut JSXAttrValue) {
+        // `title="你好"` 写成 `title={S(0)}`
+        let opts = self.opts;
+        let (Some(name), JSXAttrValue::Str(s)) = (&opts.wrap_call, &*n) else {
+            n.visit_mut_children_with(self);
+            return;
+        };
+        let span = s.span;
+        if let Some(key) = self.extract_str(s) {
+            let arg = wrap_arg_text(&key, self.quote_char(span));
+            self.push_edit(span, format!("{{{}({})}}", name, arg));
+            *n = JSXAttrValue::JSXExprContainer(JSXExprContainer {
+                span,
+                expr: JSXExpr::Expr(Box::new(wrap_call(name, &key, span))),
+            });
+        }
+    }
+
+    fn visit_mut_tpl_element(&mut self, n: &mut TplElement) {
+        // 模板字符串的静态部分默认不替换
+        if !self.opts.include_template_quasis {
+            return;
+        }
+        // cooked 为 None 说明含有非法转义，此时退回使用 raw；
+        // 含有孤立代理项时记录近似文本，同时保留 raw 以便无损还原
+        let (original, lossy) = match &n.cooked {
+            Some(cooked) => match cooked.as_str() {
+                Some(s) => (s.to_string(), false),
+                None => (cooked.to_string_lossy().into_owned(), true),
+            },
+            None => (n.raw.to_string(), false),
+        };
+        let normalized = self.normalize(&original);
+        let changed = normalized.is_some();
+        let original = normalized.unwrap_or(original);
+        // 空的静态段（如 `${a}${b}` 之间，或规范化后只剩空白的段）没有可翻译的内容
+        if original.is_empty() || !self.should_extract(&original) || self.is_ignored(n.span) {
+            return;
+        }
+        let raw = (lossy || changed || self.opts.keep_raw).then(|| n.raw.to_string());
+        if lossy && !self.accept_lossy(n.span, &n.raw) {
+            return;
+        }
+
+        // codegen 使用 raw 输出模板内容；key 只含数字/十六进制字符，无需转义
+        let key = self.record(original, raw, lossy, n.span);
+        // TplElement 的 span 只覆盖静态文本本身，不含 ` 与 ${ }
+        self.push_edit(n.span, key.clone());
+        n.cooked = Some(key.clone().into());
+        n.raw = key.into();
+    }
+}
+
+/// 查找与生成的 key 相同、但本身没有被替换的字符串字面量或模板静态段（见 [`find_key_collision`]）
+struct KeyCollisionFinder<'a> {
+    keys: &'a HashSet<String>,
+    /// 已替换的字符串的起始位置
+    recorded: &'a HashSet<BytePos>,
+    found: Option<(Span, String)>,
+}
+
+impl KeyCollisionFinder<'_> {
+    fn check(&mut self, span: Span, value: Option<&str>) {
+        if self.found.is_some() || self.recorded.contains(&span.lo) {
+            return;
+        }
+        if let Some(value) = value.filter(|v| self.keys.contains(*v)) {
+            self.found = Some((span, value.to_string()));
+        }
+    }
+}
+
+impl Visit for KeyCollisionFinder<'_> {
+    fn visit_str(&mut self, n: &Str) {
+        self.check(n.span, n.value.as_str());
+    }
+
+    fn visit_tpl_element(&mut self, n: &TplElement) {
+        self.check(n.span, Some(&n.raw));
+    }
+}
+
+/// 替换后的模块中第一个与 key 相同的未替换字面量（行号与 key）
+///
+/// restore 与 apply 按值查表，分不出哪些字面量是替换出来的（如 `--only-cjk` 时留下的 `f("0")`
+/// 与 `"你好"` 的 key `"0"`），只能在提取时拒绝。
+fn find_key_collision(module: &Module, replacer: &StringReplacer) -> Option<(usize, String)> {
+    let mut finder = KeyCollisionFinder {
+        keys: &replacer.keys,
+        recorded: &replacer.recorded,
+        found: None,
+    };
+    module.visit_with(&mut finder);
+    finder
+        .found
+        .map(|(span, key)| (replacer.location(span).line, key))
+}
+
+/// 把常量字符串拼接（`"你" + "好" + "！"`）合并为一个字面量（`fold_concat`），
+/// 译者看到的是整句而不是片段；合并后字面量的 span 覆盖整个拼接表达式，按 span 替换时整个表达式换成 key。
+/// 只合并全部由字符串字面量组成的 `+` 链，含有变量或括号的拼接不动；
+/// 合并后没有被替换的拼接由 [`ConcatUnfolder`] 恢复原样
+#[derive(Default)]
+struct ConcatFolder {
+    /// 合并后字面量的位置与原来的段数
+    folded: HashMap<Span, usize>,
+    /// 合并前的拼接表达式
+    originals: HashMap<Span, Expr>,
+}
+
+/// 表达式若是只由字符串字面量组成的 `+` 链，按顺序收集各段的值
+fn concat_parts(e: &Expr, parts: &mut Vec<String>) -> bool {
+    match e {
+        Expr::Lit(Lit::Str(s)) => match s.value.as_str() {
+            Some(value) => {
+                parts.push(value.to_string());
+                true
+            }
+            None => false,
+        },
+        Expr::Bin(b) if b.op == BinaryOp::Add => {
+            concat_parts(&b.left, parts) && concat_parts(&b.right, parts)
+        }
+        _ => false,
+    }
+}
+
+impl VisitMut for ConcatFolder {
+    fn visit_mut_expr(&mut self, n: &mut Expr) {
+        let mut parts = Vec::new();
+        if let Expr::Bin(bin) = &*n
+            && concat_parts(n, &mut parts)
+        {
+            let span = bin.span;
+            self.folded.insert(span, parts.len());
+            let folded = Expr::Lit(Lit::Str(Str {
+                span,
+                value: parts.concat().into(),
+                raw: None,
+            }));
+            self.originals.insert(span, std::mem::replace(n, folded));
+            return;
+        }
+        n.visit_mut_children_with(self);
+    }
+}
+
+/// 把合并后没有被替换的字面量（如 `--only-cjk` 时的 `"btn-" + "primary"`）恢复为原来的拼接
+struct ConcatUnfolder<'a> {
+    /// 合并前的拼接表达式（见 [`ConcatFolder`]）
+    originals: HashMap<Span, Expr>,
+    /// 已替换的字符串的起始位置
+    recorded: &'a HashSet<BytePos>,
+}
+
+impl VisitMut for ConcatUnfolder<'_> {
+    fn visit_mut_expr(&mut self, n: &mut Expr) {
+        if let Expr::Lit(Lit::Str(s)) = &*n
+            && !self.recorded.contains(&s.span.lo)
+            && let Some(original) = self.originals.remove(&s.span)
+        {
+            *n = original;
+            return;
+        }
+        n.visit_mut_children_with(self);
+    }
+}
+
+/// 对原始源码的一处文本编辑（字节偏移，左闭右开）
+#[derive(Debug)]
+struct Edit {
+    lo: usize,
+    hi: usize,
+    text: String,
+}
+
+/// 按偏移顺序把编辑应用到源码上，其余部分（缩进、空行、引号、注释）原样保留
+fn apply_edits(src: &str, mut edits: Vec<Edit>) -> String {
+    // 同一位置的插入（如导入语句）排在替换之前
+    edits.sort_by_key(|e| (e.lo, e.hi));
+
+    let mut out = String::with_capacity(src.len());
+    let mut last = 0;
+    for edit in edits {
+        out.push_str(&src[last..edit.lo]);
+        out.push_str(&edit.text);
+        last = edit.hi;
+    }
+    out.push_str(&src[last..]);
+    out
+}
+
+/// 还原器：按映射表把索引字符串替换回原始字符串（或译文）
+struct StringRestorer<'a> {
+    map: &'a Map<String, Value>,
+    /// 替换为译文而不是原始字符串
+    translate: bool,
+    /// 提取时使用的查表函数名（`--wrap-call`），其调用还原为字符串字面量
+    wrap_call: Option<&'a str>,
+    cm: &'a SourceMap,
+    /// 组件文件与 `preserve_format` 时要应用到原文件上的文本编辑（其他文件为 `None`，由 codegen 输出）
+    edits: Option<Vec<Edit>>,
+    restored: usize,
+    untranslated: usize,
+}
+
+impl<'a> StringRestorer<'a> {
+    fn new(
+        map: &'a Map<String, Value>,
+        translate: bool,
+        wrap_call: Option<&'a str>,
+        cm: &'a SourceMap,
+    ) -> Self {
+        Self {
+            map,
+            translate,
+            wrap_call,
+            cm,
+            edits: None,
+            restored: 0,
+            untranslated: 0,
+        }
+    }
+
+    /// 记录一处文本编辑：把 span 覆盖的源码替换为 text（只在按 span 还原时记录）
+    fn push_edit(&mut self, span: Span, text: impl FnOnce() -> String) {
+        if self.edits.is_none() {
+            return;
+        }
+        let lo = self.cm.lookup_byte_offset(span.lo).pos.0 as usize;
+        let hi = self.cm.lookup_byte_offset(span.hi).pos.0 as usize;
+        if let Some(edits) = &mut self.edits {
+            edits.push(Edit {
+                lo,
+                hi,
+                text: text(),
+            });
+        }
+    }
+
+    /// 还原组件中被替换的纯文本：文本就是 key，或者是 wrap_call 形式的 `{S(0)}`
+    fn restore_markup_texts(&mut self, src: &str, texts: &[Range<usize>]) {
+        for range in texts {
+            let text = &src[range.clone()];
+            let key = match self.wrap_call {
+                Some(name) => text
+                    .strip_prefix('{')
+                    .and_then(|t| t.strip_prefix(name))
+                    .and_then(|t| t.strip_prefix('('))
+                    .and_then(|t| t.strip_suffix(")}"))
+                    .and_then(|arg| serde_json::from_str::<Value>(arg).ok())
+                    .and_then(|arg| match arg {
+                        Value::Number(n) => Some(n.to_string()),
+                        Value::String(s) => Some(s),
+                        _ => None,
+                    }),
+                None => Some(text.to_string()),
+            };
+            let Some((original, _)) = key.and_then(|key| self.lookup(&key)) else {
+                continue;
+            };
+            let original = original.to_string();
+            if let Some(edits) = &mut self.edits {
+                edits.push(Edit {
+                    lo: range.start,
+                    hi: range.end,
+                    text: original,
+                });
+            }
+            self.restored += 1;
+        }
+    }
+
+    /// 还原 JSON 数据文件中选中的字符串值；没有原始字面量时写成 JSON 字符串
+    fn restore_json_strings(&mut self, strs: &[&Str]) {
+        for s in strs {
+            let Some((original, raw)) = s.value.as_str().and_then(|key| self.lookup(key)) else {
+                continue;
+            };
+            self.push_edit(s.span, || {
+                raw.map_or_else(
+                    || Value::String(original.to_string()).to_string(),
+                    str::to_string,
+                )
+            });
+            self.restored += 1;
+        }
+    }
+
+    /// 取 key 对应的替换文本与原始字面量（若有）；translate 模式下没有译文时退回原文
+    fn lookup(&mut self, key: &str) -> Option<(&'a str, Option<&'a str>)> {
+        let value = self.map.get(key)?;
+        if self.translate {
+            match merge::translation_of(value).filter(|t| !t.is_empty()) {
+                Some(translation) => return Some((translation, None)),
+                None => self.untranslated += 1,
+            }
+        }
+        Some((entry_text(value)?, entry_raw(value)))
+    }
+}
+
+impl VisitMut for StringRestorer<'_> {
+    fn visit_mut_str(&mut self, n: &mut Str) {
+        // 只还原映射表中存在的索引，其余字符串保持原样
+        let Some(key) = n.value.as_str() else {
+            return;
+        };
+        if let Some((original, raw)) = self.lookup(key) {
+            // 有原始字面量时直接使用（codegen 优先输出 raw），保证无损还原；
+            // 否则沿用替换后文件中的引号
+            n.raw = match (raw, n.raw.as_deref().and_then(|r| r.chars().next())) {
+                (Some(raw), _) => Some(raw.into()),
+                (None, Some(quote)) => Some(text::quote_js(original, quote, false).into()),
+                (None, None) => None,
+            };
+            n.value = original.into();
+            let written = n.raw.as_deref().map(str::to_string);
+            self.push_edit(n.span, || {
+                written.unwrap_or_else(|| text::quote_js(original, '"', false))
+            });
+            self.restored += 1;
+        }
+    }
+
+    fn visit_mut_expr(&mut self, n: &mut Expr) {
+        // `S(0)` 还原为字符串字面量；要在访问子节点之前判断，
+        // 否则 `S("battle.3")` 的参数会先被当作普通字符串还原
+        let Some(key) = self.wrap_call.and_then(|name| unwrap_call(n, name)) else {
+            n.visit_mut_children_with(self);
+            return;
+        };
+        match self.lookup(&key) {
+            Some((original, raw)) => {
+                let span = n.span();
+                *n = Expr::Lit(Lit::Str(Str {
+                    span,
+                    value: original.into(),
+                    raw: raw.map(Into::into),
+                }));
+                self.push_edit(span, || {
+                    raw.map_or_else(|| text::quote_js(original, '"', false), str::to_string)
+                });
+                self.restored += 1;
+            }
+            None => n.visit_mut_children_with(self),
+        }
+    }
+
+    fn visit_mut_jsx_attr_value(&mut self, n: &mut JSXAttrValue) {
+        // `title={S(0)}` 还原为 `title="你好"`
+        let unwrapped = match (&*n, self.wrap_call) {
+            (JSXAttrValue::JSXExprContainer(c), Some(name)) => match &c.expr {
+                JSXExpr::Expr(e) => unwrap_call(e, name).map(|key| (key, c.span)),
+                JSXExpr::JSXEmptyExpr(_) => None,
+            },
+            _ => None,
+        };
+        let Some((key, span)) = unwrapped else {
+            n.visit_mut_children_with(self);
+            return;
+        };
+        match self.lookup(&key) {
+            Some((original, raw)) => {
+                *n = JSXAttrValue::Str(Str {
+                    span,
+                    value: original.into(),
+                    raw: raw.map(Into::into),
+                });
+                self.push_edit(span, || {
+                    raw.map_or_else(|| text::quote_js(original, '"', false), str::to_string)
+                });
+                self.restored += 1;
+            }
+            None => n.visit_mut_children_with(self),
+        }
+    }
+
+    fn visit_mut_tpl_element(&mut self, n: &mut TplElement) {
+        // 由 --include-template-quasis 替换过的模板静态段，raw 就是 key
+        if let Some((original, raw)) = self.lookup(&n.raw) {
+            n.cooked = Some(original.into());
+            n.raw = match raw {
+                Some(raw) => raw.into(),
+                None => escape_tpl_raw(original).into(),
+            };
+            let written = n.raw.to_string();
+            self.push_edit(n.span, || written);
+            self.restored += 1;
+        }
+    }
+}
+
+/// 把文本转义为模板字符串的 raw 内容（转义 `\`、`` ` `` 与 `${`）
+fn escape_tpl_raw(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('`', "\\`")
+        .replace("${", "\\${")
+}
+
+/// key 的生成方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyMode {
+    /// 按出现顺序递增的索引 "0","1",...
+    #[default]
+    Index,
+    /// 由文件路径与字符串内容计算的 xxhash64（16 位十六进制），在增删其他字符串时保持不变
+    Hash,
+}
+
+/// 索引模式下索引的写法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IndexFormat {
+    /// 十进制 "7"
+    #[default]
+    Decimal,
+    /// 补零到指定位数的十进制，如宽度 4 时为 "0007"，key 按字符串排序时顺序正确
+    Padded(usize),
+    /// 小写十六进制 "1f"
+    Hex,
+    /// base62（`0-9A-Za-z`）"Z"，key 最短
+    Base62,
+}
+
+impl IndexFormat {
+    /// 把索引写成字符串
+    pub fn format(self, n: usize) -> String {
+        match self {
+            IndexFormat::Decimal => n.to_string(),
+            IndexFormat::Padded(width) => format!("{:0width$}", n, width = width),
+            IndexFormat::Hex => format!("{:x}", n),
+            IndexFormat::Base62 => {
+                const DIGITS: &[u8; 62] =
+                    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+                let mut n = n;
+                let mut out = Vec::new();
+                loop {
+                    out.push(DIGITS[n % 62]);
+                    n /= 62;
+                    if n == 0 {
+                        break;
+                    }
+                }
+                out.reverse();
+                String::from_utf8(out).expect("base62 字符均为 ASCII")
+            }
+        }
+    }
+}
+
+/// key 模板，如 `{file}:{line}:{n}`
+///
+/// 可用的变量：
+///   - `{file}`：源文件名（不含扩展名）
+///   - `{line}`：字符串所在的行号（从 1 开始）
+///   - `{n}`：计数器（与索引模式相同，受 `start_index` 与 `index_format` 影响）
+///   - `{hash}`：文件路径与字符串内容的 xxhash64 的前 8 位
+///
+/// 生成的 key 重复时（如模板不含 `{n}`，同一行有两个字符串）追加 `~2`、`~3` 区分。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyTemplate {
+    parts: Vec<TemplatePart>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplatePart {
+    Literal(String),
+    File,
+    Line,
+    Counter,
+    Hash,
+}
+
+impl FromStr for KeyTemplate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut rest = s;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(TemplatePart::Literal(rest[..start].to_string()));
+            }
+            let Some(len) = rest[start..].find('}') else {
+                return Err(format!("key 模板 {} 中的 {{ 没有闭合", s));
+            };
+            parts.push(match &rest[start + 1..start + len] {
+                "file" => TemplatePart::File,
+                "line" => TemplatePart::Line,
+                "n" => TemplatePart::Counter,
+                "hash" => TemplatePart::Hash,
+                other => {
+                    return Err(format!(
+                        "key 模板中有未知的变量 {{{}}}（可选 {{file}} / {{line}} / {{n}} / {{hash}}）",
+                        other
+                    ));
+                }
+            });
+            rest = &rest[start + len + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(TemplatePart::Literal(rest.to_string()));
+        }
+        if parts.is_empty() {
+            return Err("key 模板不能为空".to_string());
+        }
+        Ok(Self { parts })
+    }
+}
+
+/// key 的命名空间前缀
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyNamespace {
+    /// 以文件名（不含扩展名）为前缀，如 "battle.12"
+    File,
+}
+
+/// 字符串字面量的引号
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuoteStyle {
+    /// 单引号 `'...'`
+    Single,
+    /// 双引号 `"..."`
+    Double,
+}
+
+impl QuoteStyle {
+    fn char(self) -> char {
+        match self {
+            QuoteStyle::Single => '\'',
+            QuoteStyle::Double => '"',
+        }
+    }
+}
+
+/// 输出代码的换行符
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Newline {
+    /// 与输入一致（按输入中占多数的换行符判断）
+    #[default]
+    Auto,
+    /// `\n`
+    Lf,
+    /// `\r\n`
+    Crlf,
+}
+
+impl Newline {
+    /// 文本中占多数的换行符（没有换行时为 LF）
+    pub fn detect(text: &str) -> Self {
+        let lf = text.matches('\n').count();
+        let crlf = text.matches("\r\n").count();
+        if crlf * 2 > lf {
+            Newline::Crlf
+        } else {
+            Newline::Lf
+        }
+    }
+
+    /// `Auto` 时按 `src` 判断
+    pub fn resolve(self, src: &str) -> Self {
+        match self {
+            Newline::Auto => Newline::detect(src),
+            other => other,
+        }
+    }
+
+    /// 换行符本身（`Auto` 视为 LF）
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Newline::Crlf => "\r\n",
+            Newline::Lf | Newline::Auto => "\n",
+        }
+    }
+
+    /// 把文本中的换行统一为该换行符（`Auto` 时原样返回）
+    fn normalize(self, text: String) -> String {
+        match self {
+            Newline::Auto => text,
+            Newline::Lf => text.replace("\r\n", "\n"),
+            Newline::Crlf => text.replace("\r\n", "\n").replace('\n', "\r\n"),
+        }
+    }
+}
+
+/// UTF-8 BOM：输入带有时输出也带上
+const BOM: char = '\u{feff}';
+
+/// 代码生成选项，用来让输出代码的风格与项目一致
+#[derive(Debug, Clone)]
+pub struct CodegenOptions {
+    /// 输出代码的目标版本（影响转义等写法，不做语法降级），默认 ESNext
+    pub target: EsVersion,
+    /// 压缩输出
+    pub minify: bool,
+    /// 只输出 ASCII 字符，其余字符写成转义序列
+    pub ascii_only: bool,
+    /// 统一字符串字面量的引号；为 `None` 时保留原来的写法，新生成的字符串由 codegen 决定
+    pub quote: Option<QuoteStyle>,
+    /// 省略代码块与文件末尾最后一条语句的分号
+    pub omit_last_semi: bool,
+    /// 换行符，默认与输入一致（Windows 下保存的 CRLF 文件输出仍为 CRLF）
+    pub newline: Newline,
+}
+
+impl Default for CodegenOptions {
+    fn default() -> Self {
+        Self {
+            target: EsVersion::latest(),
+            minify: false,
+            ascii_only: false,
+            quote: None,
+            omit_last_semi: false,
+            newline: Newline::Auto,
+        }
+    }
+}
+
+impl CodegenOptions {
+    /// 转换为 swc codegen 的配置
+    fn config(&self) -> Config {
+        Config::default()
+            .with_target(self.target)
+            .with_minify(self.minify)
+            .with_ascii_only(self.ascii_only)
+            .with_omit_last_semi(self.omit_last_semi)
+    }
+}
+
+/// 还原选项
+#[derive(Debug, Clone, Default)]
+pub struct RestoreOptions {
+    /// 提取时使用的查表函数名（见 [`ExtractOptions::wrap_call`]），此时 `S(0)` 这样的调用同样还原为字符串
+    pub wrap_call: Option<String>,
+    /// 提取时替换了组件中的纯文本（见 [`ExtractOptions::markup_text`]），同样还原
+    pub markup_text: bool,
+    /// 提取 JSON 数据文件时使用的选择器（见 [`ExtractOptions::json_paths`]），只还原选中的值
+    pub json_paths: Vec<JsonPath>,
+    /// 提取时使用了 [`ExtractOptions::preserve_format`]：同样按 span 在替换后的文件上还原，
+    /// 缩进、空行与注释都不变（配合 `keep_raw` 时与原文逐字节一致）
+    pub preserve_format: bool,
+    /// 代码生成选项（组件文件与 `preserve_format` 时按 span 还原，不经过 codegen）
+    pub codegen: CodegenOptions,
+}
+
+/// 源码语法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceSyntax {
+    /// TypeScript（`Syntax::Typescript`）
+    Typescript,
+    /// 普通 JavaScript（`Syntax::Es`）
+    Javascript,
+}
+
+/// 提取选项
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions {
+    /// 源文件路径，仅用于解析错误定位；为 `None` 时使用匿名文件名
+    pub file_name: Option<PathBuf>,
+    /// 是否同时替换类型位置中的字符串（默认跳过）
+    pub include_types: bool,
+    /// 是否跳过 import/export-from/require/动态 import 中的模块路径
+    pub skip_imports: bool,
+    /// 是否把相同的字符串合并为同一个索引（映射表中记录出现次数）
+    pub dedupe: bool,
+    /// key 的生成方式
+    pub key_mode: KeyMode,
+    /// 索引模式下索引的写法
+    pub index_format: IndexFormat,
+    /// key 模板；设置后代替 `key_mode` 与 `namespace` 生成 key
+    pub key_template: Option<KeyTemplate>,
+    /// key 的命名空间前缀；为 `None` 时 key 不带前缀
+    pub namespace: Option<KeyNamespace>,
+    /// 是否启用 JSX（`file_name` 以 `.tsx` 结尾时自动启用）
+    pub jsx: bool,
+    /// 源码语法；为 `None` 时按 `file_name` 的扩展名判断
+    pub syntax: Option<SourceSyntax>,
+    /// 允许旧式装饰器（TypeScript `experimentalDecorators` 与 Babel legacy 的写法）：
+    /// JavaScript 中装饰器也可以写在 `export` 前面（`@Component export class X {}`）。
+    /// TypeScript 总是允许，装饰器本身总是启用
+    pub legacy_decorators: bool,
+    /// 按声明文件（`.d.ts`）的规则解析；为 `None` 时按 `file_name` 判断（见 [`is_declaration_file`]）
+    pub dts: Option<bool>,
+    /// 容错解析：swc 能够恢复的语法错误（机器导出的脚本中常见的小错误）不中止处理，
+    /// 记录在 [`ExtractResult::warnings`] 中；默认遇到即报错
+    pub tolerant: bool,
+    /// 是否同时提取模板字符串的静态部分（quasis），并把它们替换为各自的 key
+    pub include_template_quasis: bool,
+    /// 带标签的模板（`` sql`...` ``、`` gql`...` ``）中的内容总是跳过，只有这些标签（如 `t`、`i18n.t`）除外：
+    /// 其中的静态部分（需同时设置 `include_template_quasis`）与 `${}` 中的字符串照常替换
+    pub include_tags: Vec<String>,
+    /// 只替换包含中日韩文字的字符串
+    pub only_cjk: bool,
+    /// 只替换匹配该正则的字符串（与 `only_cjk` 同时设置时需同时满足）
+    pub pattern: Option<Regex>,
+    /// 只替换字符数不少于该值的字符串
+    pub min_len: Option<usize>,
+    /// 只替换字符数不超过该值的字符串
+    pub max_len: Option<usize>,
+    /// 不替换这些字符串：按 [`string_hash`]（文件路径 + 内容）列出，来自 `review` 中排除的字符串
+    pub excluded: BTreeSet<String>,
+    /// 输出代码中去掉注释（默认保留）
+    pub strip_comments: bool,
+    /// 不经过 codegen 重新生成整个模块，而是在原始源码上按 span 替换字面量，
+    /// 缩进、空行、引号风格与注释都与输入完全一致（此时忽略 `strip_comments`）
+    pub preserve_format: bool,
+    /// 在映射表中记录每个字符串的位置（行、列与字节范围）
+    pub with_locations: bool,
+    /// 在映射表中记录每个字符串外层的函数、类、调用表达式或 JSX 元素
+    pub with_context: bool,
+    /// 只替换这些函数/方法调用的参数中的字符串（如 `t`、`i18n.t`、`showText`），为空时不限制
+    pub only_calls: Vec<String>,
+    /// 不替换这些函数/方法调用中的字符串（如 `console.log`、`require`、`fetch`），
+    /// 优先于 `only_calls`
+    pub skip_calls: Vec<String>,
+    /// 启发式地跳过用于比较的字符串：`typeof x === "string"`、`kind === "attack"`、
+    /// `case "attack":` 与 `"hp" in obj`（这些字符串替换后会改变控制流，对译者也没有意义）
+    pub skip_comparisons: bool,
+    /// 同时替换 TS 字符串枚举成员的值（`enum Color { Red = "red" }`），默认跳过
+    pub include_enums: bool,
+    /// 同时替换装饰器中的字符串（`@Component({ selector: "app-x" })`），默认跳过
+    pub include_decorators: bool,
+    /// 同时替换属性名中的字符串（`{"hp": 10}`、`obj["hp"]`），默认跳过
+    pub include_keys: bool,
+    /// 同时替换指令序言（`"use strict"`、`"use client"` 等）中的字符串，默认跳过
+    pub include_directives: bool,
+    /// 同时替换 `.svelte` 组件中标签之间的纯文本（见 [`markup`]；`.html` 页面不替换），文本替换为 key，
+    /// 设置了 `wrap_call` 时替换为 `{S(0)}`
+    pub markup_text: bool,
+    /// 替换之前先把常量字符串拼接（`"你" + "好"`）合并为一个字面量（见 [`ExtractResult::folded`]）
+    pub fold_concat: bool,
+    /// 同时替换 JSX 元素之间的文本（`<p>你好</p>`）：按 JSX 的规则折叠空白后记录，
+    /// 文本换成 `{"0"}`（设置了 `wrap_call` 时为 `{S(0)}`），两端的空白保留
+    pub jsx_text: bool,
+    /// JSON 数据文件（`.json`）中要替换的字符串值的位置（如 `$.dialog[*].text`，见 [`jsonpath`]），
+    /// 为空时替换所有字符串值；成员名总是保留，也不写成查表调用
+    pub json_paths: Vec<JsonPath>,
+    /// 把表达式位置的字符串替换为对该函数的查表调用（如 `S(0)`、`i18n.t("battle.3")`），
+    /// 而不是字符串字面量；key 是十进制索引时参数写成数字。JSX 属性写成 `title={S(0)}`，
+    /// 属性名、TS 枚举成员、类型与模块路径等不能写成调用的位置仍替换为字面量
+    pub wrap_call: Option<String>,
+    /// 查表模块的路径（如 `./battle_strings`）；与普通函数名形式的 `wrap_call` 一起设置时，
+    /// 在模块开头（指令序言之后）插入 `import { S } from "./battle_strings";`
+    pub loader_module: Option<String>,
+    /// 代码生成选项（`preserve_format` 模式下不经过 codegen，只有 `quote` 影响新写入的字面量）
+    pub codegen: CodegenOptions,
+    /// 同时生成替换后代码到源文件的 sourcemap（见 [`ExtractResult::source_map`]）；
+    /// `preserve_format` 模式下行号不变，不生成
+    pub source_map: bool,
+    /// 输入本身的 sourcemap（JSON，输入是由其他工具生成的代码时），生成的 sourcemap 与它串联
+    pub input_source_map: Option<String>,
+    /// 在映射表中同时记录字面量的原始写法（转义序列、引号），还原时逐字节复现
+    pub keep_raw: bool,
+    /// 去掉字符串两端的空白（含换行）后再写入映射表；有变化的记录同时带上原始字面量 `raw`，
+    /// 还原时原样复现。只含空白的字符串不替换
+    pub trim: bool,
+    /// 把字符串中连续的空白（含换行）合并为一个空格后再写入映射表，原始字面量同样记录在 `raw` 中
+    pub collapse_whitespace: bool,
+    /// 遇到含有孤立代理项、无法无损转换为 UTF-8 的字符串时报错，而不是记录近似文本
+    pub strict: bool,
+    /// 索引模式下的起始索引（多个文件共用一个计数器时由调用方接续）
+    pub start_index: usize,
+}
+
+/// 映射表中的一条记录
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// 替换后代码中使用的索引
+    pub key: String,
+    /// 原始字符串
+    pub original: String,
+    /// 原始字面量（字符串含引号，模板静态段不含反引号）。字符串含有孤立代理项、
+    /// 无法无损转换为 UTF-8 时总是记录（此时 `original` 中的孤立代理项被替换为 U+FFFD），
+    /// 空白被 `trim` / `collapse_whitespace` 规范化过时也总是记录，否则只在 `keep_raw` 时记录；
+    /// 还原时优先使用，保证逐字节还原
+    pub raw: Option<String>,
+    /// 出现次数（仅 dedupe 模式下记录）
+    pub count: Option<usize>,
+    /// 共用这个 key 的出现次数（dedupe 与 hash 模式下可能大于 1），v2 映射表中记录
+    pub occurrences: usize,
+    /// 来源文件（多个文件合并到一张映射表时记录）
+    pub file: Option<String>,
+    /// 第一次出现的行号（从 1 开始），v2 映射表中记录
+    pub first_line: usize,
+    /// 每次出现的位置（仅 `with_locations` 时记录）
+    pub locations: Vec<Location>,
+    /// 外层的函数、类、调用表达式等，如 `Battle > start > showDialog(...)`
+    /// （仅 `with_context` 时记录；dedupe 模式下取第一次出现的位置）
+    pub context: Option<String>,
+    /// 写给译者的说明，来自字符串前面的 `// i18n: ...` 或 `/* note: ... */` 注释
+    /// （dedupe 模式下取第一条）
+    pub note: Option<String>,
+}
+
+/// 字符串在源文件中的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    /// 行号（从 1 开始）
+    pub line: usize,
+    /// 列号（从 1 开始，按字符计）
+    pub column: usize,
+    /// 起始字节偏移（含引号）
+    pub start: usize,
+    /// 结束字节偏移（不含）
+    pub end: usize,
+}
+
+impl Location {
+    fn to_value(self) -> Value {
+        let mut obj = Map::new();
+        obj.insert("line".to_string(), Value::from(self.line));
+        obj.insert("column".to_string(), Value::from(self.column));
+        obj.insert("start".to_string(), Value::from(self.start));
+        obj.insert("end".to_string(), Value::from(self.end));
+        Value::Object(obj)
+    }
+}
+
+impl Entry {
+    /// 是否带有原始字符串以外的附加信息
+    fn has_metadata(&self) -> bool {
+        self.count.is_some()
+            || self.raw.is_some()
+            || self.file.is_some()
+            || !self.locations.is_empty()
+            || self.context.is_some()
+            || self.note.is_some()
+    }
+
+    /// 序列化为映射表中的值：没有附加信息时是原始字符串本身，
+    /// 否则是形如 {"text": "原始", "count": 3, "file": "src/a.ts"} 的对象
+    pub fn to_value(&self) -> Value {
+        if !self.has_metadata() {
+            return Value::String(self.original.clone());
+        }
+        let mut obj = Map::new();
+        obj.insert("text".to_string(), Value::String(self.original.clone()));
+        if let Some(raw) = &self.raw {
+            obj.insert("raw".to_string(), Value::String(raw.clone()));
+        }
+        if let Some(count) = self.count {
+            obj.insert("count".to_string(), Value::from(count));
+        }
+        if let Some(file) = &self.file {
+            obj.insert("file".to_string(), Value::String(file.clone()));
+        }
+        if !self.locations.is_empty() {
+            let locations = self.locations.iter().map(|l| l.to_value()).collect();
+            obj.insert("locations".to_string(), Value::Array(locations));
+        }
+        if let Some(context) = &self.context {
+            obj.insert("context".to_string(), Value::String(context.clone()));
+        }
+        if let Some(note) = &self.note {
+            obj.insert("note".to_string(), Value::String(note.clone()));
+        }
+        Value::Object(obj)
+    }
+
+    /// 序列化为 v2 映射表中的值：总是对象，带有出现次数 `count`、来源文件 `files`
+    /// 与第一次出现的行号 `first_line`，其余附加信息与 [`Entry::to_value`] 相同
+    ///
+    /// `source` 为条目所在的源文件，条目自带 `file` 时以条目为准。
+    pub fn to_value_v2(&self, source: Option<&str>) -> Value {
+        let mut obj = match self.to_value() {
+            Value::Object(obj) => obj,
+            text => Map::from_iter([("text".to_string(), text)]),
+        };
+        obj.insert("count".to_string(), Value::from(self.occurrences));
+        obj.remove("file");
+        let files = self
+            .file
+            .as_deref()
+            .or(source)
+            .map(|f| Value::String(f.to_string()));
+        obj.insert(
+            "files".to_string(),
+            Value::Array(files.into_iter().collect()),
+        );
+        obj.insert("first_line".to_string(), Value::from(self.first_line));
+        Value::Object(obj)
+    }
+}
+
+/// 取映射表中一条记录的原始文本，兼容纯字符串与 {"text": ...} 两种形式
+pub fn entry_text(v: &Value) -> Option<&str> {
+    match v {
+        Value::String(s) => Some(s),
+        Value::Object(obj) => obj.get("text").and_then(Value::as_str),
+        _ => None,
+    }
+}
+
+/// 取映射表中一条记录的原始字面量（只有对象形式的记录可能带有 `"raw"`）
+pub fn entry_raw(v: &Value) -> Option<&str> {
+    match v {
+        Value::Object(obj) => obj.get("raw").and_then(Value::as_str),
+        _ => None,
+    }
+}
+
+/// 提取结果
+#[derive(Debug, Clone)]
+pub struct ExtractResult {
+    /// 替换后的代码
+    pub code: String,
+    /// 按索引顺序排列的映射记录
+    pub entries: Vec<Entry>,
+    /// 替换后代码到源文件的 sourcemap（JSON），仅在设置了 `source_map` 时生成
+    pub source_map: Option<String>,
+    /// 容错解析时跳过的语法错误（见 [`ExtractOptions::tolerant`]）
+    pub warnings: Vec<ParseWarning>,
+    /// 合并后替换了的字符串拼接（见 [`ExtractOptions::fold_concat`]）
+    pub folded: Vec<FoldedConcat>,
+    /// 被 `+` 拼接或 `.join("")` 拆成片段、且其中有字符串被替换了的句子，按位置排列。
+    /// 映射表中只有无法单独翻译的片段，应改写为带占位符的整句（或使用 `fold_concat`）
+    pub fragments: Vec<Fragment>,
+}
+
+/// 被拆成片段的一个句子
+#[derive(Debug, Clone)]
+pub struct Fragment {
+    /// 整个表达式的位置
+    pub location: Location,
+    /// 拆开的写法
+    pub kind: FragmentKind,
+    /// 其中被替换了的字符串个数
+    pub strings: usize,
+    /// 整个表达式的源码
+    pub source: String,
+}
+
+/// 句子被拆开的写法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FragmentKind {
+    /// `"你有 " + n + " 个金币"`
+    Concat,
+    /// `["你有 ", n, " 个金币"].join("")`
+    Join,
+}
+
+/// 合并后替换了的一处字符串拼接
+#[derive(Debug, Clone)]
+pub struct FoldedConcat {
+    /// 整个拼接表达式的位置
+    pub location: Location,
+    /// 原来的段数
+    pub parts: usize,
+    /// 合并后的文本
+    pub text: String,
+}
+
+/// 容错解析时跳过的一处语法错误
+#[derive(Debug, Clone)]
+pub struct ParseWarning {
+    /// 出错位置的行号（从 1 开始）
+    pub line: usize,
+    /// 出错位置的列号（从 1 开始，按字符计）
+    pub column: usize,
+    /// swc 给出的说明
+    pub message: String,
+}
+
+impl ExtractResult {
+    /// 生成映射表：{"0": "原始0", "1": "原始1", ...}
+    pub fn mapping(&self) -> Map<String, Value> {
+        let mut map = Map::new();
+        for entry in &self.entries {
+            map.insert(entry.key.clone(), entry.to_value());
+        }
+        map
+    }
+
+    /// 生成格式化后的映射表 JSON 文本（key 按自然顺序排列，见 [`to_canonical_json`]）
+    pub fn mapping_json(&self) -> serde_json::Result<String> {
+        Ok(to_canonical_json(
+            &Value::Object(self.mapping()),
+            JsonStyle::default(),
+        ))
+    }
+}
+
+/// 还原结果
+#[derive(Debug, Clone)]
+pub struct RestoreResult {
+    /// 还原后的代码
+    pub code: String,
+    /// 实际还原的字符串个数
+    pub restored: usize,
+    /// 没有译文、退回原文的字符串个数（仅 [`apply_translations`]）
+    pub untranslated: usize,
+}
+
+/// 库的错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// 解析源码失败
+    #[error("解析源文件失败（第 {line} 行第 {column} 列）: {}", .error.kind().msg())]
+    Parse {
+        /// 出错位置的行号（从 1 开始）
+        line: usize,
+        /// 出错位置的列号（从 1 开始，按字符计）
+        column: usize,
+        /// swc 给出的错误
+        error: swc_core::ecma::parser::error::Error,
+    },
+    /// 生成代码失败
+    #[error("生成代码失败: {0}")]
+    Emit(#[source] std::io::Error),
+    /// 生成的代码不是合法 UTF-8
+    #[error("输出编码转换失败: {0}")]
+    Utf8(#[source] FromUtf8Error),
+    /// strict 模式下遇到无法无损写入映射表的字符串（含有孤立代理项）
+    #[error("第 {line} 行的字符串含有孤立的 UTF-16 代理项，无法无损写入映射表: {raw}")]
+    InvalidString {
+        /// 所在行号（从 1 开始）
+        line: usize,
+        /// 原始字面量
+        raw: String,
+    },
+    /// 生成 sourcemap 失败
+    #[error("生成 sourcemap 失败: {0}")]
+    SourceMap(String),
+    /// 没有替换的字符串与生成的 key 相同：还原或应用译文时会被误当作 key 换掉
+    #[error(
+        "第 {line} 行没有替换的字符串 {key:?} 与生成的 key 相同，还原时会被误换；请换一种 key 的生成方式（如 hash 或带前缀的 key）"
+    )]
+    KeyCollision {
+        /// 所在行号（从 1 开始）
+        line: usize,
+        /// 重复的 key
+        key: String,
+    },
+    /// 编码映射表失败（二进制格式）
+    #[error("生成 {format} 映射表失败: {message}")]
+    MapRender {
+        /// 映射表格式
+        format: MapFormat,
+        /// 编码器给出的错误
+        message: String,
+    },
+}
+
+/// 取文件扩展名
+fn file_ext(file_name: Option<&Path>) -> Option<&str> {
+    file_name
+        .and_then(|p| p.extension())
+        .and_then(|s| s.to_str())
+}
+
+/// 是否为 TypeScript 声明文件（`.d.ts`、`.d.mts`、`.d.cts`）
+pub fn is_declaration_file(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    [".d.ts", ".d.mts", ".d.cts"]
+        .iter()
+        .any(|ext| name.ends_with(ext))
+}
+
+/// 确定解析所用的语法：显式指定优先，否则按扩展名判断（`.js`/`.mjs`/`.cjs` 为 JavaScript，
+/// 其余按 TypeScript 处理）；`.tsx` 文件总是启用 JSX，`.d.ts` 文件按声明文件解析（`dts` 可以覆盖）
+fn resolve_syntax(
+    file_name: Option<&Path>,
+    syntax: Option<SourceSyntax>,
+    jsx: bool,
+    legacy_decorators: bool,
+    dts: Option<bool>,
+) -> Syntax {
+    let ext = file_ext(file_name);
+    let syntax = syntax.unwrap_or(match ext {
+        Some("js" | "mjs" | "cjs") => SourceSyntax::Javascript,
+        _ => SourceSyntax::Typescript,
+    });
+
+    match syntax {
+        SourceSyntax::Typescript => Syntax::Typescript(TsSyntax {
+            tsx: jsx || ext == Some("tsx"),
+            decorators: true,
+            dts: dts.unwrap_or_else(|| file_name.is_some_and(is_declaration_file)),
+            ..Default::default()
+        }),
+        SourceSyntax::Javascript => Syntax::Es(EsSyntax {
+            jsx,
+            decorators: true,
+            decorators_before_export: legacy_decorators,
+            ..Default::default()
+        }),
+    }
+}
+
+/// 由文件路径与字符串内容计算的 xxhash64（16 位十六进制）：hash 模式下的 key，
+/// 也是 [`decisions`] 中记录取舍时使用的标识
+///
+/// 路径先经过 [`hash_path`] 规范化，`src/a.ts`、`./src/a.ts` 与当前目录下的绝对路径得到相同的 key。
+pub fn string_hash(file_name: Option<&Path>, original: &str) -> String {
+    let file = file_name.map(hash_path).unwrap_or_default();
+    let mut data = Vec::with_capacity(file.len() + 1 + original.len());
+    data.extend_from_slice(file.as_bytes());
+    data.push(0);
+    data.extend_from_slice(original.as_bytes());
+    format!("{:016x}", xxh64(&data, 0))
+}
+
+/// 计算 hash 时使用的路径：按字面去掉 `.`、消去 `..`，当前目录下的绝对路径改为相对路径，
+/// 统一使用 `/` 分隔（保证不同平台上生成的 key 一致）；不访问文件系统，符号链接不展开
+fn hash_path(path: &Path) -> String {
+    let path = match std::env::current_dir() {
+        Ok(cwd) if path.is_absolute() => {
+            let (path, cwd) = (clean_path(path), clean_path(&cwd));
+            match path.strip_prefix(&cwd) {
+                Ok(relative) => relative.to_path_buf(),
+                Err(_) => path,
+            }
+        }
+        _ => clean_path(path),
+    };
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// 按字面规范化路径：去掉 `.`，`..` 与前一级目录相消（开头的 `..` 保留）
+fn clean_path(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match out.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    out.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => out.push(".."),
+            },
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// 是否为 JSON 数据文件（`.json`）：按 [`ExtractOptions::json_paths`] 替换其中的字符串值
+pub fn is_data_file(path: &Path) -> bool {
+    file_ext(Some(path)) == Some("json")
+}
+
+/// 解析 JSON 数据文件：按 JavaScript 表达式解析，得到每个值的 span，
+/// 替换时按 span 在原文件上进行，缩进与成员顺序都不变
+fn parse_json(
+    cm: &Lrc<SourceMap>,
+    file_name: Option<PathBuf>,
+    src: &str,
+) -> Result<Box<Expr>, Error> {
+    let name = match file_name {
+        Some(p) => FileName::Real(p),
+        None => FileName::Anon,
+    };
+    let fm = cm.new_source_file(name.into(), src.to_string());
+    let lexer = Lexer::new(
+        Syntax::Es(EsSyntax::default()),
+        EsVersion::latest(),
+        StringInput::from(&*fm),
+        None,
+    );
+    let mut parser = Parser::new_from(lexer);
+    let parse_error = |error: swc_core::ecma::parser::error::Error| {
+        let loc = cm.lookup_char_pos(error.span().lo);
+        Error::Parse {
+            line: loc.line,
+            column: loc.col.0 + 1,
+            error,
+        }
+    };
+    let expr = parser.parse_expr().map_err(parse_error)?;
+    if let Some(error) = parser.take_errors().into_iter().next() {
+        return Err(parse_error(error));
+    }
+
+    // 表达式之后只能有空白
+    let end = expr.span().hi;
+    let rest = &src[cm.lookup_byte_offset(end).pos.0 as usize..];
+    if let Some(got) = rest.trim_start().chars().next() {
+        let pos = end + BytePos((rest.len() - rest.trim_start().len()) as u32);
+        return Err(parse_error(swc_core::ecma::parser::error::Error::new(
+            Span::new(pos, pos),
+            swc_core::ecma::parser::error::SyntaxError::Unexpected {
+                got: got.to_string(),
+                expected: "JSON 数据结束",
+            },
+        )));
+    }
+    Ok(expr)
+}
+
+/// 按文档顺序收集被 `paths` 选中的字符串值（`paths` 为空时为全部字符串值）；成员名不在其列
+fn json_strings<'e>(expr: &'e Expr, paths: &[JsonPath]) -> Vec<&'e Str> {
+    fn walk<'e>(
+        expr: &'e Expr,
+        path: &mut Vec<PathStep>,
+        paths: &[JsonPath],
+        out: &mut Vec<&'e Str>,
+    ) {
+        match expr {
+            Expr::Lit(Lit::Str(s)) if paths.is_empty() || paths.iter().any(|p| p.matches(path)) => {
+                out.push(s);
+            }
+            Expr::Array(array) => {
+                for (i, elem) in array.elems.iter().enumerate() {
+                    if let Some(elem) = elem {
+                        path.push(PathStep::Index(i));
+                        walk(&elem.expr, path, paths, out);
+                        path.pop();
+                    }
+                }
+            }
+            Expr::Object(object) => {
+                for prop in &object.props {
+                    let PropOrSpread::Prop(prop) = prop else {
+                        continue;
+                    };
+                    let Prop::KeyValue(kv) = &**prop else {
+                        continue;
+                    };
+                    let key = match &kv.key {
+                        PropName::Str(s) => s.value.to_string_lossy().into_owned(),
+                        PropName::Ident(i) => i.sym.to_string(),
+                        PropName::Num(n) => n.value.to_string(),
+                        _ => continue,
+                    };
+                    path.push(PathStep::Key(key));
+                    walk(&kv.value, path, paths, out);
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut out = Vec::new();
+    walk(expr, &mut Vec::new(), paths, &mut out);
+    out
+}
+
+/// 提取 JSON 数据文件中的字符串（见 [`ExtractOptions::json_paths`]），总是按 span 在原文件上替换
+fn extract_json(src: &str, opts: ExtractOptions) -> Result<ExtractResult, Error> {
+    let opts = ExtractOptions {
+        preserve_format: true,
+        source_map: false,
+        ..opts
+    };
+    let cm: Lrc<SourceMap> = Default::default();
+    let expr = parse_json(&cm, opts.file_name.clone(), src)?;
+
+    let mut replacer = StringReplacer::new(&opts, &cm, src, Directives::default());
+    replacer.extract_json_strings(&json_strings(&expr, &opts.json_paths));
+    if let Some((line, raw)) = replacer.invalid.take() {
+        return Err(Error::InvalidString { line, raw });
+    }
+
+    let code = apply_edits(src, std::mem::take(&mut replacer.edits));
+    Ok(ExtractResult {
+        code: opts.codegen.newline.normalize(code),
+        entries: replacer.entries,
+        source_map: None,
+        warnings: Vec::new(),
+        folded: Vec::new(),
+        fragments: Vec::new(),
+    })
+}
+
+/// 按扩展名扫描组件文件（`.svelte`），其他文件为 `None`
+fn markup_document(file_name: Option<&Path>, src: &str) -> Option<Document> {
+    let kind = Markup::from_path(file_name?)?;
+    Some(Document::scan(src, kind))
+}
+
+/// SourceMap 中唯一一个文件的起始位置（`BytePos` 与字节偏移之间相差它）
+fn file_start(cm: &SourceMap) -> BytePos {
+    cm.files().first().map_or(BytePos(0), |file| file.start_pos)
+}
+
+/// 解析源码
+///
+/// swc 遇到能够恢复的语法错误时仍会给出完整的模块：`tolerant` 时这些错误作为警告返回，
+/// 否则把第一个作为解析错误。
+fn parse_source(
+    cm: &Lrc<SourceMap>,
+    file_name: Option<PathBuf>,
+    src: String,
+    syntax: Syntax,
+    comments: Option<&dyn Comments>,
+    tolerant: bool,
+) -> Result<(Module, Vec<ParseWarning>), Error> {
+    // 有真实文件名时使用真实文件名，方便解析错误定位
+    let name = match file_name {
+        Some(p) => FileName::Real(p),
+        None => FileName::Anon,
+    };
+    let fm = cm.new_source_file(name.into(), src);
+
+    // 按最新版本解析，输出目标由 codegen 选项决定
+    let lexer = Lexer::new(
+        syntax,
+        EsVersion::latest(),
+        StringInput::from(&*fm),
+        comments,
+    );
+
+    let mut parser = Parser::new_from(lexer);
+
+    let parse_error = |error: swc_core::ecma::parser::error::Error| {
+        let loc = cm.lookup_char_pos(error.span().lo);
+        Error::Parse {
+            line: loc.line,
+            column: loc.col.0 + 1,
+            error,
+        }
+    };
+    let module = parser.parse_module().map_err(parse_error)?;
+
+    let mut recovered = parser.take_errors().into_iter();
+    if !tolerant {
+        return match recovered.next() {
+            Some(error) => Err(parse_error(error)),
+            None => Ok((module, Vec::new())),
+        };
+    }
+    let warnings = recovered
+        .map(|error| {
+            let loc = cm.lookup_char_pos(error.span().lo);
+            ParseWarning {
+                line: loc.line,
+                column: loc.col.0 + 1,
+                message: error.kind().msg().into_owned(),
+            }
+        })
+        .collect();
+    Ok((module, warnings))
+}
+
+/// 按 `--quote` 统一字符串字面量的引号
+///
+/// 原来就使用该引号的字面量保持原样（保留其中的转义写法）；JSX 属性值不能含转义，不处理。
+struct Requote {
+    quote: char,
+    ascii_only: bool,
+}
+
+impl VisitMut for Requote {
+    fn visit_mut_str(&mut self, n: &mut Str) {
+        if n.raw
+            .as_deref()
+            .is_some_and(|raw| raw.starts_with(self.quote))
+        {
+            return;
+        }
+        // 含有孤立代理项的字符串只能使用原来的写法
+        if let Some(value) = n.value.as_str() {
+            n.raw = Some(text::quote_js(value, self.quote, self.ascii_only).into());
+        }
+    }
+
+    fn visit_mut_jsx_attr_value(&mut self, n: &mut JSXAttrValue) {
+        if !matches!(n, JSXAttrValue::Str(_)) {
+            n.visit_mut_children_with(self);
+        }
+    }
+}
+
+/// 生成代码；`comments` 为 `None` 时去掉注释，给出 `mappings` 时记录 sourcemap 所需的位置对应关系
+///
+/// 换行符按 `codegen.newline` 决定（默认与源码 `src` 一致）；源码带 BOM 时输出也带上（codegen 会丢掉它）。
+fn emit_ts(
+    cm: &Lrc<SourceMap>,
+    module: &mut Module,
+    comments: Option<&dyn Comments>,
+    codegen: &CodegenOptions,
+    src: &str,
+    mappings: Option<&mut Vec<(BytePos, LineCol)>>,
+) -> Result<String, Error> {
+    if let Some(quote) = codegen.quote {
+        module.visit_mut_with(&mut Requote {
+            quote: quote.char(),
+            ascii_only: codegen.ascii_only,
+        });
+    }
+
+    let mut buf = vec![];
+    if src.starts_with(BOM) {
+        let mut bom = [0; 3];
+        buf.extend_from_slice(BOM.encode_utf8(&mut bom).as_bytes());
+    }
+
+    {
+        let newline = codegen.newline.resolve(src).as_str();
+        let writer = JsWriter::new(cm.clone(), newline, &mut buf, mappings);
+
+        let mut emitter = Emitter {
+            cfg: codegen.config(),
+            cm: cm.clone(),
+            comments,
+            wr: writer,
+        };
+
+        emitter.emit_module(module).map_err(Error::Emit)?;
+    }
+
+    String::from_utf8(buf).map_err(Error::Utf8)
+}
+
+/// sourcemap 的生成设置：`sources` 只写文件名（替换后的文件与源文件在同一目录），并内联源码
+struct SourceMapConfig;
+
+impl SourceMapGenConfig for SourceMapConfig {
+    fn file_name_to_source(&self, f: &FileName) -> String {
+        match f {
+            FileName::Real(path) => path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| f.to_string()),
+            _ => f.to_string(),
+        }
+    }
+
+    fn inline_sources_content(&self, _: &FileName) -> bool {
+        true
+    }
+}
+
+/// 由 codegen 记录的位置对应关系生成 sourcemap（JSON）
+///
+/// 给出 `input` 时（输入本身是生成的代码），把结果与它串联，映射直接指回最初的源文件。
+fn render_source_map(
+    cm: &SourceMap,
+    mappings: &[(BytePos, LineCol)],
+    input: Option<&str>,
+) -> Result<String, Error> {
+    let orig = input
+        .map(|json| swc_sourcemap::SourceMap::from_slice(json.as_bytes()))
+        .transpose()
+        .map_err(|e| Error::SourceMap(format!("读取输入的 sourcemap 失败: {}", e)))?;
+    let map = cm.build_source_map(mappings, orig, SourceMapConfig);
+    let mut buf = Vec::new();
+    map.to_writer(&mut buf)
+        .map_err(|e| Error::SourceMap(e.to_string()))?;
+    String::from_utf8(buf).map_err(Error::Utf8)
+}
+
+/// 解析源码，替换所有普通字符串字面量，并返回替换后的代码与原始字符串列表
+pub fn extract_strings(src: &str, opts: ExtractOptions) -> Result<ExtractResult, Error> {
+    if opts.file_name.as_deref().is_some_and(is_data_file) {
+        return extract_json(src, opts);
+    }
+    let cm: Lrc<SourceMap> = Default::default();
+    // 组件文件只解析其中的脚本，总是按 span 在原文件上替换（见 `markup` 模块），不生成 sourcemap
+    let doc = markup_document(opts.file_name.as_deref(), src);
+    let opts = match &doc {
+        Some(_) => ExtractOptions {
+            preserve_format: true,
+            source_map: false,
+            ..opts
+        },
+        None => opts,
+    };
+    let syntax = resolve_syntax(
+        opts.file_name.as_deref(),
+        opts.syntax.or(doc.as_ref().map(Document::syntax)),
+        opts.jsx,
+        opts.legacy_decorators,
+        opts.dts,
+    );
+    // 收集注释，用于识别 sb-dice-ignore 系列指令
+    let comments = SingleThreadedComments::default();
+    let (mut module, warnings) = parse_source(
+        &cm,
+        opts.file_name.clone(),
+        doc.as_ref()
+            .map_or_else(|| src.to_string(), |doc| doc.mask(src)),
+        syntax,
+        Some(&comments),
+        opts.tolerant,
+    )?;
+
+    // 默认保留注释，--strip-comments 时去掉
+    let out_comments: Option<&dyn Comments> = if opts.strip_comments {
+        None
+    } else {
+        Some(&comments)
+    };
+
+    let directives = Directives::collect(&cm, &comments);
+    if directives.ignore_file {
+        // 整个文件被 sb-dice-ignore-file 排除：原样输出，映射表为空
+        let code = if opts.preserve_format {
+            opts.codegen.newline.normalize(src.to_string())
+        } else {
+            emit_ts(&cm, &mut module, out_comments, &opts.codegen, src, None)?
+        };
+        return Ok(ExtractResult {
+            code,
+            entries: Vec::new(),
+            source_map: None,
+            warnings,
+            folded: Vec::new(),
+            fragments: Vec::new(),
+        });
+    }
+
+    let mut replacer = StringReplacer::new(&opts, &cm, src, directives);
+    let mut folder = ConcatFolder::default();
+    if opts.fold_concat {
+        module.visit_mut_with(&mut folder);
+        replacer.folded = std::mem::take(&mut folder.folded);
+    }
+    module.visit_mut_with(&mut replacer);
+    if !folder.originals.is_empty() {
+        module.visit_mut_with(&mut ConcatUnfolder {
+            originals: folder.originals,
+            recorded: &replacer.recorded,
+        });
+    }
+    if let Some(doc) = doc
+        .as_ref()
+        .filter(|doc| opts.markup_text && doc.kind == Markup::Svelte)
+    {
+        replacer.extract_markup_texts(&doc.texts, file_start(&cm));
+    }
+
+    // 替换为查表调用时从查表模块导入查表函数（方法名形式的 wrap_call 由调用方自行提供）
+    if let (Some(name), Some(from)) = (&opts.wrap_call, &opts.loader_module)
+        && !name.contains('.')
+        && !replacer.entries.is_empty()
+    {
+        match &doc {
+            // HTML 页面中的脚本通常不是模块，不能 import：查表函数由页面自行加载
+            Some(doc) if doc.kind == Markup::Html => {}
+            // 组件中还没有脚本：在开头新加一段
+            Some(doc) if doc.scripts.is_empty() => {
+                let text = format!(
+                    "<script>\n{}\n</script>\n",
+                    replacer.import_text(name, from)
+                );
+                replacer.edits.push(Edit { lo: 0, hi: 0, text });
+            }
+            _ => replacer.insert_import(&mut module, name, from),
+        }
+    }
+
+    if let Some((line, raw)) = replacer.invalid.take() {
+        return Err(Error::InvalidString { line, raw });
+    }
+    if let Some((line, key)) = find_key_collision(&module, &replacer) {
+        return Err(Error::KeyCollision { line, key });
+    }
+    // 外层的拼接在访问完内层之后才记录
+    replacer.fragments.sort_by_key(|f| f.location.start);
+
+    // preserve_format 模式下直接在原始源码上按 span 做替换，不经过 codegen（行号不变，不生成 sourcemap）
+    let mut mappings = Vec::new();
+    let code = if opts.preserve_format {
+        let code = apply_edits(src, std::mem::take(&mut replacer.edits));
+        opts.codegen.newline.normalize(code)
+    } else {
+        let mappings = opts.source_map.then_some(&mut mappings);
+        emit_ts(&cm, &mut module, out_comments, &opts.codegen, src, mappings)?
+    };
+    let source_map = if opts.source_map && !opts.preserve_format {
+        Some(render_source_map(
+            &cm,
+            &mappings,
+            opts.input_source_map.as_deref(),
+        )?)
+    } else {
+        None
+    };
+
+    Ok(ExtractResult {
+        code,
+        entries: replacer.entries,
+        source_map,
+        warnings,
+        folded: replacer.folds,
+        fragments: replacer.fragments,
+    })
+}
+
+/// 按映射表把替换后的源码还原（语法按 `file_name` 的扩展名判断）
+pub fn restore_strings(
+    src: &str,
+    map: &Map<String, Value>,
+    file_name: Option<PathBuf>,
+    opts: &RestoreOptions,
+) -> Result<RestoreResult, Error> {
+    rewrite_keys(src, map, file_name, false, opts)
+}
+
+/// 按译文表把替换后的源码中的索引字符串替换为译文，得到可以直接发布的本地化代码
+///
+/// 译文表的记录形如 `{"text": "原始", "translation": "译文"}`（即 `merge` 的输出），
+/// 纯字符串记录视为译文；没有译文的字符串退回原文，并计入 `untranslated`。
+pub fn apply_translations(
+    src: &str,
+    map: &Map<String, Value>,
+    file_name: Option<PathBuf>,
+    opts: &RestoreOptions,
+) -> Result<RestoreResult, Error> {
+    rewrite_keys(src, map, file_name, true, opts)
+}
+
+/// 把源码中的索引字符串替换为映射表中的原文或译文
+fn rewrite_keys(
+    src: &str,
+    map: &Map<String, Value>,
+    file_name: Option<PathBuf>,
+    translate: bool,
+    opts: &RestoreOptions,
+) -> Result<RestoreResult, Error> {
+    let cm: Lrc<SourceMap> = Default::default();
+    // JSON 数据文件只还原选中的值，其余恰好与 key 相同的字符串不动
+    if file_name.as_deref().is_some_and(is_data_file) {
+        let expr = parse_json(&cm, file_name, src)?;
+        let mut restorer = StringRestorer::new(map, translate, None, &cm);
+        restorer.edits = Some(Vec::new());
+        restorer.restore_json_strings(&json_strings(&expr, &opts.json_paths));
+        let edits = restorer.edits.take().unwrap_or_default();
+        return Ok(RestoreResult {
+            code: opts.codegen.newline.normalize(apply_edits(src, edits)),
+            restored: restorer.restored,
+            untranslated: restorer.untranslated,
+        });
+    }
+    let doc = markup_document(file_name.as_deref(), src);
+    // 两种装饰器写法都接受：替换后的文件沿用提取时的写法
+    let syntax = resolve_syntax(
+        file_name.as_deref(),
+        doc.as_ref().map(Document::syntax),
+        false,
+        true,
+        None,
+    );
+    // 还原时保留替换后文件中的注释；容错提取（且保留格式）的输出中可能仍有能够恢复的语法错误，这里不报错
+    let comments = SingleThreadedComments::default();
+    let (mut module, _) = parse_source(
+        &cm,
+        file_name,
+        doc.as_ref()
+            .map_or_else(|| src.to_string(), |doc| doc.mask(src)),
+        syntax,
+        Some(&comments),
+        true,
+    )?;
+
+    let mut restorer = StringRestorer::new(map, translate, opts.wrap_call.as_deref(), &cm);
+    // 组件文件与保留格式时与提取时一样按 span 在原文件上还原
+    if doc.is_some() || opts.preserve_format {
+        restorer.edits = Some(Vec::new());
+    }
+    module.visit_mut_with(&mut restorer);
+
+    let code = match &doc {
+        Some(doc) => {
+            if opts.markup_text && doc.kind == Markup::Svelte {
+                restorer.restore_markup_texts(src, &doc.texts);
+            }
+            let edits = restorer.edits.take().unwrap_or_default();
+            opts.codegen.newline.normalize(apply_edits(src, edits))
+        }
+        None if opts.preserve_format => {
+            let edits = restorer.edits.take().unwrap_or_default();
+            opts.codegen.newline.normalize(apply_edits(src, edits))
+        }
+        None => emit_ts(&cm, &mut module, Some(&comments), &opts.codegen, src, None)?,
+    };
+
+    Ok(RestoreResult {
+        code,
+        restored: restorer.restored,
+        untranslated: restorer.untranslated,
+    })
+}
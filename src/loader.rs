@@ -0,0 +1,61 @@
+//! 查表模块（`--emit-loader`）：`<name>_strings.ts`
+//!
+//! 导出字符串表 `strings`、全部 key 的类型 `StringKey` 与查表函数，
+//! 替换后的文件配合它即可独立编译运行，运行时不再需要 JSON 映射表。
+//! 查表函数与 `--wrap-call` 同名（未指定或为 `i18n.t` 这样的方法名时为 `S`）；
+//! key 为十进制索引时也可以直接传数字，与 `S(0)` 的写法对应。
+
+use std::fmt::Write;
+
+use serde_json::Value;
+
+use crate::Entry;
+
+/// 未指定 wrap_call 时查表函数的名字
+const DEFAULT_NAME: &str = "S";
+
+/// 查表函数的名字：wrap_call 为普通函数名时与之相同，否则为 `S`
+pub fn loader_fn_name(wrap_call: Option<&str>) -> &str {
+    match wrap_call {
+        Some(name) if !name.contains('.') => name,
+        _ => DEFAULT_NAME,
+    }
+}
+
+/// 条目原文在 TS 源码中的写法
+///
+/// 含有孤立代理项的字符串只能用原始字面量无损表示；其余用 JSON 字符串（也是合法的 JS 字符串）。
+fn literal(entry: &Entry) -> String {
+    match &entry.raw {
+        Some(raw) if raw.starts_with(['"', '\'']) => raw.clone(),
+        _ => Value::String(entry.original.clone()).to_string(),
+    }
+}
+
+/// 渲染查表模块
+pub fn render_loader(entries: &[Entry], wrap_call: Option<&str>) -> String {
+    let name = loader_fn_name(wrap_call);
+    let mut out = String::from("// 由 sb_dice 生成，请勿手动修改\n\n");
+
+    out.push_str("export const strings = {\n");
+    for entry in entries {
+        let key = Value::String(entry.key.clone());
+        let _ = writeln!(out, "  {}: {},", key, literal(entry));
+    }
+    out.push_str("};\n\n");
+
+    out.push_str("/** 字符串表中的全部 key */\n");
+    out.push_str("export type StringKey = keyof typeof strings;\n\n");
+    out.push_str("/** 十进制索引 key 对应的数字，如 \"0\" 对应 0 */\n");
+    out.push_str("type IndexOf<K> = K extends `${infer N extends number}` ? N : never;\n\n");
+
+    out.push_str("/** 按 key 取字符串，找不到时返回 key 本身 */\n");
+    let _ = writeln!(
+        out,
+        "export function {}(key: StringKey | IndexOf<StringKey>): string {{",
+        name
+    );
+    out.push_str("  return strings[String(key) as StringKey] ?? String(key);\n");
+    out.push_str("}\n");
+    out
+}
@@ -1,235 +1,4794 @@
-//! CLI 工具：接收一个 .ts 文件路径，解析并将所有普通字符串字面量（不包括模板字符串的 quasis）替换为按顺序递增的索引字符串 "0","1",...
+//! CLI 工具：接收一个 .ts/.tsx/.js/.mjs/.cjs/.svelte/.html 文件路径，解析并将所有普通字符串字面量（不包括模板字符串的 quasis）替换为按顺序递增的索引字符串 "0","1",...
 //! 输出两个文件：`<name>_r.ts`（替换后的 TS）与 `<name>_s.json`（映射表，形如 {"0":"原始字符串0","1":"原始字符串1",...}）
 //!
 //! 使用说明：
-//!   sb_dice <path/to/file.ts>
+//!   sb_dice [选项] <path/to/file.ts>
+//!   sb_dice [选项] <path/to/dir>
+//!   sb_dice [选项] "src/**/*.ts" --exclude "**/node_modules/**"
+//!   sb_dice --watch [选项] <path/to/dir>
+//!   sb_dice extract [选项] <输入>...
+//!   sb_dice restore [选项] <file_r.ts> <file_s.json>
+//!   sb_dice merge [--fuzzy-threshold 0.8 | --no-fuzzy] <new_s.json> <old_translated.json>
+//!   sb_dice apply [选项] <file_r.ts> <translated.json> [-o <file_zh.ts>]
+//!   sb_dice check [选项] <输入>...
+//!   sb_dice review [选项] <file.ts>...
+//!   sb_dice check-placeholders <source.json> <translated.json>
+//!   sb_dice diff [--format text|json|unified] <old_s.json> <new_s.json>
+//!   sb_dice diff-src [选项] <old.ts> <new.ts>
+//!   sb_dice dupes <s.json>
+//!   sb_dice glossary [选项] <s.json>... [-o glossary.csv]
+//!   sb_dice renumber [选项] <s.json> --from <old.ts> --to <new.ts>
+//!   sb_dice stats [选项] <输入>...
+//!
+//! 参数由 clap 解析（见文件末尾的 [`Cli`]），不写子命令时等同于 `extract`；
+//! 子命令的选项要写在子命令之后。
+//!
+//! `restore` 子命令做反向替换：把 `_r.ts` 中的索引字符串按映射表还原为原始字符串，输出 `<name>_o.ts`。
+//! `merge` 子命令把旧译文按原始字符串带到重新提取的映射表上，输出 `<name>_m.json`。
+//! `apply` 子命令把 `_r.ts` 中的索引字符串直接替换为译文，输出本地化后的脚本。
+//! `check` 子命令在内存中重新提取，检查已有的映射表是否过期，用作 CI 检查。
 //!
 //! 错误处理：
-//!   - 如果参数不对或不是以 `.ts` 结尾，会打印错误并返回非零退出码。
-//!   - 解析或写文件失败会打印错误并返回非零退出码。
+//!   - 所有错误都汇总为 `CliError`，在 `main` 中统一输出（人类可读或 `--diagnostics json`）。
+//!   - 退出码按类别划分：参数错误 1、读写失败 3、解析失败 4、代码生成失败 5，
+//!     子命令的检查结果另有退出码（见 [`CliError::exit_code`]）。
 //!
-//! 备注：不会替换模板字符串中的静态部分（quasis）；会替换 import/require 中的字符串模块路径。
-//!      输出代码中去掉注释（通过 emitter.comments = None 控制）。
+//! 备注：默认不替换模板字符串中的静态部分（quasis，`--include-template-quasis` 可开启）；会替换 import/require 中的字符串模块路径
+//!      （`--skip-imports` 可跳过）；
+//!      默认不替换类型位置中的字符串（`--include-types` 可改回替换）。
+//!      默认保留注释，`--strip-comments` 时去掉。
+//!
+//! 解析、替换与代码生成都在库（`src/lib.rs`）中实现，这里只负责参数处理、读写文件与退出码。
 
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::env;
+use std::ffi::OsStr;
 use std::fs;
-use std::path::Path;
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
 use std::process;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use glob::Pattern;
+use notify::{EventKind, RecursiveMode, Watcher};
+use regex::Regex;
+use serde_json::{Map, Value};
+use xxhash_rust::xxh64::xxh64;
 
-use swc_core::common::{sync::Lrc, FileName, SourceMap};
-use swc_core::ecma::ast::Str;
-use swc_core::ecma::codegen::{text_writer::JsWriter, Emitter, Config};
-use swc_core::ecma::parser::{lexer::Lexer, Parser, StringInput, Syntax};
-use swc_core::ecma::visit::{VisitMut, VisitMutWith};
-use swc_core::ecma::ast::EsVersion;
+use sb_dice::{
+    CodegenOptions, Compression, Decision, Decisions, Entry, Error, EsVersion, ExtractOptions,
+    FoldedConcat, FormatOptions, Fragment, FragmentKind, GlossaryOptions, IndexFormat, JsonPath,
+    JsonStyle, KeyMode, KeyNamespace, KeyTemplate, LINK_PREFIX, Location, META_KEY, META_VERSION,
+    MapFormat, MapMeta, MapSchema, MappingDiff, MergeOptions, Newline, ParseWarning, QuoteStyle,
+    RestoreOptions, SeqEdit, SourceEncoding, SourceSyntax, SplitMode, StringStats, TsConfig,
+    apply_translations, build_glossary, check_placeholders, chunk_name, chunk_record, content_hash,
+    diff_mappings, diff_multisets, diff_sequences, encode_text, extract_strings, find_dupes,
+    group_hunks, is_data_file, is_declaration_file, keys_hash, link_comment, merge_mappings_with,
+    natural_cmp, render_glossary, render_loader, renumber_mapping, restore_strings, split_entries,
+    split_link, string_hash, to_canonical_json, validate_icu,
+};
 
-use serde_json::Map;
-use serde_json::Value;
+fn print_usage_and_exit() -> ! {
+    CliError::Usage("缺少参数".to_string()).exit()
+}
 
-/// 替换器：记录计数并收集原始字符串（按顺序）
-struct StringReplacer {
-    counter: usize,
-    originals: Vec<String>,
+/// `--diagnostics json`：错误、警告与跳过的文件以 JSON 行的形式输出到标准错误
+static JSON_DIAGNOSTICS: AtomicBool = AtomicBool::new(false);
+
+/// 一条诊断信息
+struct Diagnostic<'a> {
+    /// 级别：error / warning / note / info
+    severity: &'static str,
+    /// 机器可读的类别，如 `parse`、`untranslated`
+    code: &'static str,
+    /// 相关的文件
+    file: Option<&'a Path>,
+    /// 行号与列号（从 1 开始）
+    position: Option<(usize, Option<usize>)>,
+    /// 说明文字
+    message: &'a str,
 }
 
-impl StringReplacer {
-    fn new() -> Self {
-        Self {
-            counter: 0,
-            originals: Vec::new(),
+impl Diagnostic<'_> {
+    /// 输出到标准错误；人类可读形式下只打印说明文字，`info` 级别不打印
+    fn report(&self) {
+        if !JSON_DIAGNOSTICS.load(Ordering::Relaxed) {
+            if self.severity != "info" {
+                eprintln!("{}", self.message);
+            }
+            return;
         }
+        let (line, column) = match self.position {
+            Some((line, column)) => (Some(line), column),
+            None => (None, None),
+        };
+        let value = serde_json::json!({
+            "severity": self.severity,
+            "code": self.code,
+            "file": self.file.map(slash_path),
+            "line": line,
+            "column": column,
+            // JSON 形式中去掉给人看的帮助提示
+            "message": self.message.lines().next().unwrap_or_default(),
+        });
+        eprintln!("{}", value);
     }
 }
 
-impl VisitMut for StringReplacer {
-    fn visit_mut_str(&mut self, n: &mut Str) {
-        // 只针对 Str 节点（这不会匹配模板的 quasis，模板静态文本是 TplElement）
-        // 使用字符串的原始值，而不是 Debug 格式（避免生成带转义的双引号）
-        let original = n.value.as_str().unwrap_or_default().to_string();
-        // 记录原始内容
-        self.originals.push(original);
+/// 输出一条警告
+fn warn(code: &'static str, file: Option<&Path>, message: &str) {
+    Diagnostic {
+        severity: "warning",
+        code,
+        file,
+        position: None,
+        message,
+    }
+    .report();
+}
 
-        // 生成新的字符串值，例如 "0", "1", ...
-        let new_val = self.counter.to_string();
-        n.value = new_val.into();
+/// 输出容错解析（`--tolerant`）时跳过的语法错误，每处一条警告
+fn report_recovered(file: Option<&Path>, warnings: &[ParseWarning]) {
+    let name = file.map_or_else(|| "<stdin>".into(), |path| path.display().to_string());
+    for warning in warnings {
+        Diagnostic {
+            severity: "warning",
+            code: "recovered",
+            file,
+            position: Some((warning.line, Some(warning.column))),
+            message: &format!(
+                "警告：{}:{}:{} 有语法错误，已跳过继续处理：{}",
+                name, warning.line, warning.column, warning.message
+            ),
+        }
+        .report();
+    }
+}
 
-        // 清除 raw，强制 codegen 使用新的 value
-        n.raw = None;
+/// 输出合并后替换了的字符串拼接（`--fold-concat`），每处一条
+fn report_folded(file: Option<&Path>, folded: &[FoldedConcat]) {
+    let name = file.map_or_else(|| "<stdin>".into(), |path| path.display().to_string());
+    for fold in folded {
+        let loc = fold.location;
+        Diagnostic {
+            severity: "note",
+            code: "folded",
+            file,
+            position: Some((loc.line, Some(loc.column))),
+            message: &format!(
+                "{}:{}:{}: 合并了 {} 段字符串拼接：{}",
+                name,
+                loc.line,
+                loc.column,
+                fold.parts,
+                Value::String(fold.text.clone())
+            ),
+        }
+        .report();
+    }
+}
 
-        self.counter += 1;
+/// 警告不是合法 ICU MessageFormat 的字符串（`--validate icu`），每条一处，位置为第一次出现的行
+fn report_icu(file: Option<&Path>, entries: &[Entry]) {
+    let name = file.map_or_else(|| "<stdin>".into(), |path| path.display().to_string());
+    for entry in entries {
+        let Err(error) = validate_icu(&entry.original) else {
+            continue;
+        };
+        Diagnostic {
+            severity: "warning",
+            code: "icu",
+            file,
+            position: Some((entry.first_line, None)),
+            message: &format!(
+                "警告：{}:{}: 字符串 {} 不是合法的 ICU 消息，{}：{}",
+                name,
+                entry.first_line,
+                entry.key,
+                error,
+                Value::String(entry.original.clone())
+            ),
+        }
+        .report();
     }
 }
 
-fn print_help() {
-    println!(r#"sb_dice - 用来解决 DICE 的 sb 字符串机制的 字符串提取与替换工具
+/// 警告被拆成片段的句子：映射表中只有无法单独翻译的片段，每处一条
+fn report_fragments(file: Option<&Path>, fragments: &[Fragment]) {
+    let name = file.map_or_else(|| "<stdin>".into(), |path| path.display().to_string());
+    for fragment in fragments {
+        let loc = fragment.location;
+        let (how, hint) = match fragment.kind {
+            FragmentKind::Concat => (
+                "+ 拼接",
+                "（改写为整句，或加 --fold-concat 合并纯字符串的拼接）",
+            ),
+            FragmentKind::Join => (".join(\"\")", "（改写为整句）"),
+        };
+        // 只显示表达式的第一行
+        let source = fragment.source.lines().next().unwrap_or_default();
+        let more = if source.len() < fragment.source.len() {
+            " ..."
+        } else {
+            ""
+        };
+        Diagnostic {
+            severity: "warning",
+            code: "fragment",
+            file,
+            position: Some((loc.line, Some(loc.column))),
+            message: &format!(
+                "警告：{}:{}:{} 句子被 {} 拆成片段，其中 {} 个字符串被单独替换：{}{}\n{}",
+                name, loc.line, loc.column, how, fragment.strings, source, more, hint
+            ),
+        }
+        .report();
+    }
+}
 
-Author: shenjack & Gemini 3 Pro & GPT 5 mini & GLM 4.7 & DeepSeek v3.2 (按照贡献多少排序(确信))
+/// 记录一个被跳过的文件（只在 JSON 诊断中输出）
+fn report_skipped(path: &Path, reason: &str) {
+    Diagnostic {
+        severity: "info",
+        code: "skipped",
+        file: Some(path),
+        position: None,
+        message: reason,
+    }
+    .report();
+}
 
-用法:
-  sb_dice <path/to/file.ts>
-  sb_dice -h
-  sb_dice --help
+/// 命令行工具的错误，按类别对应退出码（见 [`CliError::exit_code`]）
+#[derive(Debug, thiserror::Error)]
+enum CliError {
+    /// 命令行参数有误
+    #[error("错误：{0}\n使用 'sb_dice -h' 或 'sb_dice --help' 查看帮助信息")]
+    Usage(String),
+    /// 命令行解析失败（clap 给出的错误，已带有用法提示）
+    #[error("{}", .0.to_string().trim_end())]
+    Args(clap::Error),
+    /// 不支持的输入文件
+    #[error("错误：仅支持 .ts/.tsx/.js/.mjs/.cjs/.svelte/.html 文件（或直接给出的 .json 数据文件）作为输入：{}", .0.display())]
+    UnsupportedInput(PathBuf),
+    /// 无法取得输入文件名
+    #[error("无法解析输入文件名：{}", .0.display())]
+    FileName(PathBuf),
+    /// 读写文件或目录失败
+    #[error("{action}失败 {}: {source}", .path.display())]
+    Io {
+        /// 正在进行的操作，如 `读取文件`
+        action: &'static str,
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    /// 读写标准输入输出失败
+    #[error("{action}失败: {source}")]
+    Stdio {
+        action: &'static str,
+        #[source]
+        source: io::Error,
+    },
+    /// 文件监视失败
+    #[error("{action}失败: {source}")]
+    Watch {
+        action: String,
+        #[source]
+        source: notify::Error,
+    },
+    /// 调用 git 失败（`--changed` / `--since`）
+    #[error("运行 git {args} 失败: {message}")]
+    Git { args: String, message: String },
+    /// 映射表无法解析
+    #[error("解析映射表失败 {}: {message}", .path.display())]
+    Mapping { path: PathBuf, message: String },
+    /// 配置文件无法解析
+    #[error("解析配置文件失败 {}: {message}", .path.display())]
+    Config { path: PathBuf, message: String },
+    /// 取舍记录文件格式错误
+    #[error("解析取舍记录失败 {}: {message}", .path.display())]
+    Decisions { path: PathBuf, message: String },
+    /// 处理源文件失败（解析、代码生成等）
+    #[error("处理文件失败 {}: {source}", .path.display())]
+    Source {
+        path: PathBuf,
+        #[source]
+        source: Error,
+    },
+    /// 处理标准输入失败
+    #[error(transparent)]
+    Lib(#[from] Error),
+    /// 生成 JSON 失败
+    #[error("生成 JSON 失败: {0}")]
+    Json(#[from] serde_json::Error),
+    /// 译文的占位符与原文不一致
+    #[error("错误：{0} 条译文的占位符与原文不一致")]
+    Placeholders(usize),
+    /// 映射表与源码不一致（`check`）
+    #[error("错误：{0} 个映射表与源码不一致，重新运行 sb_dice 更新")]
+    Outdated(usize),
+    /// 映射表与要处理的文件或选项不对应（按映射表的 `$meta` 检查）
+    #[error("错误：映射表 {} 不能用于本次操作：{message}", .path.display())]
+    Mismatch { path: PathBuf, message: String },
+    /// 输入像是已经处理过的文件（未指定 `--allow-reprocess`）
+    #[error(
+        "错误：{} 像是已经处理过的文件：{reason}。再次提取会破坏它与映射表的对应关系且无法恢复，确实需要时加 --allow-reprocess",
+        .path.display()
+    )]
+    AlreadyProcessed { path: PathBuf, reason: String },
+    /// 输出文件已存在（未指定 `--force`）
+    #[error(
+        "错误：以下输出文件已存在，为避免覆盖手动修改过的内容已中止（使用 --force 覆盖）：\n  {}",
+        .0.join("\n  ")
+    )]
+    OutputExists(Vec<String>),
+}
 
-选项:
-  -h, --help    显示此帮助信息
+impl CliError {
+    /// 供 `map_err` 使用：对 `path` 的 `action` 操作失败
+    fn io(action: &'static str, path: &Path) -> impl FnOnce(io::Error) -> Self {
+        let path = path.to_path_buf();
+        move |source| CliError::Io {
+            action,
+            path,
+            source,
+        }
+    }
 
-参数:
-  <path/to/file.ts>  输入的 TypeScript 文件路径
+    /// 供 `map_err` 使用：处理 `path` 时库返回了错误
+    fn from_lib(path: &Path) -> impl FnOnce(Error) -> Self {
+        let path = path.to_path_buf();
+        move |source| CliError::Source { path, source }
+    }
 
-说明:
-  解析 TypeScript 文件，将所有普通字符串字面量（不包括模板字符串的 quasis）
-  替换为按顺序递增的索引字符串 "0","1",...
+    /// 库返回的错误
+    fn lib_error(&self) -> Option<&Error> {
+        match self {
+            CliError::Source { source, .. } | CliError::Lib(source) => Some(source),
+            _ => None,
+        }
+    }
 
-输出:
-  生成两个文件：
-    - <name>_r.ts  : 替换后的 TS 文件
-    - <name>_s.json: 映射表，形如 {{"0":"原始字符串0","1":"原始字符串1",...}}
+    /// 退出码，按类别划分
+    fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_)
+            | CliError::Args(_)
+            | CliError::UnsupportedInput(_)
+            | CliError::FileName(_) => 1,
+            CliError::Io { .. }
+            | CliError::Stdio { .. }
+            | CliError::Watch { .. }
+            | CliError::Git { .. } => 3,
+            CliError::Mapping { .. } | CliError::Config { .. } | CliError::Decisions { .. } => 4,
+            CliError::Source { .. } | CliError::Lib(_) => match self.lib_error() {
+                Some(
+                    Error::Parse { .. } | Error::InvalidString { .. } | Error::KeyCollision { .. },
+                ) => 4,
+                _ => 5,
+            },
+            CliError::Json(_) => 5,
+            CliError::Placeholders(_) => 13,
+            CliError::Outdated(_) => 14,
+            CliError::OutputExists(_) => 15,
+            CliError::Mismatch { .. } => 16,
+            CliError::AlreadyProcessed { .. } => 17,
+        }
+    }
 
-注意事项:
-  - 不会替换模板字符串中的静态部分（quasis） (反正你也用不到)
-  - 会替换 import/require 中的字符串模块路径 (反正也不应该有)
-  - 输出代码中去掉注释"#);
+    /// 诊断信息中的类别，与退出码一一对应
+    fn kind(&self) -> &'static str {
+        match self.exit_code() {
+            1 => "usage",
+            3 => "io",
+            4 => "parse",
+            5 => "emit",
+            13 => "placeholders",
+            14 => "outdated",
+            15 => "output-exists",
+            16 => "mismatch",
+            17 => "already-processed",
+            _ => "error",
+        }
+    }
+
+    /// 相关的文件
+    fn file(&self) -> Option<&Path> {
+        match self {
+            CliError::UnsupportedInput(path)
+            | CliError::FileName(path)
+            | CliError::Io { path, .. }
+            | CliError::Mapping { path, .. }
+            | CliError::Config { path, .. }
+            | CliError::Decisions { path, .. }
+            | CliError::Mismatch { path, .. }
+            | CliError::AlreadyProcessed { path, .. }
+            | CliError::Source { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// 出错位置的行号与列号
+    fn position(&self) -> Option<(usize, Option<usize>)> {
+        match self.lib_error()? {
+            Error::Parse { line, column, .. } => Some((*line, Some(*column))),
+            Error::InvalidString { line, .. } | Error::KeyCollision { line, .. } => {
+                Some((*line, None))
+            }
+            _ => None,
+        }
+    }
+
+    /// 打印错误（不退出），监视模式下出错时使用
+    fn report(&self) {
+        Diagnostic {
+            severity: "error",
+            code: self.kind(),
+            file: self.file(),
+            position: self.position(),
+            message: &self.to_string(),
+        }
+        .report();
+    }
+
+    /// 打印错误并以对应的退出码退出
+    fn exit(self) -> ! {
+        self.report();
+        process::exit(self.exit_code());
+    }
 }
 
-fn print_usage_and_exit() -> ! {
-    eprintln!(r#"错误：缺少参数
+/// 读取文件内容（`.gz` / `.zst` 文件自动解压）
+fn read_input(path: &Path) -> Result<String, CliError> {
+    let data = read_bytes(path)?;
+    String::from_utf8(data)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        .map_err(CliError::io("读取文件", path))
+}
 
-使用 'sb_dice -h' 或 'sb_dice --help' 查看详细帮助信息"#);
-    process::exit(1);
+/// 按 `--encoding` 读取源文件，返回文本与实际使用的编码（`.gz` / `.zst` 文件自动解压）
+fn read_source(
+    path: &Path,
+    encoding: SourceEncoding,
+) -> Result<(String, &'static encoding_rs::Encoding), CliError> {
+    let data = read_bytes(path)?;
+    encoding
+        .decode(&data)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        .map_err(CliError::io("读取文件", path))
 }
 
-fn main() {
-    // 解析命令行参数
-    let mut args = env::args().skip(1);
+/// 把代码编码回源文件的编码，准备写出到 `path`
+fn encode_source(
+    path: &Path,
+    code: &str,
+    encoding: &'static encoding_rs::Encoding,
+) -> Result<Vec<u8>, CliError> {
+    encode_text(code, encoding)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        .map_err(CliError::io("写入输出 TS 文件", path))
+}
 
-    // 检查帮助参数
-    let arg = args.next();
-    if arg.as_deref() == Some("-h") || arg.as_deref() == Some("--help") {
-        print_help();
-        process::exit(0);
+/// 读取文件的全部字节，扩展名为 `.gz` / `.zst` 时解压
+fn read_bytes(path: &Path) -> Result<Vec<u8>, CliError> {
+    let data = fs::read(path).map_err(CliError::io("读取文件", path))?;
+    match Compression::from_path(path) {
+        Some(compression) => compression
+            .decompress(&data)
+            .map_err(CliError::io("解压文件", path)),
+        None => Ok(data),
     }
+}
 
-    let input_path = match arg {
-        Some(p) => p,
-        None => {
-            print_usage_and_exit();
-        }
+/// 去掉压缩扩展名（`.gz` / `.zst`）后的路径，用来按扩展名判断格式与计算输出文件名
+fn uncompressed_path(path: &Path) -> PathBuf {
+    match Compression::from_path(path) {
+        Some(_) => path.with_extension(""),
+        None => path.to_path_buf(),
+    }
+}
+
+/// 压缩后的文件路径：在原文件名后追加 `.gz` / `.zst`（未压缩时原样返回）
+fn compressed_path(path: PathBuf, compress: Option<Compression>) -> PathBuf {
+    let Some(compression) = compress else {
+        return path;
+    };
+    let mut path = path.into_os_string();
+    path.push(".");
+    path.push(compression.extension());
+    PathBuf::from(path)
+}
+
+/// 按需压缩后写出文件
+fn write_output(
+    path: &Path,
+    contents: impl AsRef<[u8]>,
+    compress: Option<Compression>,
+) -> io::Result<()> {
+    match compress {
+        Some(compression) => write_atomic(path, compression.compress(contents.as_ref())?),
+        None => write_atomic(path, contents),
+    }
+}
+
+/// 支持作为输入的扩展名
+const INPUT_EXTS: &[&str] = &["ts", "tsx", "js", "mjs", "cjs", "svelte", "html", "htm"];
+
+/// 取输入文件的扩展名（仅限支持的扩展名）
+fn input_ext(path: &Path) -> Option<&str> {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .filter(|e| INPUT_EXTS.contains(e))
+}
+
+/// 只能在命令行上直接给出的输入扩展名：JSON 数据文件旁边往往还有配置与映射表，遍历目录与展开 glob 时不收集
+const DATA_EXTS: &[&str] = &["json"];
+
+/// 确保是支持的输入文件（.ts/.tsx/.js/.mjs/.cjs/.svelte/.html，或 .json 数据文件）；返回扩展名
+fn ensure_input(path: &Path) -> Result<&str, CliError> {
+    input_ext(path)
+        .or_else(|| {
+            path.extension()
+                .and_then(|s| s.to_str())
+                .filter(|e| DATA_EXTS.contains(e))
+        })
+        .ok_or_else(|| CliError::UnsupportedInput(path.to_path_buf()))
+}
+
+/// 取输入文件名（不含扩展名）
+fn file_stem(path: &Path) -> Result<&str, CliError> {
+    path.file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| CliError::FileName(path.to_path_buf()))
+}
+
+/// 读取并解析映射表（去掉 `$meta` 记录）
+///
+/// 未指定格式时按扩展名判断：`.csv` / `.tsv` 为表格，`.msgpack` / `.bin` 为二进制映射表，其余按 JSON 解析；
+/// 扩展名为 `.gz` / `.zst` 时先解压，再按去掉它之后的扩展名判断。
+/// 分块映射表的索引（`<base>.index.json`）读取其中列出的各块并合并。
+fn read_mapping(path: &Path, format: Option<MapFormat>) -> Result<Map<String, Value>, CliError> {
+    Ok(read_mapping_meta(path, format)?.0)
+}
+
+/// 读取并解析映射表，同时取出 `$meta` 记录（没有时为 `None`）
+fn read_mapping_meta(
+    path: &Path,
+    format: Option<MapFormat>,
+) -> Result<(Map<String, Value>, Option<MapMeta>), CliError> {
+    // 二进制格式不是 UTF-8 文本，按字节读取，由各格式自行解码
+    let data = read_bytes(path)?;
+    let format = format.unwrap_or_else(|| mapping_format(path).unwrap_or(MapFormat::Json));
+
+    let error = |message| CliError::Mapping {
+        path: path.to_path_buf(),
+        message,
+    };
+    let mut map = format.parse(&data).map_err(error)?;
+    let meta = MapMeta::take(&mut map).map_err(error)?;
+    if let Some(chunks) = chunk_files(&map) {
+        map = read_chunks(path, &chunks)?;
+    }
+    Ok((map, meta))
+}
+
+/// 按扩展名判断可以读取的映射表格式（压缩扩展名不计）；其他格式（PO、i18next 等）为 `None`
+fn mapping_format(path: &Path) -> Option<MapFormat> {
+    let name = uncompressed_path(path);
+    if name.to_string_lossy().ends_with(".i18next.json") {
+        return None;
+    }
+    match name.extension()?.to_str()? {
+        "json" => Some(MapFormat::Json),
+        "csv" => Some(MapFormat::Csv),
+        "tsv" => Some(MapFormat::Tsv),
+        "msgpack" => Some(MapFormat::Msgpack),
+        "bin" => Some(MapFormat::Bincode),
+        _ => None,
+    }
+}
+
+/// 分块映射表索引中各块的文件名（见 [`write_mappings`]）；不是索引时为 `None`
+fn chunk_files(map: &Map<String, Value>) -> Option<Vec<Vec<&str>>> {
+    if map.len() != 1 {
+        return None;
+    }
+    map.get("chunks")?
+        .as_array()?
+        .iter()
+        .map(|chunk| {
+            chunk
+                .get("files")?
+                .as_array()?
+                .iter()
+                .map(Value::as_str)
+                .collect()
+        })
+        .collect()
+}
+
+/// 是否为分块映射表的索引（见 [`chunk_files`]）
+fn is_chunk_index(src: &str) -> bool {
+    if !src.contains("\"chunks\"") {
+        return false;
+    }
+    let Ok(mut map) = serde_json::from_str::<Map<String, Value>>(src) else {
+        return false;
+    };
+    map.remove(META_KEY);
+    chunk_files(&map).is_some()
+}
+
+/// 读取索引 `index` 中列出的各块（相对索引所在的目录）并合并；每块取第一个可以读取的格式
+fn read_chunks(index: &Path, chunks: &[Vec<&str>]) -> Result<Map<String, Value>, CliError> {
+    let dir = index.parent().unwrap_or(Path::new(""));
+    let mut map = Map::new();
+    for (n, files) in chunks.iter().enumerate() {
+        let chunk = files
+            .iter()
+            .map(|name| dir.join(name))
+            .find_map(|path| mapping_format(&path).map(|format| (path, format)));
+        let Some((path, format)) = chunk else {
+            return Err(CliError::Mapping {
+                path: index.to_path_buf(),
+                message: format!(
+                    "第 {} 块没有可以读取的映射表（需要 json、csv、tsv、msgpack 或 bincode 格式）",
+                    n + 1
+                ),
+            });
+        };
+        map.extend(read_mapping(&path, Some(format))?);
+    }
+    Ok(map)
+}
+
+/// 检查替换后的文件与映射表是否对应，返回去掉链接注释后的代码（见 [`split_link`]）
+///
+/// 映射表的 `$meta` 记录了替换后文件的哈希时，要求文件内容与其中之一一致；
+/// 文件末尾有链接注释时，要求其中的哈希与映射表的 key 集合一致。`verify` 为 `false`（`--no-verify`）时只去掉链接注释。
+fn verify_link(
+    path: &Path,
+    map: &Map<String, Value>,
+    meta: Option<&MapMeta>,
+    src: &str,
+    verify: bool,
+) -> Result<String, CliError> {
+    let (code, link) = split_link(src);
+    if !verify {
+        return Ok(code);
+    }
+    let mismatch = |message: &str| CliError::Mismatch {
+        path: path.to_path_buf(),
+        message: format!("{}（确认无误时加 --no-verify 跳过检查）", message),
     };
+    if let Some(meta) = meta.filter(|meta| !meta.code.is_empty()) {
+        let hash = content_hash(code.as_bytes());
+        if !meta.code.values().any(|h| *h == hash) {
+            return Err(mismatch(
+                "替换后的文件不是与它同一次提取生成的，或者提取后又改过",
+            ));
+        }
+    }
+    if let Some(link) = link
+        && keys_hash(map.keys().map(String::as_str)) != link
+    {
+        return Err(mismatch(
+            "key 与替换后的文件末尾记录的不一致，可能用错了映射表",
+        ));
+    }
+    Ok(code)
+}
 
-    // 确保是 .ts 文件
-    let path = Path::new(&input_path);
-    if path.extension().and_then(|s| s.to_str()) != Some("ts") {
-        eprintln!("错误：仅支持 .ts 文件作为输入：{}", input_path);
-        eprintln!("使用 'sb_dice -h' 或 'sb_dice --help' 查看帮助信息");
-        process::exit(2);
+/// 按映射表的 `$meta` 检查它能否用于 `restore` / `apply`
+///
+/// 格式版本比当前工具新、或 `--wrap-call` 与提取时不一致时拒绝（结果一定是错的）；
+/// 源文件在提取后又改过时只给出警告（映射表可能过期，但仍能还原提取时的代码）。
+fn verify_meta(path: &Path, meta: &MapMeta, wrap_call: Option<&str>) -> Result<(), CliError> {
+    let mismatch = |message: String| CliError::Mismatch {
+        path: path.to_path_buf(),
+        message,
+    };
+    if meta.version > META_VERSION {
+        return Err(mismatch(format!(
+            "格式版本为 {}（由 {} 生成），当前的 sb_dice 只支持到 {}，请升级",
+            meta.version, meta.tool, META_VERSION
+        )));
+    }
+    if meta.wrap_call() != wrap_call {
+        let describe = |name: Option<&str>| {
+            name.map_or("没有使用 --wrap-call".to_string(), |name| {
+                format!("使用了 --wrap-call {}", name)
+            })
+        };
+        return Err(mismatch(format!(
+            "提取时{}，本次{}，请给出与提取时相同的选项",
+            describe(meta.wrap_call()),
+            describe(wrap_call)
+        )));
+    }
+    for (source, hash) in &meta.sources {
+        let Ok(data) = fs::read(source) else {
+            continue;
+        };
+        if content_hash(&data) != *hash {
+            warn(
+                "stale-mapping",
+                Some(path),
+                &format!("源文件 {} 在提取后又改过，映射表可能已经过期", source),
+            );
+        }
     }
+    Ok(())
+}
 
-    // 读取文件内容
-    let src = match fs::read_to_string(path) {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("读取文件失败 {}: {}", input_path, e);
-            eprintln!("使用 'sb_dice -h' 或 'sb_dice --help' 查看帮助信息");
-            process::exit(3);
+/// 原子地写入文件：先写入同一目录下的临时文件，再重命名覆盖目标
+///
+/// 中途崩溃、磁盘写满或 Ctrl-C 时，目标文件要么保持旧内容，要么是完整的新内容，
+/// 不会留下截断的输出。覆盖已有文件时沿用它的权限。
+fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let tmp = parent_dir(path).join(format!(
+        ".{}.{}.tmp",
+        file_name(path).to_string_lossy(),
+        process::id()
+    ));
+    let result = (|| -> io::Result<()> {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(contents.as_ref())?;
+        if let Ok(meta) = fs::metadata(path) {
+            file.set_permissions(meta.permissions())?;
         }
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// 输入以 `#!` 开头（可以直接执行的 Node 脚本）时，把输入的权限复制给新写出的文件，输出同样可以执行
+fn keep_executable(input: &Path, output: &Path, src: &str) -> Result<(), CliError> {
+    if !src.starts_with("#!") || input == output {
+        return Ok(());
+    }
+    copy_permissions(input, output)
+}
+
+/// 把 `from` 的权限复制给 `to`
+fn copy_permissions(from: &Path, to: &Path) -> Result<(), CliError> {
+    let permissions = fs::metadata(from)
+        .map_err(CliError::io("读取文件", from))?
+        .permissions();
+    fs::set_permissions(to, permissions).map_err(CliError::io("设置文件权限", to))
+}
+
+/// 写出格式化后的 JSON（key 按自然顺序排列）
+fn write_json(path: &Path, value: &Value) -> Result<(), CliError> {
+    let json_text = to_canonical_json(value, JsonStyle::default());
+    write_atomic(path, json_text).map_err(CliError::io("写入输出 JSON 文件", path))
+}
+
+/// `merge` 子命令：`sb_dice merge <new_s.json> <old_translated.json>`
+///
+/// 把旧译文带到新映射表上，输出 `<name>_m.json` 并打印新增/删除/变化的 key。
+fn run_merge(args: MergeArgs) -> Result<(), CliError> {
+    let new_path = args.new.as_path();
+    let (new_map, meta) = read_mapping_meta(new_path, Some(MapFormat::Json))?;
+    let old_map = read_mapping(&args.old, Some(MapFormat::Json))?;
+
+    let opts = MergeOptions {
+        fuzzy: (!args.no_fuzzy).then_some(args.fuzzy_threshold),
     };
+    let result = merge_mappings_with(&new_map, &old_map, &opts);
 
-    // --- 解析 ---
-    let cm: Lrc<SourceMap> = Default::default();
-    // 使用真实文件名，方便解析错误定位
-    let fm = cm.new_source_file(FileName::Real(path.to_path_buf()).into(), src);
+    // 输出文件名：去掉 `_s` 后缀（与压缩扩展名）后追加 `_m.json`
+    let name_path = uncompressed_path(new_path);
+    let stem = file_stem(&name_path)?;
+    let name = stem.strip_suffix("_s").unwrap_or(stem);
+    let parent = new_path.parent().unwrap_or_else(|| Path::new("."));
+    let out_path = parent.join(format!("{}_m.json", name));
 
-    let lexer = Lexer::new(
-        Syntax::Typescript(Default::default()),
-        EsVersion::Es2020,
-        StringInput::from(&*fm),
-        None,
+    // 保留新映射表的 $meta，apply 时据此检查
+    let mut merged = result.merged;
+    if let Some(meta) = &meta {
+        merged.insert(META_KEY.to_string(), meta.to_value());
+    }
+    write_json(&out_path, &Value::Object(merged))?;
+
+    for key in &result.added {
+        println!("+ {}", key);
+    }
+    for key in &result.removed {
+        println!("- {}", key);
+    }
+    for key in &result.changed {
+        println!("~ {}", key);
+    }
+    for key in &result.fuzzy {
+        println!("? {}", key);
+    }
+    println!(
+        "成功：新增 {} 条，删除 {} 条，变化 {} 条，待确认 {} 条，生成 {}",
+        result.added.len(),
+        result.removed.len(),
+        result.changed.len(),
+        result.fuzzy.len(),
+        out_path.display()
     );
+    Ok(())
+}
+
+/// `restore` 子命令：`sb_dice restore [--from csv] <file_r.ts> <file_s.json>`
+///
+/// 映射表为 CSV/TSV 时，填写了译文的字符串还原为译文，其余还原为原文。
+fn run_restore(args: RestoreArgs) -> Result<(), CliError> {
+    let ts_path = args.file.as_path();
+    let name_path = uncompressed_path(ts_path);
+    let ext = ensure_input(&name_path)?;
+
+    let (src, encoding) = read_source(ts_path, args.style.encoding)?;
+    let (map, meta) = read_mapping_meta(&args.map, args.from)?;
+
+    let mut opts = args.style.restore_options();
+    opts.preserve_format =
+        args.preserve_format || meta.as_ref().is_some_and(MapMeta::preserve_format);
+    if let Some(meta) = &meta {
+        verify_meta(&args.map, meta, opts.wrap_call.as_deref())?;
+    }
+    let src = verify_link(&args.map, &map, meta.as_ref(), &src, !args.no_verify)?;
+    let restored = restore_strings(&src, &map, Some(ts_path.to_path_buf()), &opts)
+        .map_err(CliError::from_lib(ts_path))?;
+
+    // 输出文件名：去掉 `_r` 后缀（与压缩扩展名）后追加 `_o.ts`（或 `_o.tsx`）
+    let stem = file_stem(&name_path)?;
+    let name = stem.strip_suffix("_r").unwrap_or(stem);
+    let parent = ts_path.parent().unwrap_or_else(|| Path::new("."));
+    let out_path = parent.join(format!("{}_o.{}", name, ext));
+
+    let code = encode_source(&out_path, &restored.code, encoding)?;
+    write_atomic(&out_path, code).map_err(CliError::io("写入输出 TS 文件", &out_path))?;
+    keep_executable(ts_path, &out_path, &restored.code)?;
+
+    println!(
+        "成功：还原 {} 个字符串，生成 {}",
+        restored.restored,
+        out_path.display()
+    );
+    Ok(())
+}
 
-    let mut parser = Parser::new_from(lexer);
+/// `check` 子命令：`sb_dice check [选项] <输入>...`
+///
+/// 按与提取相同的选项（包括配置文件）在内存中重新提取，与已有的 JSON 映射表逐条比较，
+/// 不写出任何文件；有映射表缺失或过期时以退出码 14 退出，用来在 CI 中发现忘记重新提取的改动。
+fn run_check(mut args: ExtractArgs) -> Result<(), CliError> {
+    let inputs = std::mem::take(&mut args.inputs);
+    if inputs.is_empty() && args.tsconfig.is_none() {
+        print_usage_and_exit();
+    }
+    let mut cli = CliArgs::from(args);
+    if cli.watch || cli.stdout || inputs.iter().any(|i| i == "-") {
+        arg_error("check 不支持 --watch 与管道模式");
+    }
+    cli.opts.excluded = load_decisions(&cli)?.excluded();
+    if cli.split.is_some() {
+        arg_error("check 不支持分块的映射表（--split-map / --split-by）");
+    }
+    if !cli.formats.contains(&MapFormat::Json) {
+        arg_error("check 只检查 JSON 映射表，--format 中需要包含 json");
+    }
 
-    let mut module = match parser.parse_module() {
-        Ok(m) => m,
-        Err(err) => {
-            eprintln!("解析 TypeScript 文件失败: {:?}", err);
-            eprintln!("使用 'sb_dice -h' 或 'sb_dice --help' 查看帮助信息");
-            process::exit(4);
+    let mut files = select_files(&inputs, &cli)?;
+    files.retain(|file| {
+        let declaration = is_declaration_file(file);
+        if declaration {
+            report_skipped(file, "声明文件只列出字符串，没有映射表");
         }
+        !declaration
+    });
+    let mirror = mirror_roots(&inputs, &cli);
+    let map_path = |file: &Path| -> Result<PathBuf, CliError> {
+        let dir = output_dir(file, &mirror, cli.out_dir.as_deref());
+        let paths = output_paths(file, &dir, &cli)?;
+        Ok(mapping_path(&paths.map_base, MapFormat::Json, cli.compress))
+    };
+    let expected = |file: &Path, start_index: usize| -> Result<Vec<Entry>, CliError> {
+        let (src, _) = read_source(file, cli.encoding)?;
+        let opts = ExtractOptions {
+            file_name: Some(file.to_path_buf()),
+            input_source_map: if cli.input_source_map {
+                read_input_source_map(file)?
+            } else {
+                None
+            },
+            source_map: false,
+            start_index,
+            ..cli.opts.clone()
+        };
+        let result = extract_strings(&src, opts).map_err(CliError::from_lib(file))?;
+        report_recovered(Some(file), &result.warnings);
+        Ok(result.entries)
     };
 
-    // --- 遍历并替换 ---
-    let mut replacer = StringReplacer::new();
-    module.visit_mut_with(&mut replacer);
+    let mut checked = 0;
+    let mut outdated = 0;
+    if let Some(combined) = &cli.combined_map {
+        let mut entries = Vec::new();
+        for file in &files {
+            let start = cli.opts.start_index + entries.len();
+            entries.extend(expected(file, start)?.into_iter().map(|entry| Entry {
+                file: Some(slash_path(file)),
+                ..entry
+            }));
+        }
+        let path = mapping_path(&combined.with_extension(""), MapFormat::Json, cli.compress);
+        checked += 1;
+        outdated += usize::from(check_mapping(&path, &entries, None, &cli)?);
+    } else {
+        let work = |file: &PathBuf| -> Result<_, CliError> {
+            Ok((map_path(file)?, expected(file, cli.opts.start_index)?))
+        };
+        let mut result = Ok(());
+        for_each_parallel(&files, cli.jobs, work, |file, (path, entries)| {
+            checked += 1;
+            match check_mapping(&path, &entries, Some(&slash_path(file)), &cli) {
+                Ok(stale) => outdated += usize::from(stale),
+                Err(err) if result.is_ok() => result = Err(err),
+                Err(_) => {}
+            }
+        })?;
+        result?;
+    }
 
-    // --- 代码生成（去掉注释） ---
-    let mut buf = vec![];
+    if outdated > 0 {
+        return Err(CliError::Outdated(outdated));
+    }
+    println!("成功：{} 个映射表都与源码一致", checked);
+    Ok(())
+}
 
-    {
-        let writer = JsWriter::new(cm.clone(), "\n", &mut buf, None);
+/// 比较重新提取出的条目与已有的映射表，不一致（或映射表不存在）时打印差异并返回 `true`
+///
+/// 重新提取的条目按 `--map-format` 指定的结构生成映射表后再比较（`source` 同 [`write_mappings`]）。
+fn check_mapping(
+    path: &Path,
+    entries: &[Entry],
+    source: Option<&str>,
+    cli: &CliArgs,
+) -> Result<bool, CliError> {
+    if !path.is_file() {
+        println!("{}: 缺少映射表", path.display());
+        return Ok(true);
+    }
+    let committed = read_mapping(path, Some(MapFormat::Json))?;
+    let expected = cli.format_opts.schema.mapping(entries, source);
+    let diff = diff_mappings(&committed, &expected);
+    if diff.is_empty() {
+        return Ok(false);
+    }
+    println!("{}: 与源码不一致", path.display());
+    print_mapping_diff(&diff, "  ");
+    Ok(true)
+}
 
-        let mut emitter = Emitter {
-            cfg: Config::default(),
-            cm: cm.clone(),
-            comments: None, // 去掉注释
-            wr: writer,
-        };
+/// 打印映射表的差异：新增(+)、删除(-)、原文变化(~)的字符串，以及只有附加信息变化的 key
+fn print_mapping_diff(diff: &MappingDiff, indent: &str) {
+    let quote = |text: &str| Value::String(text.to_string());
+    for (key, text) in &diff.added {
+        println!("{}+ {} {}", indent, key, quote(text));
+    }
+    for (key, text) in &diff.removed {
+        println!("{}- {} {}", indent, key, quote(text));
+    }
+    for change in &diff.changed {
+        println!(
+            "{}~ {} {} -> {}",
+            indent,
+            change.key,
+            quote(&change.old),
+            quote(&change.new)
+        );
+    }
+    if !diff.metadata.is_empty() {
+        println!(
+            "{}~ {}（位置等附加信息变化）",
+            indent,
+            diff.metadata.join(" ")
+        );
+    }
+}
 
-        if let Err(e) = emitter.emit_module(&module) {
-            eprintln!("生成代码失败: {:?}", e);
-            process::exit(5);
+/// `diff` 子命令：`sb_dice diff [--format text|json|unified] <old_s.json> <new_s.json>`
+///
+/// 按 key 比较两张映射表，方便译者在游戏版本更新后看清到底改了哪些字符串；只输出差异，不写文件。
+fn run_diff(args: DiffArgs) -> Result<(), CliError> {
+    let old = read_mapping(&args.old, None)?;
+    let new = read_mapping(&args.new, None)?;
+    let diff = diff_mappings(&old, &new);
+
+    match args.format {
+        DiffFormat::Text => {
+            print_mapping_diff(&diff, "");
+            println!(
+                "新增 {} 条，删除 {} 条，变化 {} 条，只有附加信息变化 {} 条",
+                diff.added.len(),
+                diff.removed.len(),
+                diff.changed.len(),
+                diff.metadata.len()
+            );
+        }
+        DiffFormat::Json => {
+            let pairs = |items: &[(String, String)]| -> Vec<Value> {
+                items
+                    .iter()
+                    .map(|(key, text)| serde_json::json!({ "key": key, "text": text }))
+                    .collect()
+            };
+            let changed: Vec<Value> = diff
+                .changed
+                .iter()
+                .map(|c| serde_json::json!({ "key": c.key, "old": c.old, "new": c.new }))
+                .collect();
+            let value = serde_json::json!({
+                "added": pairs(&diff.added),
+                "removed": pairs(&diff.removed),
+                "changed": changed,
+                "metadata": diff.metadata,
+            });
+            println!("{:#}", value);
+        }
+        DiffFormat::Unified => {
+            // 删除与变化的旧值记为 -，新增与变化的新值记为 +，按 key 的自然顺序排列
+            let mut lines: Vec<(&str, Option<&str>, Option<&str>)> = Vec::new();
+            lines.extend(
+                diff.removed
+                    .iter()
+                    .map(|(k, t)| (k.as_str(), Some(t.as_str()), None)),
+            );
+            lines.extend(
+                diff.added
+                    .iter()
+                    .map(|(k, t)| (k.as_str(), None, Some(t.as_str()))),
+            );
+            lines.extend(
+                diff.changed
+                    .iter()
+                    .map(|c| (c.key.as_str(), Some(c.old.as_str()), Some(c.new.as_str()))),
+            );
+            lines.sort_by(|a, b| natural_cmp(a.0, b.0));
+
+            let quote = |text: &str| Value::String(text.to_string());
+            println!("--- {}", args.old.display());
+            println!("+++ {}", args.new.display());
+            for (key, old, new) in lines {
+                if let Some(old) = old {
+                    println!("-{}: {}", quote(key), quote(old));
+                }
+                if let Some(new) = new {
+                    println!("+{}: {}", quote(key), quote(new));
+                }
+            }
         }
     }
+    Ok(())
+}
+
+/// `diff-src --format unified` 中差异前后保留的字符串个数
+const DIFF_CONTEXT: usize = 3;
+
+/// `diff-src` 子命令：`sb_dice diff-src [选项] <old.ts> <new.ts>`
+///
+/// 在内存中提取两个文件的字符串（按提取选项筛选），按出现顺序比较（`--unordered` 时不考虑顺序），
+/// 列出删除与新增的字符串及其行号，用来在动手改映射表之前审查上游游戏更新改了哪些文字。
+fn run_diff_src(args: DiffSrcArgs) -> Result<(), CliError> {
+    let mut base = ExtractOptions {
+        with_locations: true,
+        ..ExtractOptions::default()
+    };
+    args.filter.apply(&mut base);
+    let extract = |path: &Path| -> Result<Vec<Entry>, CliError> {
+        ensure_input(path)?;
+        let src = read_input(path)?;
+        let opts = ExtractOptions {
+            file_name: Some(path.to_path_buf()),
+            ..base.clone()
+        };
+        let result = extract_strings(&src, opts).map_err(CliError::from_lib(path))?;
+        Ok(result.entries)
+    };
+    let old = extract(&args.old)?;
+    let new = extract(&args.new)?;
+
+    let texts =
+        |entries: &[Entry]| -> Vec<String> { entries.iter().map(|e| e.original.clone()).collect() };
+    let (old_texts, new_texts) = (texts(&old), texts(&new));
+    let edits = if args.unordered {
+        diff_multisets(&old_texts, &new_texts)
+    } else {
+        diff_sequences(&old_texts, &new_texts)
+    };
+    let line = |entry: &Entry| entry.locations.first().map_or(0, |l| l.line);
+    let quote = |text: &str| Value::String(text.to_string());
+    let removed = edits
+        .iter()
+        .filter(|e| matches!(e, SeqEdit::Removed(_)))
+        .count();
+    let added = edits
+        .iter()
+        .filter(|e| matches!(e, SeqEdit::Added(_)))
+        .count();
 
-    let output_code = match String::from_utf8(buf) {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("输出编码转换失败: {}", e);
-            process::exit(6);
+    match args.format {
+        DiffFormat::Text => {
+            for edit in &edits {
+                match *edit {
+                    SeqEdit::Removed(i) => println!(
+                        "- {}:{} {}",
+                        args.old.display(),
+                        line(&old[i]),
+                        quote(&old[i].original)
+                    ),
+                    SeqEdit::Added(j) => println!(
+                        "+ {}:{} {}",
+                        args.new.display(),
+                        line(&new[j]),
+                        quote(&new[j].original)
+                    ),
+                    SeqEdit::Same(..) => {}
+                }
+            }
+            println!(
+                "旧文件 {} 个字符串，新文件 {} 个字符串；删除 {} 个，新增 {} 个",
+                old.len(),
+                new.len(),
+                removed,
+                added
+            );
         }
+        DiffFormat::Json => {
+            let item =
+                |entry: &Entry| serde_json::json!({ "text": entry.original, "line": line(entry) });
+            let mut value = serde_json::json!({
+                "old": slash_path(&args.old),
+                "new": slash_path(&args.new),
+                "removed": [],
+                "added": [],
+            });
+            for edit in &edits {
+                match *edit {
+                    SeqEdit::Removed(i) => {
+                        value["removed"].as_array_mut().unwrap().push(item(&old[i]))
+                    }
+                    SeqEdit::Added(j) => value["added"].as_array_mut().unwrap().push(item(&new[j])),
+                    SeqEdit::Same(..) => {}
+                }
+            }
+            println!("{:#}", value);
+        }
+        DiffFormat::Unified => {
+            // 只输出差异及其前后 DIFF_CONTEXT 个没有变化的字符串，每段以 @@ -旧行号 +新行号 @@ 开头
+            println!("--- {}", args.old.display());
+            println!("+++ {}", args.new.display());
+            let changed: Vec<bool> = edits
+                .iter()
+                .map(|e| !matches!(e, SeqEdit::Same(..)))
+                .collect();
+            let near = |i: usize| {
+                let end = (i + DIFF_CONTEXT).min(edits.len() - 1);
+                changed[i.saturating_sub(DIFF_CONTEXT)..=end].contains(&true)
+            };
+            let (mut next_old, mut next_new) = (0, 0);
+            let mut in_hunk = false;
+            for (i, edit) in edits.iter().enumerate() {
+                if !near(i) {
+                    in_hunk = false;
+                } else {
+                    if !in_hunk {
+                        let old_line = old.get(next_old).map_or(0, line);
+                        let new_line = new.get(next_new).map_or(0, line);
+                        println!("@@ -{} +{} @@", old_line, new_line);
+                        in_hunk = true;
+                    }
+                    match *edit {
+                        SeqEdit::Same(i, _) => println!(" {}", quote(&old[i].original)),
+                        SeqEdit::Removed(i) => println!("-{}", quote(&old[i].original)),
+                        SeqEdit::Added(j) => println!("+{}", quote(&new[j].original)),
+                    }
+                }
+                match edit {
+                    SeqEdit::Same(..) => (next_old, next_new) = (next_old + 1, next_new + 1),
+                    SeqEdit::Removed(_) => next_old += 1,
+                    SeqEdit::Added(_) => next_new += 1,
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `renumber` 子命令：`sb_dice renumber [选项] <s.json> --from <old.ts> --to <new.ts>`
+///
+/// 用与生成映射表时相同的选项（筛选与 key 选项要一致）在内存中提取旧、新源文件，按原文把旧 key
+/// 对应到新 key，写出改用新 key 的映射表与 key 迁移表，下游引用旧 key 的补丁可以据此自动更新。
+fn run_renumber(args: RenumberArgs) -> Result<(), CliError> {
+    let map = read_mapping(&args.map, Some(MapFormat::Json))?;
+    let mut base = ExtractOptions::default();
+    args.filter.apply(&mut base);
+    args.keys.apply(&mut base);
+    let extract = |path: &Path| -> Result<Vec<Entry>, CliError> {
+        ensure_input(path)?;
+        let src = read_input(path)?;
+        let opts = ExtractOptions {
+            file_name: Some(path.to_path_buf()),
+            ..base.clone()
+        };
+        let result = extract_strings(&src, opts).map_err(CliError::from_lib(path))?;
+        Ok(result.entries)
     };
+    let old = extract(&args.from)?;
+    let new = extract(&args.to)?;
+    let result = renumber_mapping(&map, &old, &new);
 
-    // --- 写入输出文件 ---
-    // 构造输出文件名：原名_r.ts 与 原名_s.json
-    let stem = match path.file_stem().and_then(|s| s.to_str()) {
-        Some(s) => s.to_string(),
-        None => {
-            eprintln!("无法解析输入文件名");
-            process::exit(7);
+    // 输出文件名：去掉 `_s` 后缀（与压缩扩展名）后追加 `_n.json` / `_keys.json`
+    let name_path = uncompressed_path(&args.map);
+    let stem = file_stem(&name_path)?;
+    let name = stem.strip_suffix("_s").unwrap_or(stem);
+    let parent = args.map.parent().unwrap_or_else(|| Path::new("."));
+    let out_path = args
+        .output
+        .unwrap_or_else(|| parent.join(format!("{}_n.json", name)));
+    let migration_path = args
+        .migration
+        .unwrap_or_else(|| parent.join(format!("{}_keys.json", name)));
+
+    if !result.unknown.is_empty() {
+        warn(
+            "unknown-key",
+            Some(&args.map),
+            &format!(
+                "{} 个 key 不在 {} 的提取结果中，已跳过（映射表与旧源文件或提取选项不对应？）：{}",
+                result.unknown.len(),
+                args.from.display(),
+                result.unknown.join(", ")
+            ),
+        );
+    }
+    write_json(&out_path, &Value::Object(result.mapping))?;
+    write_json(&migration_path, &Value::Object(result.migration))?;
+
+    for key in &result.removed {
+        println!("- {}", key);
+    }
+    for key in &result.added {
+        println!("+ {}", key);
+    }
+    println!(
+        "成功：{} 条改了 key，删除 {} 条，新增 {} 条（映射表中没有，需要重新提取或合并），生成 {} 与 {}",
+        result.moved,
+        result.removed.len(),
+        result.added.len(),
+        out_path.display(),
+        migration_path.display()
+    );
+    Ok(())
+}
+
+/// 取舍记录的默认文件（在当前目录下，`--decisions-file` 可以改）
+const DECISIONS_FILE: &str = ".sb_dice_decisions.json";
+
+/// `review` 中字符串上下各显示的源码行数
+const REVIEW_CONTEXT: usize = 2;
+
+/// `review` 的操作说明
+const REVIEW_HELP: &str = "回车 保持当前决定并继续 / y 替换 / n 不替换 / b 上一条 / \
+i <正则> 匹配的全部替换 / x <正则> 匹配的全部不替换 / l 列出全部 / w 完成并替换 / q 放弃";
+
+/// 读取取舍记录（文件不存在时为空）
+fn read_decisions(path: &Path) -> Result<Decisions, CliError> {
+    match fs::read_to_string(path) {
+        Ok(text) => Decisions::parse(&text).map_err(|message| CliError::Decisions {
+            path: path.to_path_buf(),
+            message,
+        }),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Decisions::default()),
+        Err(e) => Err(CliError::io("读取取舍记录", path)(e)),
+    }
+}
+
+/// 读取 `--decisions-file` 指定的取舍记录；`--no-decisions` 时为空
+fn load_decisions(cli: &CliArgs) -> Result<Decisions, CliError> {
+    match &cli.decisions_file {
+        Some(path) => read_decisions(path),
+        None => Ok(Decisions::default()),
+    }
+}
+
+/// `review` 中的一个候选字符串：同一个文件中内容相同的字符串合为一条
+struct Candidate {
+    /// 标识（文件路径 + 内容的 xxhash64）
+    hash: String,
+    text: String,
+    /// 每次出现的位置
+    locations: Vec<Location>,
+    /// 是否替换
+    include: bool,
+    /// 本次是否确认过（逐条确认或按正则批量决定）；没有确认过的不写入取舍记录，下次再询问
+    decided: bool,
+}
+
+/// `review` 子命令：`sb_dice review [选项] <file.ts>...`
+///
+/// 逐个显示将被替换的字符串与前后几行源码，由用户决定是否替换（也可以按正则批量决定），
+/// 完成后把取舍写入 `.sb_dice_decisions.json`，再按与 `extract` 相同的选项只替换确认过的字符串。
+/// 默认只询问还没有记录的字符串，已有记录的沿用上次的决定；`--all` 时全部重新确认，
+/// 以上次的决定为初始状态。中途放弃时不写出任何文件。
+fn run_review(args: ReviewArgs) -> Result<(), CliError> {
+    let ReviewArgs {
+        all,
+        extract: mut args,
+    } = args;
+    let inputs = std::mem::take(&mut args.inputs);
+    if inputs.is_empty() && args.tsconfig.is_none() {
+        print_usage_and_exit();
+    }
+    let mut cli = CliArgs::from(args);
+    if cli.watch || cli.stdout || inputs.iter().any(|i| i == "-") {
+        arg_error("review 需要在终端中逐条确认，不支持 --watch 与管道模式");
+    }
+    let Some(path) = cli.decisions_file.clone() else {
+        arg_error("review 需要写入取舍记录，不能与 --no-decisions 同时使用");
+    };
+
+    let mut files = select_files(&inputs, &cli)?;
+    files.retain(|file| !is_declaration_file(file));
+    let mut decisions = read_decisions(&path)?;
+    println!("{}", REVIEW_HELP);
+    for file in &files {
+        let (src, mut candidates) = review_candidates(file, &cli, &decisions)?;
+        if !all {
+            let before = candidates.len();
+            candidates.retain(|c| !decisions.records.contains_key(&c.hash));
+            if candidates.len() < before {
+                println!(
+                    "{}: {} 个字符串沿用已有的决定（--all 可重新确认）",
+                    file.display(),
+                    before - candidates.len()
+                );
+            }
+        }
+        if candidates.is_empty() {
+            println!("{}: 没有需要确认的字符串", file.display());
+            continue;
+        }
+        if !review_file(file, &src, &mut candidates)? {
+            println!("已放弃，没有写出任何文件");
+            return Ok(());
+        }
+        let pending = candidates.iter().filter(|c| !c.decided).count();
+        if pending > 0 {
+            println!(
+                "{}: {} 个字符串没有确认，仍按默认替换，下次 review 时再询问",
+                file.display(),
+                pending
+            );
+        }
+        for candidate in candidates.into_iter().filter(|c| c.decided) {
+            let decision = Decision {
+                file: slash_path(file),
+                text: candidate.text,
+                include: candidate.include,
+            };
+            decisions.records.insert(candidate.hash, decision);
         }
+    }
+    write_atomic(&path, decisions.to_json()).map_err(CliError::io("写入取舍记录", &path))?;
+    println!("取舍已记入 {}", path.display());
+
+    cli.opts.excluded = decisions.excluded();
+    let mirror = mirror_roots(&inputs, &cli);
+    extract_files(&files, &mirror, &cli)
+}
+
+/// 在内存中提取文件中的全部候选字符串（不考虑已有的取舍），初始状态取已有的记录，没有记录时为替换
+fn review_candidates(
+    file: &Path,
+    cli: &CliArgs,
+    decisions: &Decisions,
+) -> Result<(String, Vec<Candidate>), CliError> {
+    let (src, _) = read_source(file, cli.encoding)?;
+    let opts = ExtractOptions {
+        file_name: Some(file.to_path_buf()),
+        with_locations: true,
+        excluded: BTreeSet::new(),
+        source_map: false,
+        ..cli.opts.clone()
     };
+    let result = extract_strings(&src, opts).map_err(CliError::from_lib(file))?;
 
-    let parent = path.parent().unwrap_or_else(|| Path::new("."));
-    let out_ts_path = parent.join(format!("{}_r.ts", stem));
-    let out_json_path = parent.join(format!("{}_s.json", stem));
+    let mut candidates: Vec<Candidate> = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for entry in result.entries {
+        let hash = string_hash(Some(file), &entry.original);
+        if let Some(&i) = seen.get(&hash) {
+            candidates[i].locations.extend(entry.locations);
+            continue;
+        }
+        seen.insert(hash.clone(), candidates.len());
+        let include = decisions.records.get(&hash).is_none_or(|d| d.include);
+        candidates.push(Candidate {
+            hash,
+            text: entry.original,
+            locations: entry.locations,
+            include,
+            decided: false,
+        });
+    }
+    Ok((src, candidates))
+}
 
-    // 写 ts 文件
-    if let Err(e) = fs::write(&out_ts_path, output_code) {
-        eprintln!("写入输出 TS 文件失败 {}: {}", out_ts_path.display(), e);
-        process::exit(8);
+/// 显示一个候选字符串：位置、当前的决定、前后几行源码（所在行以 > 标出）与字符串本身
+fn print_candidate(file: &Path, lines: &[&str], candidates: &[Candidate], i: usize) {
+    let candidate = &candidates[i];
+    let state = if candidate.include {
+        "替换"
+    } else {
+        "不替换"
+    };
+    let more = match candidate.locations.len() {
+        0 | 1 => String::new(),
+        n => format!("（共 {} 处）", n),
+    };
+    println!();
+    match candidate.locations.first() {
+        Some(loc) => {
+            println!(
+                "[{}/{}] {}:{}:{}{}  当前：{}",
+                i + 1,
+                candidates.len(),
+                file.display(),
+                loc.line,
+                loc.column,
+                more,
+                state
+            );
+            let first = loc.line.saturating_sub(REVIEW_CONTEXT).max(1);
+            let last = (loc.line + REVIEW_CONTEXT).min(lines.len());
+            let width = last.to_string().len();
+            for n in first..=last {
+                let marker = if n == loc.line { '>' } else { ' ' };
+                println!("{} {:>width$} | {}", marker, n, lines[n - 1], width = width);
+            }
+        }
+        None => println!(
+            "[{}/{}] {}  当前：{}",
+            i + 1,
+            candidates.len(),
+            file.display(),
+            state
+        ),
     }
+    println!("  {}", Value::String(candidate.text.clone()));
+}
 
-    // 生成 JSON 映射：{"0": "原始0", "1": "原始1", ...}
-    let mut map = Map::new();
-    for (idx, orig) in replacer.originals.iter().enumerate() {
-        map.insert(idx.to_string(), Value::String(orig.clone()));
+/// 在终端中逐条确认一个文件的候选字符串；返回 false 表示放弃（`q` 或输入结束）
+fn review_file(file: &Path, src: &str, candidates: &mut [Candidate]) -> Result<bool, CliError> {
+    let lines: Vec<&str> = src.lines().collect();
+    let stdin = io::stdin();
+    let mut i = 0;
+    let mut show = true;
+    loop {
+        if show {
+            match candidates.get(i) {
+                Some(_) => print_candidate(file, &lines, candidates, i),
+                None => println!("\n已是最后一条：w 完成并替换，b 返回上一条，l 列出全部"),
+            }
+        }
+        show = true;
+        print!("[回车/y/n/b/i/x/l/w/q] > ");
+        let mut input = String::new();
+        let read = io::stdout()
+            .flush()
+            .and_then(|_| stdin.lock().read_line(&mut input))
+            .map_err(|source| CliError::Stdio {
+                action: "读取终端输入",
+                source,
+            })?;
+        if read == 0 {
+            return Ok(false);
+        }
+        let input = input.trim();
+        let (command, pattern) = input
+            .split_once(char::is_whitespace)
+            .map_or((input, ""), |(c, p)| (c, p.trim()));
+        let at_end = i == candidates.len();
+        match command {
+            "" | "y" | "n" if at_end => show = false,
+            "" => {
+                candidates[i].decided = true;
+                i += 1;
+            }
+            "y" | "n" => {
+                candidates[i].include = command == "y";
+                candidates[i].decided = true;
+                i += 1;
+            }
+            "b" => i = i.saturating_sub(1),
+            "i" | "x" if pattern.is_empty() => {
+                println!("用法：{} <正则>", command);
+                show = false;
+            }
+            "i" | "x" => {
+                match Regex::new(pattern) {
+                    Ok(re) => {
+                        let include = command == "i";
+                        let mut matched = 0;
+                        for candidate in candidates.iter_mut().filter(|c| re.is_match(&c.text)) {
+                            candidate.include = include;
+                            candidate.decided = true;
+                            matched += 1;
+                        }
+                        let state = if include { "替换" } else { "不替换" };
+                        println!("{} 条匹配 {}，已标记为{}", matched, pattern, state);
+                    }
+                    Err(e) => println!("无效的正则：{}", e),
+                }
+                show = false;
+            }
+            "l" => {
+                for (n, candidate) in candidates.iter().enumerate() {
+                    let mark = if candidate.include { '+' } else { '-' };
+                    let line = candidate.locations.first().map_or(0, |l| l.line);
+                    let text = Value::String(candidate.text.clone());
+                    println!(
+                        "{} {:>4}  {}:{} {}",
+                        mark,
+                        n + 1,
+                        file.display(),
+                        line,
+                        text
+                    );
+                }
+                show = false;
+            }
+            "w" => return Ok(true),
+            "q" => return Ok(false),
+            _ => {
+                println!("无法识别的操作 {}（{}）", input, REVIEW_HELP);
+                show = false;
+            }
+        }
     }
+}
 
-    let json_text = match serde_json::to_string_pretty(&Value::Object(map)) {
-        Ok(j) => j,
-        Err(e) => {
-            eprintln!("生成 JSON 失败: {}", e);
-            process::exit(9);
+/// `check-placeholders` 子命令：`sb_dice check-placeholders <source.json> <translated.json>`
+///
+/// 列出译文中丢失、多出或改名的占位符；有问题时以退出码 13 退出，方便在 CI 中使用。
+fn run_check_placeholders(args: CheckArgs) -> Result<(), CliError> {
+    let source = read_mapping(&args.source, None)?;
+    let translated = read_mapping(&args.translated, Some(MapFormat::Json))?;
+
+    let issues = check_placeholders(&source, &translated);
+    for issue in &issues {
+        let kind = match (issue.missing.is_empty(), issue.extra.is_empty()) {
+            (false, false) => "改名",
+            (false, true) => "丢失",
+            _ => "多出",
+        };
+        let mut detail = Vec::new();
+        if !issue.missing.is_empty() {
+            detail.push(format!("缺少 {}", issue.missing.join(" ")));
         }
+        if !issue.extra.is_empty() {
+            detail.push(format!("多出 {}", issue.extra.join(" ")));
+        }
+        println!("{} [{}] {}", issue.key, kind, detail.join("，"));
+    }
+
+    if !issues.is_empty() {
+        return Err(CliError::Placeholders(issues.len()));
+    }
+    println!("成功：占位符全部一致");
+    Ok(())
+}
+
+/// `init` 检测源码目录时依次尝试的目录名
+const SOURCE_DIRS: &[&str] = &["src", "scripts", "source", "lib", "app"];
+
+/// `init` 子命令：`sb_dice init [--force]`
+///
+/// 在当前目录写出带注释的配置文件 `sb_dice.toml`：有 tsconfig.json 时按它选择输入（`tsconfig`），
+/// 否则取检测到的源码目录（都没有时为当前目录）；有 node_modules 时一并排除。
+fn run_init(args: InitArgs) -> Result<(), CliError> {
+    let path = Path::new(CONFIG_FILE);
+    if path.exists() && !args.force {
+        return Err(CliError::OutputExists(vec![path.display().to_string()]));
+    }
+
+    let tsconfig = Path::new("tsconfig.json").is_file();
+    let dirs: Vec<String> = SOURCE_DIRS
+        .iter()
+        .filter(|d| Path::new(d).is_dir())
+        .map(|d| d.to_string())
+        .collect();
+    let inputs = if tsconfig {
+        println!("检测到 tsconfig.json，按其中的 files / include / exclude 选择输入");
+        Vec::new()
+    } else if dirs.is_empty() {
+        println!("没有检测到源码目录，输入为当前目录");
+        vec![".".to_string()]
+    } else {
+        println!("检测到源码目录：{}", dirs.join("、"));
+        dirs
+    };
+    let mut excludes = Vec::new();
+    if Path::new("node_modules").is_dir() {
+        excludes.push("**/node_modules/**".to_string());
+    }
+
+    write_atomic(path, render_config(&inputs, tsconfig, &excludes))
+        .map_err(CliError::io("写入配置文件", path))?;
+    println!("成功：生成 {}", path.display());
+    Ok(())
+}
+
+/// `init` 写出的配置文件内容；`tsconfig` 时按 tsconfig.json 选择输入，`inputs` 留空
+fn render_config(inputs: &[String], tsconfig: bool, excludes: &[String]) -> String {
+    let list = |items: &[String]| {
+        items
+            .iter()
+            .map(|item| toml::Value::String(item.clone()).to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
     };
+    let inputs = if tsconfig {
+        r#"# 按 tsconfig.json 的 files / include / exclude 选择要处理的文件
+tsconfig = "tsconfig.json"
 
-    if let Err(e) = fs::write(&out_json_path, json_text) {
-        eprintln!("写入输出 JSON 文件失败 {}: {}", out_json_path.display(), e);
-        process::exit(10);
+# 另外要处理的文件、目录或 glob 模式；命令行上给出输入（或 --tsconfig）时与 tsconfig 一起忽略
+# inputs = ["scripts"]"#
+            .to_string()
+    } else {
+        format!(
+            r#"# 要处理的文件、目录或 glob 模式；命令行上给出输入（或 --tsconfig）时忽略
+inputs = [{}]
+
+# 也可以按 tsconfig.json 的 files / include / exclude 选择要处理的文件
+# tsconfig = "tsconfig.json""#,
+            list(inputs)
+        )
+    };
+    format!(
+        r#"# sb_dice 配置文件（由 sb_dice init 生成）
+#
+# 在这个目录下运行 sb_dice（或 sb_dice extract、sb_dice check）时自动读取，也可以用 --config 指定其他文件。
+# 每一项对应一个同名的命令行选项（去掉开头的 --）：开关写 true，可重复的选项写成数组。
+# 命令行上给出的选项优先；--exclude、--format 等可重复的选项与这里的合并。
+
+{}
+
+# 排除匹配这些 glob 模式的文件
+exclude = [{}]
+
+# 映射表格式：json（默认）、po、xliff、csv、tsv、fluent、i18next、dts、msgpack、bincode、sqlite
+# format = ["json"]
+
+# 输出写到单独的目录中（保持输入的目录结构），不在源码目录旁生成文件
+# out-dir = "build/i18n"
+
+# 只替换包含中日韩文字的字符串
+# only-cjk = true
+
+# 相同的字符串共用同一个 key
+# dedupe = true
+
+# key 以文件名为前缀，合并多个映射表时不会冲突
+# namespace = "file"
+
+# 把字符串替换为查表调用 S(0)，并生成查表模块
+# wrap-call = "S"
+# emit-loader = true
+"#,
+        inputs,
+        list(excludes)
+    )
+}
+
+/// `dupes` 子命令：`sb_dice dupes <s.json>`
+///
+/// 按去掉空白与标点、忽略大小写后的原文给映射表中的记录分组，列出每组的 key 与原文，
+/// 只输出结果，不改动映射表。
+fn run_dupes(args: DupesArgs) -> Result<(), CliError> {
+    let map = read_mapping(&args.map, None)?;
+    let groups = find_dupes(&map);
+    for group in &groups {
+        println!("{} 条：", group.entries.len());
+        for (key, original) in &group.entries {
+            println!("  {} {}", key, Value::String(original.clone()));
+        }
+    }
+    if groups.is_empty() {
+        println!("没有发现近似重复的字符串");
+    } else {
+        let entries: usize = groups.iter().map(|g| g.entries.len()).sum();
+        println!(
+            "共 {} 组近似重复，涉及 {} 条记录（合并后可减少 {} 条）",
+            groups.len(),
+            entries,
+            entries - groups.len()
+        );
     }
+    Ok(())
+}
 
+/// `glossary` 子命令：`sb_dice glossary [选项] <s.json>... [-o glossary.csv]`
+///
+/// 统计各映射表原文中反复出现的中日韩词语，按出现次数写成 CSV 术语表，
+/// 译文列留空，翻译团队据此先统一术语的译法。
+fn run_glossary(args: GlossaryArgs) -> Result<(), CliError> {
+    let maps = args
+        .maps
+        .iter()
+        .map(|path| read_mapping(path, None))
+        .collect::<Result<Vec<_>, _>>()?;
+    let opts = GlossaryOptions {
+        min_count: args.min_count,
+        min_len: args.min_len,
+        max_len: args.max_len,
+    };
+    let terms = build_glossary(&maps, &opts);
+    write_atomic(&args.output, render_glossary(&terms))
+        .map_err(CliError::io("写入术语表", &args.output))?;
     println!(
-        "成功：生成 {} 与 {}",
-        out_ts_path.display(),
-        out_json_path.display()
+        "成功：{} 个词语（至少出现 {} 次），生成 {}",
+        terms.len(),
+        opts.min_count,
+        args.output.display()
     );
+    Ok(())
+}
+
+/// `stats` 中最长字符串最多显示的字符数
+const STATS_PREVIEW: usize = 40;
+
+/// `stats` 子命令：`sb_dice stats [选项] <输入>...`
+///
+/// 按提取选项统计每个文件与合计的字符串个数、字符数、重复与语言分布，不写出任何文件，
+/// 用来在提取之前估算翻译工作量。
+fn run_stats(args: StatsArgs) -> Result<(), CliError> {
+    // 不去重，每次出现都计入，重复由统计本身给出
+    let mut base = ExtractOptions::default();
+    args.filter.apply(&mut base);
+
+    let files = collect_inputs(
+        &args.inputs,
+        args.tsconfig.as_deref(),
+        &args.exclude,
+        args.include_dts,
+    )?;
+    let mut all = Vec::new();
+    for file in &files {
+        let mut src = read_input(file)?;
+        // 分块映射表的索引：统计合并后的各块，而不是索引本身
+        if is_chunk_index(&src) {
+            src = Value::Object(read_mapping(file, Some(MapFormat::Json))?).to_string();
+        }
+        let opts = ExtractOptions {
+            file_name: Some(file.clone()),
+            ..base.clone()
+        };
+        let result = extract_strings(&src, opts).map_err(CliError::from_lib(file))?;
+        let stats = StringStats::from_entries(&result.entries);
+        println!(
+            "{}: {} 个字符串，{} 个字符",
+            file.display(),
+            stats.total.strings,
+            stats.total.chars
+        );
+        all.extend(result.entries);
+    }
+
+    let stats = StringStats::from_entries(&all);
+    println!(
+        "合计：{} 个文件，{} 个字符串，{} 个字符",
+        files.len(),
+        stats.total.strings,
+        stats.total.chars
+    );
+    println!(
+        "  重复：{} 个（去重后 {} 个）",
+        stats.duplicates,
+        stats.total.strings - stats.duplicates
+    );
+    for (name, tally) in [
+        ("中日韩", stats.cjk),
+        ("纯 ASCII", stats.ascii),
+        ("其他", stats.other),
+    ] {
+        println!(
+            "  {}：{} 个字符串，{} 个字符",
+            name, tally.strings, tally.chars
+        );
+    }
+    if let Some(longest) = &stats.longest {
+        let chars = longest.chars().count();
+        let mut preview: String = longest.chars().take(STATS_PREVIEW).collect();
+        if chars > STATS_PREVIEW {
+            preview.push('…');
+        }
+        println!("  最长：{} 个字符 {}", chars, Value::String(preview));
+    }
+    Ok(())
+}
+
+/// `apply` 子命令：`sb_dice apply <file_r.ts> <translated.json> [-o <file_zh.ts>]`
+///
+/// 把替换后文件中的索引字符串直接替换为译文，没有译文的字符串退回原文；
+/// 未指定 `-o` 时输出 `<name>_t.ts`。
+fn run_apply(args: ApplyArgs) -> Result<(), CliError> {
+    let ts_path = args.file.as_path();
+    let name_path = uncompressed_path(ts_path);
+    let ext = ensure_input(&name_path)?;
+
+    let (src, encoding) = read_source(ts_path, args.style.encoding)?;
+    let (map, meta) = read_mapping_meta(&args.map, args.from)?;
+
+    let mut opts = args.style.restore_options();
+    opts.preserve_format =
+        args.preserve_format || meta.as_ref().is_some_and(MapMeta::preserve_format);
+    if let Some(meta) = &meta {
+        verify_meta(&args.map, meta, opts.wrap_call.as_deref())?;
+    }
+    let src = verify_link(&args.map, &map, meta.as_ref(), &src, !args.no_verify)?;
+    let applied = apply_translations(&src, &map, Some(ts_path.to_path_buf()), &opts)
+        .map_err(CliError::from_lib(ts_path))?;
+
+    // 输出文件名：去掉 `_r` 后缀后追加 `_t.ts`（或 `_t.tsx`）
+    let out_path = match args.output {
+        Some(path) => path,
+        None => {
+            let stem = file_stem(&name_path)?;
+            let name = stem.strip_suffix("_r").unwrap_or(stem);
+            let parent = ts_path.parent().unwrap_or_else(|| Path::new("."));
+            parent.join(format!("{}_t.{}", name, ext))
+        }
+    };
+
+    let code = encode_source(&out_path, &applied.code, encoding)?;
+    write_atomic(&out_path, code).map_err(CliError::io("写入输出 TS 文件", &out_path))?;
+    keep_executable(ts_path, &out_path, &applied.code)?;
+
+    if applied.untranslated > 0 {
+        warn(
+            "untranslated",
+            Some(ts_path),
+            &format!(
+                "警告：{} 个字符串没有译文，已使用原文",
+                applied.untranslated
+            ),
+        );
+    }
+    println!(
+        "成功：替换 {} 个字符串，生成 {}",
+        applied.restored,
+        out_path.display()
+    );
+    Ok(())
+}
+
+/// 递归收集目录下的所有支持的输入文件（按路径排序，保证输出顺序稳定）
+///
+/// 会跳过本工具自己生成的 `_r.ts` / `_o.ts` / `_t.ts` / `_strings.ts`（以及其他扩展名的对应文件），避免重复处理。
+fn collect_ts_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), CliError> {
+    let entries = fs::read_dir(dir).map_err(CliError::io("读取目录", dir))?;
+
+    let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+    paths.sort();
+
+    for p in paths {
+        if p.is_dir() {
+            collect_ts_files(&p, files)?;
+            continue;
+        }
+        if is_source_file(&p) {
+            files.push(p);
+        } else if input_ext(&p).is_some() {
+            report_skipped(&p, "本工具生成的文件");
+        }
+    }
+    Ok(())
+}
+
+/// 判断字符串是否含有 glob 通配符
+fn has_glob_chars(s: &str) -> bool {
+    s.contains(['*', '?', '['])
+}
+
+/// 取 glob 模式中不含通配符的前缀目录（监视模式下监视该目录）
+fn glob_root(pattern: &str) -> PathBuf {
+    let root: PathBuf = Path::new(pattern)
+        .components()
+        .take_while(|c| !has_glob_chars(&c.as_os_str().to_string_lossy()))
+        .collect();
+    if root.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        root
+    }
+}
+
+/// `--out-dir` 时输入对应的镜像根目录：目录为其本身，glob 为不含通配符的前缀，文件为所在目录
+fn mirror_root(input: &str) -> PathBuf {
+    let path = Path::new(input);
+    if path.is_dir() {
+        path.to_path_buf()
+    } else if has_glob_chars(input) && !path.exists() {
+        glob_root(input)
+    } else {
+        parent_dir(path).to_path_buf()
+    }
+}
+
+/// 文件所在目录，没有父目录时为当前目录
+fn parent_dir(path: &Path) -> &Path {
+    path.parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."))
+}
+
+/// 文件名（不含目录）
+fn file_name(path: &Path) -> &OsStr {
+    path.file_name().unwrap_or_default()
+}
+
+/// 输出文件所在目录：未指定 `--out-dir` 时为输入文件所在目录，
+/// 否则为输出目录下的对应位置，保持输入相对于其镜像根目录的目录结构
+fn output_dir(file: &Path, mirror: &[PathBuf], out_dir: Option<&Path>) -> PathBuf {
+    let parent = parent_dir(file);
+    let Some(out_dir) = out_dir else {
+        return parent.to_path_buf();
+    };
+
+    // 监视模式下收到的是绝对路径，统一转换后再比较；有多个根目录包含该文件时取最深的一个
+    let absolute = |p: &Path| std::path::absolute(p).unwrap_or_else(|_| p.to_path_buf());
+    let parent = absolute(parent);
+    let relative = mirror
+        .iter()
+        .filter_map(|root| parent.strip_prefix(absolute(root)).ok())
+        .min_by_key(|rel| rel.components().count())
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    out_dir.join(relative)
+}
+
+/// 判断路径是否被 `--exclude` 排除
+///
+/// 同时尝试原路径与相对于当前目录的路径，监视模式下收到的绝对路径也能匹配相对模式。
+fn is_excluded(path: &Path, excludes: &[Pattern]) -> bool {
+    if excludes.is_empty() {
+        return false;
+    }
+    let cwd = env::current_dir().unwrap_or_default();
+    let relative = path.strip_prefix(&cwd).unwrap_or(path);
+    excludes
+        .iter()
+        .any(|p| p.matches_path(path) || p.matches_path(relative))
+}
+
+/// 遍历目录、展开 glob 或按 tsconfig 选出的文件是否保留：`.d.ts` 声明文件默认跳过，
+/// 其中只有类型层面的字符串（`--include-dts` 时保留，只列出字符串）
+fn keep_walked(file: &Path, include_dts: bool) -> bool {
+    if include_dts || !is_declaration_file(file) {
+        return true;
+    }
+    report_skipped(file, "声明文件（--include-dts 时包含）");
+    false
+}
+
+/// 展开所有输入（文件、目录或 glob 模式），去掉被排除的文件；保持输入顺序并去重
+///
+/// 命令行上直接给出的声明文件总会保留，见 [`keep_walked`]。
+fn expand_inputs(
+    inputs: &[String],
+    excludes: &[Pattern],
+    include_dts: bool,
+) -> Result<Vec<PathBuf>, CliError> {
+    let mut files = Vec::new();
+
+    for input in inputs {
+        let path = Path::new(input);
+        if path.is_dir() {
+            // 目录：递归处理其中所有支持的输入文件
+            let mut walked = Vec::new();
+            collect_ts_files(path, &mut walked)?;
+            files.extend(walked.into_iter().filter(|p| keep_walked(p, include_dts)));
+        } else if has_glob_chars(input) && !path.exists() {
+            let paths = glob::glob(input)
+                .map_err(|e| CliError::Usage(format!("无效的 glob 模式 {}: {}", input, e)))?;
+            files.extend(
+                paths
+                    .filter_map(|p| p.ok())
+                    .filter(|p| is_source_file(p) && keep_walked(p, include_dts)),
+            );
+        } else {
+            ensure_input(path)?;
+            files.push(path.to_path_buf());
+        }
+    }
+
+    let mut seen = HashSet::new();
+    files.retain(|f| {
+        if is_excluded(f, excludes) {
+            report_skipped(f, "被 --exclude 排除");
+            return false;
+        }
+        seen.insert(f.clone())
+    });
+    Ok(files)
+}
+
+/// 展开命令行上的输入，并加上 `--tsconfig` 选中的文件（同样经过 `--exclude` 筛选）
+fn collect_inputs(
+    inputs: &[String],
+    tsconfig: Option<&Path>,
+    excludes: &[Pattern],
+    include_dts: bool,
+) -> Result<Vec<PathBuf>, CliError> {
+    let mut files = expand_inputs(inputs, excludes, include_dts)?;
+    if let Some(path) = tsconfig {
+        for file in tsconfig_files(path)? {
+            if is_excluded(&file, excludes) {
+                report_skipped(&file, "被 --exclude 排除");
+            } else if keep_walked(&file, include_dts) && !files.contains(&file) {
+                files.push(file);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// tsc 未给出 `exclude` 时默认排除的目录（另外还有 `outDir`）
+const TSCONFIG_DEFAULT_EXCLUDE: &[&str] = &["node_modules", "bower_components", "jspm_packages"];
+
+/// `--tsconfig`：按 tsconfig.json 选出要处理的文件，规则与 tsc 一致
+///
+/// `files` 与 `include` 都没有时包含配置文件所在目录下的全部文件；`exclude` 只作用于 `include`
+/// 的结果，`files` 中列出的文件总会处理；没有 `exclude` 时排除 node_modules 等目录与 `outDir`。
+fn tsconfig_files(path: &Path) -> Result<Vec<PathBuf>, CliError> {
+    let config = TsConfig::load(path).map_err(|message| CliError::Config {
+        path: path.to_path_buf(),
+        message,
+    })?;
+
+    let mut files = Vec::new();
+    for file in config.files.iter().flatten() {
+        ensure_input(file)?;
+        files.push(file.clone());
+    }
+
+    let include = match (config.include, &config.files) {
+        (Some(include), _) => include,
+        (None, Some(_)) => Vec::new(),
+        (None, None) => vec![config.dir.join("**/*")],
+    };
+    let exclude = config.exclude.unwrap_or_else(|| {
+        TSCONFIG_DEFAULT_EXCLUDE
+            .iter()
+            .map(|dir| config.dir.join(dir))
+            .chain(config.out_dir)
+            .collect()
+    });
+    let exclude = exclude
+        .iter()
+        .map(|p| {
+            let pattern = tsconfig_glob(p);
+            Pattern::new(&pattern).map_err(|e| CliError::Config {
+                path: path.to_path_buf(),
+                message: format!("无效的 exclude 模式 {}: {}", pattern, e),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for pattern in include.iter().map(|p| tsconfig_glob(p)) {
+        let paths = glob::glob(&pattern).map_err(|e| CliError::Config {
+            path: path.to_path_buf(),
+            message: format!("无效的 include 模式 {}: {}", pattern, e),
+        })?;
+        files.extend(
+            paths
+                .filter_map(|p| p.ok())
+                .filter(|p| is_source_file(p) && !exclude.iter().any(|e| e.matches_path(p))),
+        );
+    }
+
+    let mut seen = HashSet::new();
+    files.retain(|f| seen.insert(f.clone()));
+    Ok(files)
+}
+
+/// tsconfig 中的路径模式转换为 glob：最后一段既没有通配符也没有扩展名时视为目录，匹配其下全部文件
+fn tsconfig_glob(pattern: &Path) -> String {
+    let last = pattern.file_name().unwrap_or_default().to_string_lossy();
+    if has_glob_chars(&last) || Path::new(&*last).extension().is_some() {
+        pattern.to_string_lossy().into_owned()
+    } else {
+        pattern.join("**/*").to_string_lossy().into_owned()
+    }
+}
+
+/// `--changed` / `--since`：从 git 取得相对于提交 `rev` 有改动的文件（规范化后的绝对路径）
+///
+/// 包括 `rev` 之后的提交、暂存区与工作区中修改、新增或重命名的文件，以及未跟踪（且没有被忽略）的文件；
+/// 删除的文件不在其中。
+fn git_changed_files(rev: &str) -> Result<HashSet<PathBuf>, CliError> {
+    let root = PathBuf::from(git(&["rev-parse", "--show-toplevel"])?.trim_end());
+    // -z：路径不加引号、不转义非 ASCII 字符；两者都输出相对于仓库根目录的路径
+    let diff = git(&["diff", "--name-only", "-z", "--diff-filter=ACMR", rev, "--"])?;
+    let untracked = git(&[
+        "ls-files",
+        "-z",
+        "--others",
+        "--exclude-standard",
+        "--full-name",
+    ])?;
+    Ok(diff
+        .split('\0')
+        .chain(untracked.split('\0'))
+        .filter(|p| !p.is_empty())
+        .filter_map(|p| fs::canonicalize(root.join(p)).ok())
+        .collect())
+}
+
+/// 运行 git 并返回标准输出
+fn git(args: &[&str]) -> Result<String, CliError> {
+    let error = |message: String| CliError::Git {
+        args: args.join(" "),
+        message,
+    };
+    let output = process::Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|e| error(e.to_string()))?;
+    if !output.status.success() {
+        return Err(error(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|e| error(e.to_string()))
+}
+
+/// 判断是否为需要处理的源文件：扩展名受支持，且不是本工具生成的 `_r` / `_o` / `_t` 文件
+fn is_source_file(path: &Path) -> bool {
+    if input_ext(path).is_none() {
+        return false;
+    }
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    !(stem.ends_with("_r")
+        || stem.ends_with("_o")
+        || stem.ends_with("_t")
+        || stem.ends_with("_strings"))
+}
+
+/// 按 `--format` 写出映射表，每种格式一个文件：`<base>.json`、`<base>.po` ...
+///
+/// `source` 为条目所在的源文件，写入 PO 等格式的位置注释；按 `--compress` 压缩。
+/// 按 `--split-map` / `--split-by` 分块时每块一组文件（`<base>.0001.json` ...），
+/// 另写索引 `<base>.index.json`，`$meta` 只写在索引中。
+fn write_mappings(
+    base: &Path,
+    entries: &[Entry],
+    source: Option<&str>,
+    formats: &[MapFormat],
+    format_opts: &FormatOptions,
+    cli: &CliArgs,
+) -> Result<Vec<PathBuf>, CliError> {
+    let chunk_opts = FormatOptions {
+        meta: None,
+        ..format_opts.clone()
+    };
+    let opts = if cli.split.is_some() {
+        &chunk_opts
+    } else {
+        format_opts
+    };
+
+    let chunks = map_chunks(base, entries, cli.split);
+    let mut written = Vec::with_capacity(formats.len() * chunks.len());
+    let mut records = Vec::with_capacity(chunks.len());
+    for (chunk_base, chunk) in &chunks {
+        let mut files = Vec::with_capacity(formats.len());
+        for &format in formats {
+            let path = mapping_path(chunk_base, format, cli.compress);
+            write_output(&path, format.render(chunk, source, opts)?, cli.compress)
+                .map_err(CliError::io("写入映射表文件", &path))?;
+            files.push(file_name(&path).to_string_lossy().into_owned());
+            written.push(path);
+        }
+        records.push(chunk_record(files, chunk));
+    }
+
+    if cli.split.is_some() && !formats.is_empty() {
+        let mut index = Map::new();
+        index.insert("chunks".to_string(), Value::Array(records));
+        if let Some(meta) = &format_opts.meta {
+            index.insert(META_KEY.to_string(), meta.to_value());
+        }
+        let path = index_path(base);
+        write_json(&path, &Value::Object(index))?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// 映射表的各块：（去掉扩展名的路径，条目）；不分块时只有 `<base>` 一块
+fn map_chunks<'a>(
+    base: &Path,
+    entries: &'a [Entry],
+    split: Option<SplitMode>,
+) -> Vec<(PathBuf, &'a [Entry])> {
+    let Some(mode) = split else {
+        return vec![(base.to_path_buf(), entries)];
+    };
+    let name = file_name(base).to_string_lossy();
+    split_entries(entries, mode)
+        .into_iter()
+        .enumerate()
+        .map(|(i, chunk)| (base.with_file_name(chunk_name(&name, i + 1)), chunk))
+        .collect()
+}
+
+/// 将要写出的映射表文件（含分块时的索引），与 [`write_mappings`] 写出的一致
+fn mapping_outputs(
+    base: &Path,
+    entries: &[Entry],
+    formats: &[MapFormat],
+    cli: &CliArgs,
+) -> Vec<PathBuf> {
+    let mut outputs = Vec::new();
+    for (chunk_base, _) in map_chunks(base, entries, cli.split) {
+        outputs.extend(
+            formats
+                .iter()
+                .map(|&f| mapping_path(&chunk_base, f, cli.compress)),
+        );
+    }
+    if cli.split.is_some() && !formats.is_empty() {
+        outputs.push(index_path(base));
+    }
+    outputs
+}
+
+/// 分块映射表的索引：`<base>.index.json`
+fn index_path(base: &Path) -> PathBuf {
+    let mut path = base.as_os_str().to_owned();
+    path.push(".index.json");
+    PathBuf::from(path)
+}
+
+/// sourcemap 文件路径：`<替换后文件名>.map`（替换后的文件压缩过时不含压缩扩展名）
+fn source_map_path(code: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.map", uncompressed_path(code).display()))
+}
+
+/// 映射表文件路径：`<base>.<格式扩展名>`，压缩时再追加 `.gz` / `.zst`
+fn mapping_path(base: &Path, format: MapFormat, compress: Option<Compression>) -> PathBuf {
+    // 不用 with_extension：文件名中本身可能带有 `.`
+    let mut path = base.as_os_str().to_owned();
+    path.push(".");
+    path.push(format.extension());
+    compressed_path(PathBuf::from(path), compress)
+}
+
+/// 路径统一使用 `/` 分隔，写入映射表时在不同平台上保持一致
+fn slash_path(path: &Path) -> String {
+    path.display().to_string().replace('\\', "/")
+}
+
+/// 查表模块的路径：`<name>_strings.ts`
+fn loader_path(base: &Path) -> PathBuf {
+    let stem = base
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    let parent = base.parent().unwrap_or_else(|| Path::new("."));
+    parent.join(format!("{}_strings.ts", stem))
+}
+
+/// 替换后的文件导入查表模块时使用的相对路径（不含扩展名），如 `./battle_strings`、`../strings_strings`
+fn import_path(file: &Path, loader: &Path) -> String {
+    let absolute = |p: &Path| std::path::absolute(p).unwrap_or_else(|_| p.to_path_buf());
+    let from = absolute(parent_dir(file));
+    let to = absolute(&loader.with_extension(""));
+
+    let common = from
+        .components()
+        .zip(to.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut parts = vec!["..".to_string(); from.components().count() - common];
+    parts.extend(
+        to.components()
+            .skip(common)
+            .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+    );
+    let path = parts.join("/");
+    if path.starts_with("..") {
+        path
+    } else {
+        format!("./{}", path)
+    }
+}
+
+/// 写出查表模块
+fn write_loader(path: &Path, entries: &[Entry], wrap_call: Option<&str>) -> Result<(), CliError> {
+    write_atomic(path, render_loader(entries, wrap_call))
+        .map_err(CliError::io("写入查表模块", path))
+}
+
+/// `--input-source-map`：读取输入文件旁边的 `<file>.map`（不存在时返回 `None`）
+fn read_input_source_map(path: &Path) -> Result<Option<String>, CliError> {
+    let map_path = PathBuf::from(format!("{}.map", path.display()));
+    if !map_path.is_file() {
+        return Ok(None);
+    }
+    fs::read_to_string(&map_path)
+        .map(Some)
+        .map_err(CliError::io("读取 sourcemap ", &map_path))
+}
+
+/// 单个输入文件的输出路径
+struct OutputPaths {
+    /// 替换后的代码（`--in-place` 时为输入文件本身）
+    code: PathBuf,
+    /// 映射表路径去掉扩展名的部分，扩展名按输出格式补上
+    map_base: PathBuf,
+    /// 替换后的文件要导入的查表模块（`--emit-loader`）
+    loader: Option<PathBuf>,
+}
+
+/// 计算输入文件在 `out_dir` 中的输出路径：默认为 原名_r.ts 与 原名_s.json（及其他格式）
+fn output_paths(path: &Path, out_dir: &Path, cli: &CliArgs) -> Result<OutputPaths, CliError> {
+    let ext = ensure_input(path)?;
+    let stem = file_stem(path)?;
+
+    Ok(OutputPaths {
+        code: if cli.in_place {
+            path.to_path_buf()
+        } else {
+            let code = out_dir.join(cli.names.code(stem, ext));
+            compressed_path(code, cli.compress.filter(|_| cli.compress_code))
+        },
+        map_base: out_dir.join(cli.names.map_base(stem, ext)),
+        loader: match (&cli.combined_map, cli.emit_loader) {
+            (_, false) => None,
+            (Some(map_path), true) => Some(loader_path(map_path)),
+            (None, true) => Some(loader_path(&out_dir.join(file_name(path)))),
+        },
+    })
+}
+
+/// 检查输出是否会覆盖已有的替换后代码或映射表（`--force` 时跳过）
+///
+/// 在写出任何文件之前检查全部输入，避免处理到一半才发现冲突；
+/// sourcemap 与查表模块完全由工具生成，增量缓存中记录的输出是上次运行写出的，都不在检查之列。
+fn check_overwrite(
+    files: &[PathBuf],
+    mirror: &[PathBuf],
+    cli: &CliArgs,
+    cache: Option<&Cache>,
+) -> Result<(), CliError> {
+    let mut targets = Vec::new();
+    // 分块时块数要提取之后才知道，只检查索引
+    let map_targets = |base: &Path| match cli.split {
+        Some(_) => vec![index_path(base)],
+        None => cli
+            .formats
+            .iter()
+            .map(|&f| mapping_path(base, f, cli.compress))
+            .collect(),
+    };
+    if let Some(map_path) = &cli.combined_map {
+        targets.extend(map_targets(&map_path.with_extension("")));
+    }
+    for file in files {
+        let paths = output_paths(file, &output_dir(file, mirror, cli.out_dir.as_deref()), cli)?;
+        // --in-place 本来就是要覆盖原文件
+        if !cli.in_place {
+            targets.push(paths.code);
+        }
+        if cli.combined_map.is_none() {
+            targets.extend(map_targets(&paths.map_base));
+        }
+    }
+
+    let owned: HashSet<&Path> = cache.map(|c| c.outputs().collect()).unwrap_or_default();
+    let existing: Vec<String> = targets
+        .iter()
+        .filter(|p| p.exists() && !owned.contains(p.as_path()))
+        .map(|p| p.display().to_string())
+        .collect();
+    if existing.is_empty() {
+        return Ok(());
+    }
+    Err(CliError::OutputExists(existing))
+}
+
+/// 处理单个输入文件：替换字符串并在 `out_dir` 中写出 `<name>_r.<ext>` 与 `<name>_s.json`
+///
+/// `base` 为本文件使用的提取选项（合并映射表时起始索引逐文件接续），其余设置取自 `cli`。
+/// 指定了 `--combined-map` 时不写单独的映射表与查表模块（由调用方汇总）。
+/// 不打印结果，由调用方按输入顺序调用 [`Extracted::report`]（多线程处理时输出顺序才能确定）。
+fn extract_file(
+    path: &Path,
+    out_dir: &Path,
+    base: &ExtractOptions,
+    cli: &CliArgs,
+) -> Result<Extracted, CliError> {
+    let OutputPaths {
+        code: out_ts_path,
+        map_base,
+        loader,
+    } = output_paths(path, out_dir, cli)?;
+
+    // 读取文件内容（按 --encoding 解码，写出时编码回原来的编码）
+    let (src, encoding) = read_source(path, cli.encoding)?;
+    let source_hash = content_hash(src.as_bytes());
+
+    // --- 解析、替换与代码生成 ---
+    // 声明文件中只有类型层面的字符串，替换总是错的：连同类型位置一起提取，只列出、不写出任何文件
+    let declaration = is_declaration_file(path);
+    let opts = ExtractOptions {
+        file_name: Some(path.to_path_buf()),
+        include_types: base.include_types || declaration,
+        loader_module: loader.as_deref().map(|l| import_path(&out_ts_path, l)),
+        input_source_map: if cli.input_source_map {
+            read_input_source_map(path)?
+        } else {
+            None
+        },
+        ..base.clone()
+    };
+    let result = match extract_strings(&src, opts) {
+        Ok(result) => result,
+        // --tolerant：无法恢复的语法错误只跳过这个文件（没有输出），其余文件照常处理
+        Err(error @ Error::Parse { .. }) if base.tolerant => {
+            let error = CliError::from_lib(path)(error);
+            Diagnostic {
+                severity: "warning",
+                code: error.kind(),
+                file: error.file(),
+                position: error.position(),
+                message: &format!("警告：已跳过 {}", error),
+            }
+            .report();
+            return Ok(Extracted::skipped(source_hash));
+        }
+        Err(error) => return Err(CliError::from_lib(path)(error)),
+    };
+    report_recovered(Some(path), &result.warnings);
+    report_folded(Some(path), &result.folded);
+    if cli.fragment_warnings {
+        report_fragments(Some(path), &result.fragments);
+    }
+    if cli.validate_icu {
+        report_icu(Some(path), &result.entries);
+    }
+    if declaration {
+        println!(
+            "{}: 声明文件，只列出 {} 个字符串，不替换",
+            path.display(),
+            result.entries.len()
+        );
+        for entry in &result.entries {
+            println!(
+                "  {}: {}",
+                entry.first_line,
+                Value::String(entry.original.clone())
+            );
+        }
+        return Ok(Extracted::skipped(source_hash));
+    }
+    if !cli.allow_reprocess {
+        check_reprocess(path, &src, &result.entries)?;
+    }
+
+    let combined = cli.combined_map.is_some();
+    let formats: &[MapFormat] = if combined { &[] } else { &cli.formats };
+    let backup = cli
+        .backup
+        .as_deref()
+        .filter(|_| cli.in_place)
+        .map(|suffix| PathBuf::from(format!("{}{}", path.display(), suffix)));
+
+    // --dry-run：只列出将要写出的文件（--preview 时再给出代码的差分）
+    if cli.dry_run {
+        let preview = if cli.preview {
+            render_preview(path, &out_ts_path, &src, &result.code)
+        } else {
+            String::new()
+        };
+        let mut outputs = vec![out_ts_path.clone()];
+        outputs.extend(
+            result
+                .source_map
+                .as_ref()
+                .map(|_| source_map_path(&out_ts_path)),
+        );
+        outputs.extend(backup);
+        outputs.extend(mapping_outputs(&map_base, &result.entries, formats, cli));
+        outputs.extend(loader.filter(|_| !combined));
+        return Ok(Extracted {
+            entries: result.entries,
+            outputs,
+            source_hash,
+            code_hash: None,
+            preview,
+        });
+    }
+
+    // --- 写入输出文件 ---
+    fs::create_dir_all(out_dir).map_err(CliError::io("创建输出目录", out_dir))?;
+
+    // 写 `$meta` 时在末尾加一行链接注释，记录映射表 key 集合的哈希（合并映射表时各文件不单独链接，
+    // JSON 数据文件中不能写注释）；追加的几行使用与代码相同的换行符
+    let mut code = result.code;
+    let newline = Newline::detect(&code).as_str();
+    if cli.format_opts.meta.is_some() && !combined && !is_data_file(path) {
+        if !code.ends_with('\n') {
+            code.push_str(newline);
+        }
+        let keys = result.entries.iter().map(|e| e.key.as_str());
+        let link = link_comment(&keys_hash(keys));
+        code.push_str(&link.replace('\n', newline));
+    }
+
+    // 有 sourcemap 时写 `<name>_r.ts.map`，并在代码末尾注明
+    let mut map_path = None;
+    if let Some(source_map) = &result.source_map {
+        let path = source_map_path(&out_ts_path);
+        write_atomic(&path, source_map).map_err(CliError::io("写入 sourcemap ", &path))?;
+        if !code.ends_with('\n') {
+            code.push_str(newline);
+        }
+        code.push_str(&format!(
+            "//# sourceMappingURL={}{}",
+            file_name(&path).to_string_lossy(),
+            newline
+        ));
+        map_path = Some(path);
+    }
+
+    // --in-place 覆盖原文件前先按 --backup 备份（权限也与原文件相同）
+    if let Some(backup) = &backup {
+        write_atomic(backup, encode_source(backup, &src, encoding)?)
+            .map_err(CliError::io("备份原文件", backup))?;
+        copy_permissions(path, backup)?;
+    }
+
+    // 写 ts 文件（--compress-code 时压缩）
+    let compress = cli.compress.filter(|_| cli.compress_code);
+    let bytes = encode_source(&out_ts_path, &code, encoding)?;
+    write_output(&out_ts_path, bytes, compress)
+        .map_err(CliError::io("写入输出 TS 文件", &out_ts_path))?;
+    if compress.is_none() {
+        keep_executable(path, &out_ts_path, &code)?;
+    }
+    let code_hash = (
+        slash_path(&out_ts_path),
+        content_hash(split_link(&code).0.as_bytes()),
+    );
+
+    // 生成映射表：{"0": "原始0", "1": "原始1", ...}
+    let source = slash_path(path);
+    let format_opts = format_options(
+        cli,
+        [(source.clone(), source_hash.clone())],
+        [code_hash.clone()],
+    );
+    let written = write_mappings(
+        &map_base,
+        &result.entries,
+        Some(&source),
+        formats,
+        &format_opts,
+        cli,
+    )?;
+
+    let mut outputs = vec![out_ts_path];
+    outputs.extend(map_path);
+    outputs.extend(backup);
+    outputs.extend(written);
+    if let Some(loader) = loader.filter(|_| !combined) {
+        write_loader(&loader, &result.entries, base.wrap_call.as_deref())?;
+        outputs.push(loader);
+    }
+    Ok(Extracted {
+        entries: result.entries,
+        outputs,
+        source_hash,
+        code_hash: Some(code_hash),
+        preview: String::new(),
+    })
+}
+
+/// 字符串至少有这么多个时才按纯数字的比例判断是否已经处理过
+const REPROCESS_MIN_STRINGS: usize = 5;
+
+/// 检查输入是否像已经处理过的文件（`--allow-reprocess` 时跳过）
+///
+/// 末尾带有链接注释（`--with-meta` 时写入），或者提取出的字符串九成以上是纯数字（"0"、"1"、...）时
+/// 视为已经替换过：再提取一次，原来的映射表就对不上了。
+fn check_reprocess(path: &Path, src: &str, entries: &[Entry]) -> Result<(), CliError> {
+    let processed = |reason: String| CliError::AlreadyProcessed {
+        path: path.to_path_buf(),
+        reason,
+    };
+    if split_link(src).1.is_some() {
+        return Err(processed(format!(
+            "带有 sb_dice 的链接注释（{}...）",
+            LINK_PREFIX.trim_end()
+        )));
+    }
+    let numeric = entries
+        .iter()
+        .filter(|e| !e.original.is_empty() && e.original.bytes().all(|b| b.is_ascii_digit()))
+        .count();
+    if entries.len() >= REPROCESS_MIN_STRINGS && numeric * 10 >= entries.len() * 9 {
+        return Err(processed(format!(
+            "{} 个字符串中有 {} 个是纯数字",
+            entries.len(),
+            numeric
+        )));
+    }
+    Ok(())
+}
+
+/// 处理单个文件的结果（见 [`extract_file`]）
+struct Extracted {
+    /// 本文件提取出的条目
+    entries: Vec<Entry>,
+    /// 写出的文件（`--dry-run` 时为将要写出的文件）
+    outputs: Vec<PathBuf>,
+    /// 源文件内容的哈希（写入 `$meta`）
+    source_hash: String,
+    /// 替换后的文件与其内容的哈希（写入 `$meta`，试运行时为 `None`）
+    code_hash: Option<(String, String)>,
+    /// `--preview` 时源文件与替换后代码的差分（见 [`render_preview`]），否则为空
+    preview: String,
+}
+
+impl Extracted {
+    /// 跳过的文件（`--tolerant` 时无法解析的文件与只列出字符串的声明文件）：没有条目，也没有输出
+    fn skipped(source_hash: String) -> Self {
+        Extracted {
+            entries: Vec::new(),
+            outputs: Vec::new(),
+            source_hash,
+            code_hash: None,
+            preview: String::new(),
+        }
+    }
+
+    /// 打印写出了哪些文件（`--dry-run` 时为将生成哪些文件）；跳过的文件不打印
+    fn report(&self, dry_run: bool) {
+        if self.outputs.is_empty() {
+            return;
+        }
+        let names: Vec<String> = self
+            .outputs
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect();
+        if dry_run {
+            println!(
+                "将生成 {}（{} 个字符串）",
+                names.join(" 与 "),
+                self.entries.len()
+            );
+        } else {
+            println!("成功：生成 {}", names.join(" 与 "));
+        }
+    }
+}
+
+/// 用 `jobs` 个线程并行处理 `items`，并按 `items` 的顺序把结果交给 `done`
+///
+/// 每个线程各自读取、解析与生成代码（各自的 SourceMap），互不共享状态；先完成的结果暂存，
+/// 等前面的都交出后再交出，因此输出与单线程时一致。出错时不再开始新的任务，返回顺序上第一个错误。
+/// `jobs` 为 1 时直接在当前线程依次处理。
+fn for_each_parallel<I: Sync, T: Send>(
+    items: &[I],
+    jobs: usize,
+    work: impl Fn(&I) -> Result<T, CliError> + Sync,
+    mut done: impl FnMut(&I, T),
+) -> Result<(), CliError> {
+    if jobs <= 1 || items.len() <= 1 {
+        for item in items {
+            done(item, work(item)?);
+        }
+        return Ok(());
+    }
+
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let (tx, rx) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..jobs.min(items.len()) {
+            let tx = tx.clone();
+            let (next, failed, work) = (&next, &failed, &work);
+            scope.spawn(move || {
+                while !failed.load(Ordering::Relaxed) {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(i) else { break };
+                    let result = work(item);
+                    if result.is_err() {
+                        failed.store(true, Ordering::Relaxed);
+                    }
+                    if tx.send((i, result)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        // 按顺序交出结果；出错后丢弃其余结果，等各线程处理完手上的文件后退出
+        let mut pending = BTreeMap::new();
+        let mut expected = 0;
+        for (i, result) in rx {
+            pending.insert(i, result);
+            while let Some(result) = pending.remove(&expected) {
+                done(&items[expected], result?);
+                expected += 1;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// `--preview` 中每处改动前后显示的行数
+const PREVIEW_CONTEXT: usize = 3;
+
+/// `--preview`：源文件与替换后代码的逐行差分（unified diff 格式），没有改动时为空
+///
+/// 不含写出时才追加的链接注释与 sourceMappingURL。输出到终端时按 git 的习惯着色
+/// （删除为红色、新增为绿色、块头为青色），设置了 `NO_COLOR` 时不着色。
+fn render_preview(path: &Path, out_path: &Path, old: &str, new: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let hunks = group_hunks(&diff_sequences(&old_lines, &new_lines), PREVIEW_CONTEXT);
+    if hunks.is_empty() {
+        return String::new();
+    }
+    let color = io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none();
+    let paint = |style: &str, line: String| {
+        if color {
+            format!("\x1b[{}m{}\x1b[0m\n", style, line)
+        } else {
+            format!("{}\n", line)
+        }
+    };
+    // 块头中的行号从 1 开始；一侧没有行时按惯例写前一行的行号
+    let range = |start: usize, len: usize| match len {
+        0 => format!("{},0", start),
+        1 => (start + 1).to_string(),
+        _ => format!("{},{}", start + 1, len),
+    };
+
+    let mut out = paint("1", format!("--- {}", slash_path(path)));
+    out.push_str(&paint("1", format!("+++ {}", slash_path(out_path))));
+    for hunk in hunks {
+        let header = format!(
+            "@@ -{} +{} @@",
+            range(hunk.old_start, hunk.old_len),
+            range(hunk.new_start, hunk.new_len)
+        );
+        out.push_str(&paint("36", header));
+        for edit in hunk.edits {
+            match edit {
+                SeqEdit::Same(i, _) => out.push_str(&format!(" {}\n", old_lines[i])),
+                SeqEdit::Removed(i) => out.push_str(&paint("31", format!("-{}", old_lines[i]))),
+                SeqEdit::Added(j) => out.push_str(&paint("32", format!("+{}", new_lines[j]))),
+            }
+        }
+    }
+    out
+}
+
+/// `--preview` 显示差分后询问是否写出：标准输入不是终端时不询问，直接写出
+fn confirm_write() -> Result<bool, CliError> {
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        return Ok(true);
+    }
+    print!("写出以上改动？[y/N] ");
+    let mut input = String::new();
+    io::stdout()
+        .flush()
+        .and_then(|_| stdin.lock().read_line(&mut input))
+        .map_err(|source| CliError::Stdio {
+            action: "读取终端输入",
+            source,
+        })?;
+    Ok(matches!(input.trim(), "y" | "Y" | "yes"))
+}
+
+/// 输出 `--preview` 的差分：标准输出是终端时交给分页器（`$PAGER`，默认为 `less`，
+/// 不足一屏时直接显示），否则直接打印；分页器无法启动时也直接打印
+fn page(preview: &str) -> Result<(), CliError> {
+    if preview.is_empty() {
+        println!("预览：源文件没有改动");
+        return Ok(());
+    }
+    let error = |source| CliError::Stdio {
+        action: "输出预览",
+        source,
+    };
+    if io::stdout().is_terminal() {
+        let pager = env::var("PAGER")
+            .ok()
+            .filter(|p| !p.trim().is_empty())
+            .unwrap_or_else(|| "less".to_string());
+        let mut words = pager.split_whitespace();
+        let program = words.next().unwrap_or("less");
+        let child = process::Command::new(program)
+            .args(words)
+            .env(
+                "LESS",
+                env::var("LESS").unwrap_or_else(|_| "FRX".to_string()),
+            )
+            .stdin(process::Stdio::piped())
+            .spawn();
+        if let Ok(mut child) = child {
+            // 提前退出分页器时写入会失败，不算错误
+            if let Some(mut stdin) = child.stdin.take() {
+                let _ = stdin.write_all(preview.as_bytes());
+            }
+            child.wait().map_err(error)?;
+            return Ok(());
+        }
+    }
+    io::stdout().write_all(preview.as_bytes()).map_err(error)
+}
+
+/// `--dry-run` 结束时列出的字符串个数
+const DRY_RUN_SAMPLE: usize = 10;
+
+/// `--dry-run` 的汇总：替换的字符串数、将写出的文件数与前几个字符串
+fn print_dry_run_summary(files: usize, total: usize, entries: &[Entry], written: usize) {
+    println!(
+        "试运行：共 {} 个文件，将替换 {} 个字符串，写出 {} 个文件（未写入任何文件）",
+        files, total, written
+    );
+    if entries.is_empty() {
+        return;
+    }
+    println!("前 {} 个字符串：", entries.len().min(DRY_RUN_SAMPLE));
+    for entry in entries.iter().take(DRY_RUN_SAMPLE) {
+        let file = entry
+            .file
+            .as_deref()
+            .map(|f| format!("{} ", f))
+            .unwrap_or_default();
+        println!(
+            "  {}[{}] {}",
+            file,
+            entry.key,
+            Value::String(entry.original.clone())
+        );
+    }
+}
+
+/// 管道模式：`sb_dice - < in.ts > out.ts`，或 `sb_dice --stdout <file.ts>`
+///
+/// 替换后的代码写到标准输出，映射表（`--format` 指定的一种格式）写到 `--map-to` 指定的文件
+/// （可以是 `/dev/fd/3` 这样的文件描述符），未指定时写到标准错误。
+/// 不打印其他提示，避免混入输出。
+fn run_stdio(input: &str, cli: &CliArgs) -> Result<(), CliError> {
+    if cli.watch
+        || cli.in_place
+        || cli.dry_run
+        || cli.emit_loader
+        || cli.opts.source_map
+        || cli.combined_map.is_some()
+        || cli.tsconfig.is_some()
+        || cli.since.is_some()
+        || cli.compress.is_some()
+        || cli.split.is_some()
+    {
+        arg_error(
+            "管道模式不支持 --watch、--in-place、--dry-run/--preview、--emit-loader、--source-map、--combined-map、--tsconfig、--changed/--since、--compress 与 --split-map/--split-by",
+        );
+    }
+    let [format] = cli.formats[..] else {
+        arg_error("管道模式只能输出一种映射表格式");
+    };
+
+    let (src, encoding, file_name) = if input == "-" {
+        let mut data = Vec::new();
+        let (src, encoding) = io::stdin()
+            .read_to_end(&mut data)
+            .and_then(|_| {
+                cli.encoding
+                    .decode(&data)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            })
+            .map_err(|source| CliError::Stdio {
+                action: "读取标准输入",
+                source,
+            })?;
+        (src, encoding, None)
+    } else {
+        let path = Path::new(input);
+        ensure_input(path)?;
+        let (src, encoding) = read_source(path, cli.encoding)?;
+        (src, encoding, Some(path.to_path_buf()))
+    };
+
+    let source = file_name.as_deref().map(slash_path);
+    let opts = ExtractOptions {
+        file_name: file_name.clone(),
+        ..cli.opts.clone()
+    };
+    let result = match &file_name {
+        Some(path) => extract_strings(&src, opts).map_err(CliError::from_lib(path))?,
+        None => extract_strings(&src, opts)?,
+    };
+    report_recovered(file_name.as_deref(), &result.warnings);
+    report_folded(file_name.as_deref(), &result.folded);
+    if cli.fragment_warnings {
+        report_fragments(file_name.as_deref(), &result.fragments);
+    }
+    if cli.validate_icu {
+        report_icu(file_name.as_deref(), &result.entries);
+    }
+    if !cli.allow_reprocess {
+        let path = file_name.as_deref().unwrap_or(Path::new("-"));
+        check_reprocess(path, &src, &result.entries)?;
+    }
+    let hash = content_hash(src.as_bytes());
+    let format_opts = format_options(cli, source.clone().map(|file| (file, hash)), []);
+    let map = format.render(&result.entries, source.as_deref(), &format_opts)?;
+
+    let code = encode_text(&result.code, encoding)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        .map_err(|source| CliError::Stdio {
+            action: "写入标准输出",
+            source,
+        })?;
+    let mut stdout = io::stdout().lock();
+    stdout
+        .write_all(&code)
+        .and_then(|_| stdout.flush())
+        .map_err(|source| CliError::Stdio {
+            action: "写入标准输出",
+            source,
+        })?;
+
+    // 文件描述符不能重命名覆盖，这里直接写入
+    match &cli.map_to {
+        Some(path) => fs::write(path, map).map_err(CliError::io("写入映射表文件", path)),
+        None => io::stderr()
+            .write_all(&map)
+            .map_err(|source| CliError::Stdio {
+                action: "写入映射表",
+                source,
+            }),
+    }
+}
+
+/// 增量缓存的默认文件（在当前目录下）
+const CACHE_FILE: &str = ".sb_dice_cache.json";
+
+/// 增量缓存文件的格式版本，不一致时整个缓存作废
+const CACHE_VERSION: u64 = 1;
+
+/// 增量缓存：记录每个输入的哈希与写出的文件，再次运行时跳过没有变化的输入
+///
+/// 哈希由输入内容（及 `--input-source-map` 读取的 sourcemap）与影响输出的设置一起算出，
+/// 设置变化时所有输入都会重新处理；上次写出的文件缺失时也重新处理。
+/// 缓存中记录的输出由本工具生成，覆盖它们不需要 `--force`。
+struct Cache {
+    /// 缓存文件路径
+    path: PathBuf,
+    /// 影响输出的设置的哈希，作为内容哈希的种子
+    settings: u64,
+    /// `--force-rebuild`：不跳过任何输入，只重新记录
+    rebuild: bool,
+    /// 输入路径（相对于当前目录，`/` 分隔）→ 记录
+    files: BTreeMap<String, CacheEntry>,
+}
+
+/// 一个输入的缓存记录
+struct CacheEntry {
+    /// 上次处理时的哈希
+    hash: u64,
+    /// 上次写出的文件
+    outputs: Vec<PathBuf>,
+}
+
+impl Cache {
+    /// 读取缓存文件（不存在时为空缓存）
+    ///
+    /// `--no-cache`、`--in-place`（输出就是输入）与 `--combined-map`（需要全部条目）时不使用缓存；
+    /// 试运行时照常跳过没有变化的文件，但不写回缓存。
+    fn load(cli: &CliArgs) -> Option<Cache> {
+        let path = cli.cache_file.clone()?;
+        // --preview 要显示每个文件的改动，预览时不跳过没有变化的文件
+        if cli.in_place || cli.combined_map.is_some() || (cli.preview && cli.dry_run) {
+            return None;
+        }
+        let files = match fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text).unwrap_or_else(|| {
+                warn("cache", Some(&path), "缓存文件无法识别，重新处理全部输入");
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+        Some(Cache {
+            path,
+            settings: settings_hash(cli),
+            rebuild: cli.force_rebuild,
+            files,
+        })
+    }
+
+    /// 解析缓存文件：`{"version": 1, "files": {"src/a.ts": {"hash": "...", "outputs": [...]}}}`
+    fn parse(text: &str) -> Option<BTreeMap<String, CacheEntry>> {
+        let value: Value = serde_json::from_str(text).ok()?;
+        if value.get("version")?.as_u64()? != CACHE_VERSION {
+            return None;
+        }
+        value
+            .get("files")?
+            .as_object()?
+            .iter()
+            .map(|(file, entry)| {
+                let hash = u64::from_str_radix(entry.get("hash")?.as_str()?, 16).ok()?;
+                let outputs = entry
+                    .get("outputs")?
+                    .as_array()?
+                    .iter()
+                    .map(|o| o.as_str().map(PathBuf::from))
+                    .collect::<Option<_>>()?;
+                Some((file.clone(), CacheEntry { hash, outputs }))
+            })
+            .collect()
+    }
+
+    /// 计算输入当前的哈希
+    fn hash(&self, file: &Path, cli: &CliArgs) -> Result<u64, CliError> {
+        let mut data = fs::read(file).map_err(CliError::io("读取文件", file))?;
+        if cli.input_source_map {
+            data.extend(
+                read_input_source_map(file)?
+                    .unwrap_or_default()
+                    .into_bytes(),
+            );
+        }
+        Ok(xxh64(&data, self.settings))
+    }
+
+    /// 输入没有变化、上次写出的文件也都还在时不需要重新处理
+    fn is_fresh(&self, file: &Path, hash: u64) -> bool {
+        !self.rebuild
+            && self
+                .files
+                .get(&Self::key(file))
+                .is_some_and(|e| e.hash == hash && e.outputs.iter().all(|o| o.is_file()))
+    }
+
+    /// 记录处理完的输入
+    fn record(&mut self, file: &Path, hash: u64, outputs: Vec<PathBuf>) {
+        let outputs = outputs
+            .iter()
+            .map(|p| PathBuf::from(Self::key(p)))
+            .collect();
+        self.files
+            .insert(Self::key(file), CacheEntry { hash, outputs });
+    }
+
+    /// 缓存中的路径：监视模式下收到的是绝对路径，统一转换为相对于当前目录的路径
+    fn key(path: &Path) -> String {
+        let cwd = env::current_dir().unwrap_or_default();
+        slash_path(path.strip_prefix(&cwd).unwrap_or(path))
+    }
+
+    /// 缓存中记录的所有输出
+    fn outputs(&self) -> impl Iterator<Item = &Path> {
+        self.files
+            .values()
+            .flat_map(|e| e.outputs.iter().map(PathBuf::as_path))
+    }
+
+    /// 写回缓存文件，去掉已经不存在的输入
+    fn save(&mut self) -> Result<(), CliError> {
+        self.files.retain(|file, _| Path::new(file).is_file());
+        let files: Map<String, Value> = self
+            .files
+            .iter()
+            .map(|(file, entry)| {
+                let outputs: Vec<String> = entry.outputs.iter().map(|p| slash_path(p)).collect();
+                let entry = serde_json::json!({
+                    "hash": format!("{:016x}", entry.hash),
+                    "outputs": outputs,
+                });
+                (file.clone(), entry)
+            })
+            .collect();
+        let value = serde_json::json!({ "version": CACHE_VERSION, "files": files });
+        write_atomic(&self.path, value.to_string())
+            .map_err(CliError::io("写入缓存文件", &self.path))
+    }
+}
+
+/// 影响输出的设置的哈希：提取选项、源文件编码、映射表格式、输出路径与文件名模板、压缩与分块方式，以及工具版本
+fn settings_hash(cli: &CliArgs) -> u64 {
+    let settings = format!(
+        "{} {:?} {:?} {:?} {} {} {:?} {} {} {:?} {} {:?} {:?}",
+        env!("CARGO_PKG_VERSION"),
+        cli.opts,
+        cli.formats,
+        cli.format_opts,
+        cli.names.code,
+        cli.names.map,
+        cli.out_dir,
+        cli.emit_loader,
+        cli.input_source_map,
+        cli.compress,
+        cli.compress_code,
+        cli.split,
+        cli.encoding,
+    );
+    xxh64(settings.as_bytes(), 0)
+}
+
+/// 依次处理多个文件（`mirror` 为各输入的镜像根目录，见 [`output_dir`]）
+///
+/// 指定了 `--combined-map` 时所有文件共用一个索引计数器，条目汇总写入同一张映射表，
+/// 每个条目记录来源文件；否则每个文件各自写 `<name>_s.json`，并按增量缓存跳过没有变化的文件。
+fn extract_files(files: &[PathBuf], mirror: &[PathBuf], cli: &CliArgs) -> Result<(), CliError> {
+    // --preview：先试运行一遍显示差分，确认后再照常写出
+    if cli.preview && !cli.dry_run {
+        let dry = CliArgs {
+            dry_run: true,
+            ..cli.clone()
+        };
+        extract_files(files, mirror, &dry)?;
+        if !confirm_write()? {
+            println!("已取消，没有写出任何文件");
+            return Ok(());
+        }
+    }
+    let mut cache = Cache::load(cli);
+    if !cli.force {
+        check_overwrite(files, mirror, cli, cache.as_ref())?;
+    }
+
+    let extract = |file: &PathBuf, opts: &ExtractOptions| {
+        let dir = output_dir(file, mirror, cli.out_dir.as_deref());
+        extract_file(file, &dir, opts, cli)
+    };
+
+    let mut preview = String::new();
+    let Some(map_path) = &cli.combined_map else {
+        let mut sample = Vec::new();
+        let mut total = 0;
+        let mut written = 0;
+        let mut unchanged = 0;
+        let mut records = Vec::new();
+        // 有缓存时先算哈希，没有变化的文件不再处理（返回 None）
+        let each = |file: &PathBuf| -> Result<_, CliError> {
+            let Some(cache) = &cache else {
+                return Ok((None, Some(extract(file, &cli.opts)?)));
+            };
+            let hash = cache.hash(file, cli)?;
+            if cache.is_fresh(file, hash) {
+                return Ok((Some(hash), None));
+            }
+            Ok((Some(hash), Some(extract(file, &cli.opts)?)))
+        };
+        let result = for_each_parallel(files, cli.jobs, each, |file, (hash, extracted)| {
+            let Some(extracted) = extracted else {
+                unchanged += 1;
+                report_skipped(file, "没有变化（增量缓存）");
+                return;
+            };
+            extracted.report(cli.dry_run);
+            preview.push_str(&extracted.preview);
+            total += extracted.entries.len();
+            written += extracted.outputs.len();
+            // 跳过的文件不记入缓存，下次重新处理
+            if !extracted.outputs.is_empty() {
+                records.extend(hash.map(|hash| (file.clone(), hash, extracted.outputs.clone())));
+            }
+            // 试运行汇总时标明来源文件
+            if cli.dry_run && sample.len() < DRY_RUN_SAMPLE {
+                sample.extend(extracted.entries.into_iter().map(|entry| Entry {
+                    file: Some(slash_path(file)),
+                    ..entry
+                }));
+            }
+        });
+
+        // 出错时也记录已经处理完的文件
+        if let Some(cache) = cache.as_mut().filter(|_| !cli.dry_run) {
+            for (file, hash, outputs) in records {
+                cache.record(&file, hash, outputs);
+            }
+            cache.save()?;
+        }
+        result?;
+        if unchanged > 0 {
+            println!(
+                "跳过 {} 个没有变化的文件（--force-rebuild 可重新处理）",
+                unchanged
+            );
+        }
+        if cli.preview && cli.dry_run {
+            page(&preview)?;
+        }
+        if cli.dry_run {
+            print_dry_run_summary(files.len(), total, &sample, written);
+        }
+        return Ok(());
+    };
+
+    let mut combined = Vec::new();
+    let mut sources = Vec::new();
+    let mut code = Vec::new();
+    let mut written = 0;
+    let loader = cli.emit_loader.then(|| loader_path(map_path));
+    let mut collect = |file: &PathBuf, extracted: Extracted| {
+        if extracted.outputs.is_empty() {
+            return;
+        }
+        extracted.report(cli.dry_run);
+        preview.push_str(&extracted.preview);
+        written += extracted.outputs.len();
+        sources.push((slash_path(file), extracted.source_hash));
+        code.extend(extracted.code_hash);
+        combined.extend(extracted.entries.into_iter().map(|entry| Entry {
+            file: Some(slash_path(file)),
+            ..entry
+        }));
+    };
+    if cli.jobs > 1 {
+        // 索引跨文件连续：先并行统计各文件的字符串个数，算出每个文件的起始索引
+        let mut starts = Vec::with_capacity(files.len());
+        let mut next = cli.opts.start_index;
+        let count = |file: &PathBuf| count_strings(file, &cli.opts, cli.encoding);
+        for_each_parallel(files, cli.jobs, count, |_, n| {
+            starts.push(next);
+            next += n;
+        })?;
+        let items: Vec<(&PathBuf, usize)> = files.iter().zip(starts).collect();
+        let each = |&(file, start): &(&PathBuf, usize)| {
+            let opts = ExtractOptions {
+                start_index: start,
+                ..cli.opts.clone()
+            };
+            extract(file, &opts)
+        };
+        for_each_parallel(&items, cli.jobs, each, |&(file, _), extracted| {
+            collect(file, extracted)
+        })?;
+    } else {
+        let mut opts = cli.opts.clone();
+        for file in files {
+            let extracted = extract(file, &opts)?;
+            opts.start_index += extracted.entries.len();
+            collect(file, extracted);
+        }
+    }
+
+    if cli.preview && cli.dry_run {
+        page(&preview)?;
+    }
+    if cli.dry_run {
+        let base = map_path.with_extension("");
+        written += mapping_outputs(&base, &combined, &cli.formats, cli).len();
+        written += usize::from(loader.is_some());
+        print_dry_run_summary(files.len(), combined.len(), &combined, written);
+        return Ok(());
+    }
+
+    // 扩展名按输出格式替换：strings.json 同时输出 strings.po 等
+    let written = write_mappings(
+        &map_path.with_extension(""),
+        &combined,
+        None,
+        &cli.formats,
+        &format_options(cli, sources, code),
+        cli,
+    )?;
+    for path in written {
+        println!("成功：生成合并映射表 {}", path.display());
+    }
+    if let Some(loader) = &loader {
+        write_loader(loader, &combined, cli.opts.wrap_call.as_deref())?;
+        println!("成功：生成查表模块 {}", loader.display());
+    }
+    Ok(())
+}
+
+/// 统计文件中会被替换的字符串个数（并行处理合并映射表时用来确定各文件的起始索引）
+fn count_strings(
+    path: &Path,
+    base: &ExtractOptions,
+    encoding: SourceEncoding,
+) -> Result<usize, CliError> {
+    // 声明文件只列出字符串，不占索引
+    if is_declaration_file(path) {
+        return Ok(0);
+    }
+    let (src, _) = read_source(path, encoding)?;
+    let opts = ExtractOptions {
+        file_name: Some(path.to_path_buf()),
+        source_map: false,
+        ..base.clone()
+    };
+    match extract_strings(&src, opts) {
+        Ok(result) => Ok(result.entries.len()),
+        // --tolerant 时无法解析的文件会被跳过，不占索引
+        Err(Error::Parse { .. }) if base.tolerant => Ok(0),
+        Err(error) => Err(CliError::from_lib(path)(error)),
+    }
+}
+
+/// 监视模式：先完整处理一遍，之后每当源文件变化就重新提取该文件
+///
+/// 短时间内的多次变化（编辑器保存时常见）会合并为一次处理；出错时只打印错误，不退出。
+/// 使用合并映射表时索引跨文件连续，任一文件变化都要重新处理全部输入。
+fn watch(
+    roots: &[PathBuf],
+    inputs: &[String],
+    mirror: &[PathBuf],
+    cli: &CliArgs,
+) -> Result<(), CliError> {
+    let excludes = &cli.exclude;
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(|source| CliError::Watch {
+        action: "启动文件监视".to_string(),
+        source,
+    })?;
+    for root in roots {
+        watcher
+            .watch(root, RecursiveMode::Recursive)
+            .map_err(|source| CliError::Watch {
+                action: format!("监视 {} ", root.display()),
+                source,
+            })?;
+        println!("正在监视 {}（Ctrl-C 退出）", root.display());
+    }
+
+    while let Ok(first) = rx.recv() {
+        // 收集这一批变化的文件
+        let mut changed = BTreeSet::new();
+        let mut pending = Some(first);
+        while let Some(res) = pending {
+            match res {
+                Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                    changed.extend(event.paths.into_iter().filter(|p| {
+                        is_source_file(p)
+                            && !is_excluded(p, excludes)
+                            && keep_walked(p, cli.include_dts)
+                    }));
+                }
+                Ok(_) => {}
+                Err(source) => CliError::Watch {
+                    action: "文件监视".to_string(),
+                    source,
+                }
+                .report(),
+            }
+            pending = rx.recv_timeout(Duration::from_millis(200)).ok();
+        }
+
+        // 使用 tsconfig 时只处理它当前选中的文件（如 include 之外、node_modules 中的变化都忽略）
+        if let Some(tsconfig) = &cli.tsconfig {
+            let absolute = |p: &Path| std::path::absolute(p).unwrap_or_else(|_| p.to_path_buf());
+            match collect_inputs(inputs, Some(tsconfig), excludes, cli.include_dts) {
+                Ok(files) => {
+                    let files: HashSet<PathBuf> = files.iter().map(|f| absolute(f)).collect();
+                    changed.retain(|p| files.contains(&absolute(p)));
+                }
+                Err(err) => err.report(),
+            }
+        }
+        if changed.is_empty() {
+            continue;
+        }
+        if cli.combined_map.is_some() {
+            let result = collect_inputs(inputs, cli.tsconfig.as_deref(), excludes, cli.include_dts)
+                .and_then(|files| extract_files(&files, mirror, cli));
+            if let Err(err) = result {
+                err.report();
+            }
+            continue;
+        }
+        // 经过增量缓存：只是保存了一下、内容没有变化的文件不会重新处理
+        let files: Vec<PathBuf> = changed.into_iter().filter(|p| p.is_file()).collect();
+        if let Err(err) = extract_files(&files, mirror, cli) {
+            err.report();
+        }
+    }
+    Ok(())
+}
+
+/// 输出文件名模板（`--out-ts` / `--out-json`）
+///
+/// 可用变量 `{stem}`（输入文件名，不含扩展名）与 `{ext}`（输入文件的扩展名，如 `ts`）。
+#[derive(Clone)]
+struct OutputNames {
+    /// 替换后代码的文件名，默认 `{stem}_r.{ext}`
+    code: String,
+    /// 映射表的文件名，默认 `{stem}_s.json`
+    map: String,
+}
+
+impl OutputNames {
+    /// 替换后代码的文件名
+    fn code(&self, stem: &str, ext: &str) -> String {
+        self.code.replace("{stem}", stem).replace("{ext}", ext)
+    }
+
+    /// 映射表文件名去掉扩展名的部分；与 `--combined-map` 相同，扩展名按输出格式补上
+    fn map_base(&self, stem: &str, ext: &str) -> String {
+        let template = match self.map.rsplit_once('.') {
+            Some((base, suffix)) if !base.is_empty() && !suffix.contains(['{', '}']) => base,
+            _ => &self.map,
+        };
+        template.replace("{stem}", stem).replace("{ext}", ext)
+    }
+}
+
+/// 检查输出文件名模板（`--out-ts` / `--out-json`）：必须含有 `{stem}`（否则各输入的输出会互相覆盖），
+/// 不能含有其他变量或路径分隔符
+fn parse_name_template(template: &str) -> Result<String, String> {
+    let rest = template.replace("{stem}", "").replace("{ext}", "");
+    if !template.contains("{stem}") {
+        return Err("模板必须包含 {stem}".to_string());
+    }
+    if rest.contains(['{', '}']) {
+        return Err("模板只能使用 {stem} 与 {ext}".to_string());
+    }
+    if rest.contains(['/', '\\']) {
+        return Err("模板只能是文件名，不能包含目录".to_string());
+    }
+    Ok(template.to_string())
+}
+
+/// 检查 `--backup` 的后缀：不能为空，也不能包含路径分隔符
+fn parse_backup(suffix: &str) -> Result<String, String> {
+    if suffix.is_empty() || suffix.contains(['/', '\\']) {
+        return Err("需要一个文件名后缀，如 .bak".to_string());
+    }
+    Ok(suffix.to_string())
+}
+
+/// 检查 `--wrap-call` 的函数名：标识符或 `a.b` 形式的方法名
+fn parse_wrap_call(name: &str) -> Result<String, String> {
+    let valid = name.split('.').all(|part| {
+        part.starts_with(|c: char| c.is_alphabetic() || c == '_' || c == '$')
+            && part
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+    });
+    if !valid {
+        return Err("需要函数名或 a.b 形式的方法名".to_string());
+    }
+    Ok(name.to_string())
+}
+
+/// 解析 `--fuzzy-threshold`：0 到 1 之间的小数
+fn parse_ratio(ratio: &str) -> Result<f64, String> {
+    match ratio.parse::<f64>() {
+        Ok(value) if (0.0..=1.0).contains(&value) => Ok(value),
+        _ => Err("需要 0 到 1 之间的小数，如 0.8".to_string()),
+    }
+}
+
+/// 解析 `--key-mode`
+fn parse_key_mode(mode: &str) -> Result<KeyMode, String> {
+    match mode {
+        "index" => Ok(KeyMode::Index),
+        "hash" => Ok(KeyMode::Hash),
+        other => Err(format!("未知的 key 模式 {}（可选 index / hash）", other)),
+    }
+}
+
+/// 解析 `--index-format`
+fn parse_index_format(format: &str) -> Result<IndexFormat, String> {
+    match format {
+        "decimal" => Ok(IndexFormat::Decimal),
+        "hex" => Ok(IndexFormat::Hex),
+        "base62" => Ok(IndexFormat::Base62),
+        other => match other.strip_prefix("pad").map(str::parse) {
+            Some(Ok(width)) => Ok(IndexFormat::Padded(width)),
+            _ => Err(format!(
+                "未知的索引格式 {}（可选 decimal / padN / hex / base62，如 pad4）",
+                other
+            )),
+        },
+    }
+}
+
+/// 解析 `--namespace`
+fn parse_namespace(namespace: &str) -> Result<KeyNamespace, String> {
+    match namespace {
+        "file" => Ok(KeyNamespace::File),
+        other => Err(format!("未知的命名空间 {}（可选 file）", other)),
+    }
+}
+
+/// 解析 `--target`
+fn parse_target(target: &str) -> Result<EsVersion, String> {
+    match target {
+        "es5" => Ok(EsVersion::Es5),
+        "es2015" | "es6" => Ok(EsVersion::Es2015),
+        "es2016" => Ok(EsVersion::Es2016),
+        "es2017" => Ok(EsVersion::Es2017),
+        "es2018" => Ok(EsVersion::Es2018),
+        "es2019" => Ok(EsVersion::Es2019),
+        "es2020" => Ok(EsVersion::Es2020),
+        "es2021" => Ok(EsVersion::Es2021),
+        "es2022" => Ok(EsVersion::Es2022),
+        "es2023" => Ok(EsVersion::Es2023),
+        "es2024" => Ok(EsVersion::Es2024),
+        "esnext" => Ok(EsVersion::EsNext),
+        other => Err(format!(
+            "未知的目标版本 {}（可选 es5 / es2015 ~ es2024 / esnext）",
+            other
+        )),
+    }
+}
+
+/// 解析 `--quote`
+fn parse_quote(style: &str) -> Result<QuoteStyle, String> {
+    match style {
+        "single" => Ok(QuoteStyle::Single),
+        "double" => Ok(QuoteStyle::Double),
+        other => Err(format!("未知的引号风格 {}（可选 single / double）", other)),
+    }
+}
+
+/// 解析 `--semicolons`：返回是否省略代码块最后一条语句的分号
+fn parse_semicolons(mode: &str) -> Result<bool, String> {
+    match mode {
+        "auto" => Ok(true),
+        "always" => Ok(false),
+        other => Err(format!("未知的分号风格 {}（可选 auto / always）", other)),
+    }
+}
+
+/// 解析 `--newline`
+fn parse_newline(style: &str) -> Result<Newline, String> {
+    match style {
+        "auto" => Ok(Newline::Auto),
+        "lf" => Ok(Newline::Lf),
+        "crlf" => Ok(Newline::Crlf),
+        other => Err(format!("未知的换行符 {}（可选 auto / lf / crlf）", other)),
+    }
+}
+
+/// 解析 `--syntax`
+fn parse_syntax(syntax: &str) -> Result<SourceSyntax, String> {
+    match syntax {
+        "ts" => Ok(SourceSyntax::Typescript),
+        "js" => Ok(SourceSyntax::Javascript),
+        other => Err(format!("未知的语法 {}（可选 ts / js）", other)),
+    }
+}
+
+/// `sb_dice -h` 末尾的说明
+const AFTER_HELP: &str = r#"不写子命令时等同于 extract：sb_dice [选项] <输入>...
+
+输出:
+  生成两个文件：
+    - <name>_r.ts  : 替换后的 TS 文件（.tsx 输入对应 <name>_r.tsx）
+    - <name>_s.json: 映射表，形如 {"0":"原始字符串0","1":"原始字符串1",...}
+
+注释指令:
+  // sb-dice-ignore-file          整个文件都不替换
+  // sb-dice-ignore-next-line     下一行中的字符串不替换
+  /* sb-dice-ignore */ "x"        紧跟在注释后面的这一个字符串不替换
+
+注意事项:
+  - 默认不会替换模板字符串中的静态部分（quasis），需要的话加 --include-template-quasis
+  - 会替换 import/require 中的字符串模块路径 (反正也不应该有，
+    不想替换就加 --skip-imports)
+  - 默认不替换类型位置中的字符串，保证 _r.ts 仍能通过类型检查
+  - 默认不替换 "use strict"、"use client" 等指令
+  - 默认不替换对象字面量的字符串 key 与 obj["key"] 中的 key
+  - 默认不替换 TS 字符串枚举成员的值
+  - 默认保留注释，需要去掉的话加 --strip-comments
+
+退出码:
+  1 参数错误、3 读写失败、4 解析失败、5 代码生成失败、
+  13 占位符不一致（check-placeholders）、14 映射表过期（check）、15 输出文件已存在、
+  16 映射表与本次操作不对应（restore / apply 按 $meta 检查）、17 输入已经处理过"#;
+
+/// 用来解决 DICE 的 sb 字符串机制的 字符串提取与替换工具
+#[derive(Parser)]
+#[command(
+    name = "sb_dice",
+    version,
+    author = "shenjack & Gemini 3 Pro & GPT 5 mini & GLM 4.7 & DeepSeek v3.2 (按照贡献多少排序(确信))",
+    help_template = "{name} {version} - {about}\n\nAuthor: {author}\n\n{usage-heading} {usage}\n\n{all-args}{after-help}",
+    after_help = AFTER_HELP,
+    args_conflicts_with_subcommands = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// 不写子命令时的提取参数
+    #[command(flatten)]
+    extract: ExtractArgs,
+    /// 错误与警告的输出形式；json 时每条一行 JSON（含 severity、code、file、line、column、message），
+    /// 并列出跳过的文件
+    #[arg(long, value_enum, default_value = "human", global = true)]
+    diagnostics: DiagnosticsFormat,
+    /// 提取与 check 时读取的配置文件，默认为当前目录下的 sb_dice.toml（存在时）
+    #[arg(long, value_name = "FILE", global = true)]
+    config: Option<PathBuf>,
+}
+
+/// `--validate` 的取值
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ValidateFormat {
+    /// ICU MessageFormat：括号配对、参数类型、plural / select 的分支
+    Icu,
+}
+
+/// `--split-by` 的取值
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SplitBy {
+    /// 每个来源文件一块
+    File,
+}
+
+/// `--diagnostics` 的取值
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum DiagnosticsFormat {
+    /// 给人看的文字
+    Human,
+    /// 每条诊断一行 JSON
+    Json,
+}
+
+/// 子命令
+#[derive(Subcommand)]
+enum Command {
+    /// 提取字符串：生成替换后的代码与映射表（不写子命令时的默认行为）
+    Extract(Box<ExtractArgs>),
+    /// 按映射表把替换后的文件还原，生成 <name>_o.ts
+    Restore(RestoreArgs),
+    /// 按原始字符串（或 key）把旧译文带到新映射表上，生成 <name>_m.json，
+    /// 并列出新增(+)/删除(-)/变化(~)/模糊匹配待确认(?)的 key
+    Merge(MergeArgs),
+    /// 把替换后文件中的 key 直接替换为译文（没有译文的退回原文），默认生成 <name>_t.ts
+    Apply(ApplyArgs),
+    /// 按与提取相同的选项在内存中重新提取，检查已有的映射表是否与源码一致，
+    /// 列出新增(+)/删除(-)/变化(~)的字符串；不一致时以退出码 14 退出
+    Check(Box<ExtractArgs>),
+    /// 逐个确认将被替换的字符串（显示前后的源码，可按正则批量决定），
+    /// 取舍记入 .sb_dice_decisions.json，然后只替换确认过的字符串；之后的提取与检查也按这些取舍
+    Review(Box<ReviewArgs>),
+    /// 检查译文中的占位符（{name}、{{name}}、%s、${...}）是否与原文一致，有问题时以退出码 13 退出
+    CheckPlaceholders(CheckArgs),
+    /// 比较两张映射表，列出新增(+)/删除(-)/原文变化(~)的字符串
+    Diff(DiffArgs),
+    /// 在内存中提取两个源文件的字符串并按顺序比较，不需要映射表
+    DiffSrc(Box<DiffSrcArgs>),
+    /// 列出原文只有空白、标点或大小写不同的记录（近似重复），方便交给译者之前合并
+    Dupes(DupesArgs),
+    /// 统计映射表原文中反复出现的中日韩词语，按出现次数导出 CSV 术语表（默认 glossary.csv）
+    Glossary(GlossaryArgs),
+    /// 源文件中的字符串顺序变化后，按原文把映射表的旧 key 改为新 key，
+    /// 生成 <name>_n.json 与 key 迁移表 <name>_keys.json
+    Renumber(Box<RenumberArgs>),
+    /// 统计每个文件与合计的字符串个数、字符数、重复数、最长的字符串与语言分布，不写出任何文件
+    Stats(Box<StatsArgs>),
+    /// 在当前目录生成带注释的配置文件 sb_dice.toml，按 tsconfig.json 或源码目录填好输入
+    Init(InitArgs),
+}
+
+/// `extract` 的参数
+///
+/// 同一个选项可以给出多次（配置文件中的选项在前），以最后一次为准。
+#[derive(Args)]
+#[command(args_override_self = true)]
+struct ExtractArgs {
+    /// 输入的文件、目录或 glob 模式（如 "src/**/*.ts"，记得加引号），可以给出多个；
+    /// 目录中递归处理所有 .ts/.tsx/.js/.mjs/.cjs/.svelte/.html 文件；- 表示从标准输入读取
+    #[arg(value_name = "输入")]
+    inputs: Vec<String>,
+    /// 处理完后继续监视输入文件/目录，源文件变化时重新提取
+    #[arg(long)]
+    watch: bool,
+    /// 同时处理的文件数（线程数），默认为 CPU 核数；1 表示依次处理
+    #[arg(short = 'j', long, value_name = "N")]
+    jobs: Option<NonZeroUsize>,
+    /// 只处理 git 中有改动的文件：相对于 HEAD 修改或新增的文件，包括未跟踪的文件
+    #[arg(long, conflicts_with = "combined_map")]
+    changed: bool,
+    /// 只处理相对于提交 REV 有改动的文件（之后的提交与未提交的改动），如 --since origin/main
+    #[arg(long, value_name = "REV", conflicts_with_all = ["changed", "combined_map"])]
+    since: Option<String>,
+    /// 忽略增量缓存，重新处理全部输入（之后照常更新缓存）
+    #[arg(long)]
+    force_rebuild: bool,
+    /// 增量缓存文件：记录输入的哈希与输出，再次运行时跳过没有变化的文件
+    #[arg(long, value_name = "FILE", default_value = CACHE_FILE)]
+    cache_file: PathBuf,
+    /// 不读取也不写入增量缓存
+    #[arg(long, conflicts_with = "force_rebuild")]
+    no_cache: bool,
+    /// 取舍记录文件：review 中决定不替换的字符串，之后的提取也不替换
+    #[arg(long, value_name = "FILE", default_value = DECISIONS_FILE)]
+    decisions_file: PathBuf,
+    /// 不读取取舍记录，替换所有符合条件的字符串
+    #[arg(long)]
+    no_decisions: bool,
+    /// 排除匹配该 glob 模式的文件，可重复使用（如 "**/node_modules/**"）
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<Pattern>,
+    /// 遍历目录与展开 glob 时包含 .d.ts 声明文件（默认跳过）；声明文件只列出字符串，不替换
+    #[arg(long)]
+    include_dts: bool,
+    /// 按 tsconfig.json 的 files / include / exclude 选择要处理的文件（与命令行上的输入合并）
+    #[arg(long, value_name = "FILE")]
+    tsconfig: Option<PathBuf>,
+    #[command(flatten)]
+    filter: FilterArgs,
+    #[command(flatten)]
+    keys: KeyArgs,
+    #[command(flatten)]
+    style: StyleArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+/// `review` 的参数：与提取相同的选项，外加是否重新确认已有记录的字符串
+#[derive(Args)]
+struct ReviewArgs {
+    /// 重新确认全部字符串（默认只询问取舍记录中还没有的字符串）
+    #[arg(long)]
+    all: bool,
+    #[command(flatten)]
+    extract: ExtractArgs,
+}
+
+/// 选择要替换哪些字符串
+#[derive(Args)]
+#[command(next_help_heading = "筛选")]
+struct FilterArgs {
+    /// 同时替换类型位置中的字符串（字符串字面量类型、declare module "x" 等），默认跳过
+    #[arg(long)]
+    include_types: bool,
+    /// 不替换 import/export-from/require/动态 import 中的模块路径
+    #[arg(long)]
+    skip_imports: bool,
+    /// 同时替换属性名中的字符串（{"hp": 10}、obj["hp"] 等），默认跳过，替换后按 key 取值会失败
+    #[arg(long)]
+    include_keys: bool,
+    /// 同时替换 TS 字符串枚举成员的值（enum Color { Red = "red" }），默认跳过
+    #[arg(long)]
+    include_enums: bool,
+    /// 同时替换装饰器中的字符串（@Component({selector: "app-x"})），默认跳过
+    #[arg(long)]
+    include_decorators: bool,
+    /// 同时替换模块与函数体开头的指令（"use strict"、"use client" 等），默认跳过
+    #[arg(long)]
+    include_directives: bool,
+    /// 同时提取模板字符串的静态部分，每段静态文本替换为各自的 key（如 `0${name}1`）
+    #[arg(long)]
+    include_template_quasis: bool,
+    /// 替换前先把常量字符串拼接（"你" + "好"）合并为一个字符串，译者看到整句；合并了的位置逐条列出
+    #[arg(long)]
+    fold_concat: bool,
+    /// 同时替换 JSX 元素之间的文本（<p>你好</p>），按 JSX 的规则折叠空白，替换为 {"0"}（或 --wrap-call 的 {S(0)}）
+    #[arg(long)]
+    jsx_text: bool,
+    /// 带标签的模板（sql`...`、gql`...`）中的内容默认跳过；列出的标签照常替换，逗号分隔，可重复使用（如 t,i18n.t）
+    #[arg(long, value_name = "NAMES")]
+    include_tags: Vec<String>,
+    /// 只替换这些函数/方法调用的参数中的字符串，逗号分隔，可重复使用（如 t,i18n.t,showText）
+    #[arg(long, value_name = "NAMES")]
+    only_calls: Vec<String>,
+    /// 不替换这些函数/方法调用中的字符串（包括参数里嵌套的调用），逗号分隔，可重复使用
+    #[arg(long, value_name = "NAMES")]
+    skip_calls: Vec<String>,
+    /// 跳过用于比较的字符串：typeof x === "string"、kind === "attack"、case "attack":、"hp" in obj
+    #[arg(long)]
+    skip_comparisons: bool,
+    /// 只替换包含中日韩文字的字符串（跳过 key、CSS 类名、事件名等）
+    #[arg(long)]
+    only_cjk: bool,
+    /// 只替换匹配该正则的字符串
+    #[arg(long = "match", value_name = "REGEX")]
+    pattern: Option<Regex>,
+    /// 只替换字符数不少于 N 的字符串
+    #[arg(long, value_name = "N")]
+    min_len: Option<usize>,
+    /// 只替换字符数不超过 N 的字符串
+    #[arg(long, value_name = "N")]
+    max_len: Option<usize>,
+    /// 指定源码语法：ts 或 js，默认按扩展名判断（.js/.mjs/.cjs 为 JavaScript）
+    #[arg(long, value_parser = parse_syntax)]
+    syntax: Option<SourceSyntax>,
+    /// 启用 JSX 语法（.tsx 文件自动启用，从标准输入读取时使用）
+    #[arg(long)]
+    jsx: bool,
+    /// 允许旧式装饰器（experimentalDecorators / Babel legacy）：JavaScript 中装饰器可以写在 export 前面
+    #[arg(long)]
+    decorators: bool,
+    /// 按声明文件的规则解析（默认只有 .d.ts 文件如此），从标准输入读取声明文件时使用
+    #[arg(long, overrides_with = "no_dts")]
+    dts: bool,
+    /// 不按声明文件的规则解析 .d.ts 文件
+    #[arg(long, overrides_with = "dts")]
+    no_dts: bool,
+    /// 容错解析：能够恢复的语法错误只给出警告（含行列号），无法解析的文件跳过，不中止整个运行
+    #[arg(long)]
+    tolerant: bool,
+    /// 遇到含有孤立代理项（如 "\uD800"）、无法无损写入映射表的字符串时报错退出
+    #[arg(long)]
+    strict: bool,
+}
+
+impl FilterArgs {
+    /// 写入提取选项
+    fn apply(self, opts: &mut ExtractOptions) {
+        opts.include_types = self.include_types;
+        opts.skip_imports = self.skip_imports;
+        opts.include_keys = self.include_keys;
+        opts.include_enums = self.include_enums;
+        opts.include_decorators = self.include_decorators;
+        opts.include_directives = self.include_directives;
+        opts.include_template_quasis = self.include_template_quasis;
+        opts.fold_concat = self.fold_concat;
+        opts.jsx_text = self.jsx_text;
+        opts.include_tags = self
+            .include_tags
+            .iter()
+            .flat_map(|s| split_list(s))
+            .collect();
+        opts.only_calls = self.only_calls.iter().flat_map(|s| split_list(s)).collect();
+        opts.skip_calls = self.skip_calls.iter().flat_map(|s| split_list(s)).collect();
+        opts.skip_comparisons = self.skip_comparisons;
+        opts.only_cjk = self.only_cjk;
+        opts.pattern = self.pattern;
+        opts.min_len = self.min_len;
+        opts.max_len = self.max_len;
+        opts.syntax = self.syntax;
+        opts.jsx = self.jsx;
+        opts.legacy_decorators = self.decorators;
+        opts.dts = match (self.dts, self.no_dts) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        };
+        opts.tolerant = self.tolerant;
+        opts.strict = self.strict;
+    }
+}
+
+/// key 的生成方式与映射表中记录的内容
+#[derive(Args)]
+#[command(next_help_heading = "key 与映射表")]
+struct KeyArgs {
+    /// 相同的字符串共用同一个索引，映射表中记录出现次数
+    #[arg(long)]
+    dedupe: bool,
+    /// key 的生成方式：index（默认，顺序索引）或 hash（文件路径 + 字符串内容的 xxhash64）
+    #[arg(long, value_name = "MODE", value_parser = parse_key_mode)]
+    key_mode: Option<KeyMode>,
+    /// 索引模式下的起始索引；分批提取后合并到同一张字符串表时用来避免 key 冲突
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    start_index: usize,
+    /// 索引的写法：decimal（默认）、padN（补零到 N 位，如 pad4）、hex 或 base62
+    #[arg(long, value_name = "FORMAT", value_parser = parse_index_format)]
+    index_format: Option<IndexFormat>,
+    /// 按模板生成 key（代替 --key-mode 与 --namespace），可用变量 {file}、{line}、{n}、{hash}，
+    /// 如 "{file}:{line}:{n}"
+    #[arg(long, value_name = "TEMPLATE")]
+    key_template: Option<KeyTemplate>,
+    /// key 的命名空间：file 时以文件名（不含扩展名）为前缀，如 "battle.12"
+    #[arg(long, value_parser = parse_namespace)]
+    namespace: Option<KeyNamespace>,
+    /// 映射表中记录每个字符串的位置（行、列与字节范围）
+    #[arg(long)]
+    with_locations: bool,
+    /// 映射表中记录每个字符串外层的函数、类、调用或 JSX 元素（如 "Battle > start > showDialog(...)"）
+    #[arg(long)]
+    with_context: bool,
+    /// 映射表中同时记录字面量的原始写法（如 "你好\n"），restore 时逐字节还原
+    #[arg(long)]
+    keep_raw: bool,
+    /// 去掉字符串两端的空白与换行后再写入映射表（原始写法记录在 raw 中，restore 时原样还原）
+    #[arg(long)]
+    trim: bool,
+    /// 把字符串中连续的空白与换行合并为一个空格后再写入映射表（原始写法记录在 raw 中）
+    #[arg(long)]
+    collapse_whitespace: bool,
+}
+
+impl KeyArgs {
+    /// 写入提取选项
+    fn apply(self, opts: &mut ExtractOptions) {
+        opts.dedupe = self.dedupe;
+        opts.key_mode = self.key_mode.unwrap_or_default();
+        opts.start_index = self.start_index;
+        opts.index_format = self.index_format.unwrap_or_default();
+        opts.key_template = self.key_template;
+        opts.namespace = self.namespace;
+        opts.with_locations = self.with_locations;
+        opts.with_context = self.with_context;
+        opts.keep_raw = self.keep_raw;
+        opts.trim = self.trim;
+        opts.collapse_whitespace = self.collapse_whitespace;
+    }
+}
+
+/// 替换后代码的写法，extract / restore / apply 共用
+#[derive(Args)]
+#[command(next_help_heading = "代码生成")]
+struct StyleArgs {
+    /// 把字符串替换为查表调用 name(0)（key 不是数字时为 name("key")），而不是字面量 "0"；
+    /// restore / apply 时需给出同样的选项
+    #[arg(long, value_name = "NAME", value_parser = parse_wrap_call)]
+    wrap_call: Option<String>,
+    /// 输出代码的目标版本（es5 / es2015 ~ es2024 / esnext，默认 esnext），影响转义等写法，不做语法降级
+    #[arg(long, value_name = "VERSION", value_parser = parse_target)]
+    target: Option<EsVersion>,
+    /// 压缩输出代码
+    #[arg(long)]
+    minify: bool,
+    /// 输出代码只含 ASCII 字符，中文等写成 \uXXXX 转义
+    #[arg(long)]
+    ascii_only: bool,
+    /// 统一字符串字面量的引号：single 或 double（默认保留原来的写法）
+    #[arg(long, value_name = "STYLE", value_parser = parse_quote)]
+    quote: Option<QuoteStyle>,
+    /// 分号风格：always（默认）或 auto（省略代码块最后一条语句的分号）
+    #[arg(long = "semicolons", value_name = "MODE", value_parser = parse_semicolons)]
+    omit_last_semi: Option<bool>,
+    /// 换行符：auto（默认，与输入一致）、lf 或 crlf；输入带 UTF-8 BOM 时输出总是带上
+    #[arg(long, value_name = "STYLE", value_parser = parse_newline)]
+    newline: Option<Newline>,
+    /// 源文件编码：utf-8（默认）、gbk、shift-jis 等，或 auto（按内容判断）；输出代码使用同样的编码
+    #[arg(long, value_name = "ENCODING", default_value = "utf-8")]
+    encoding: SourceEncoding,
+    /// 同时替换 .svelte 组件中标签之间的纯文本（替换为 key，或 --wrap-call 的 {S(0)}）；
+    /// restore / apply 时需给出同样的选项
+    #[arg(long)]
+    markup_text: bool,
+    /// .json 数据文件中只替换这些位置的字符串值，可重复使用（如 "$.dialog[*].text"、"$..desc"），
+    /// 默认替换全部字符串值；restore / apply 时需给出同样的选项
+    #[arg(long, value_name = "PATH")]
+    json_paths: Vec<JsonPath>,
+}
+
+impl StyleArgs {
+    /// 代码生成选项
+    fn codegen(&self) -> CodegenOptions {
+        let mut codegen = CodegenOptions {
+            minify: self.minify,
+            ascii_only: self.ascii_only,
+            quote: self.quote,
+            omit_last_semi: self.omit_last_semi.unwrap_or_default(),
+            newline: self.newline.unwrap_or_default(),
+            ..CodegenOptions::default()
+        };
+        if let Some(target) = self.target {
+            codegen.target = target;
+        }
+        codegen
+    }
+
+    /// 写入提取选项
+    fn apply(self, opts: &mut ExtractOptions) {
+        opts.codegen = self.codegen();
+        opts.wrap_call = self.wrap_call;
+        opts.markup_text = self.markup_text;
+        opts.json_paths = self.json_paths;
+    }
+
+    /// restore / apply 的选项
+    fn restore_options(self) -> RestoreOptions {
+        RestoreOptions {
+            codegen: self.codegen(),
+            wrap_call: self.wrap_call,
+            markup_text: self.markup_text,
+            json_paths: self.json_paths,
+            preserve_format: false,
+        }
+    }
+}
+
+/// 输出哪些文件、写到哪里
+#[derive(Args)]
+#[command(next_help_heading = "输出")]
+struct OutputArgs {
+    /// 映射表格式，可用逗号分隔或重复给出多个：json（默认）、po、xliff、csv、tsv、fluent、i18next、dts、
+    /// msgpack、bincode、sqlite
+    #[arg(long, value_name = "FORMAT", value_delimiter = ',')]
+    format: Vec<MapFormat>,
+    /// JSON 映射表的结构：v1（默认，扁平形式）、v2（每条记录带有 count、files 与 first_line）
+    /// 或 array（原文组成的数组，下标即 key）
+    #[arg(long, value_name = "SCHEMA", default_value = "v1")]
+    map_format: MapSchema,
+    /// 在 JSON 映射表中写入 "$meta"（格式版本、工具版本、源文件哈希与提取选项），
+    /// restore / apply 据此拒绝不对应的映射表、提示过期的映射表
+    #[arg(long)]
+    with_meta: bool,
+    /// JSON 映射表中的非 ASCII 字符写成 \uXXXX（默认原样写出 UTF-8）
+    #[arg(long, visible_alias = "ascii-map")]
+    escape_non_ascii: bool,
+    /// JSON 映射表末尾加一个换行
+    #[arg(long)]
+    trailing_newline: bool,
+    /// i18next 输出的命名空间
+    #[arg(long, value_name = "NS", default_value = "translation")]
+    i18next_ns: String,
+    /// i18next 输出中拆分 key 的分隔符，为空时不拆分
+    #[arg(long, value_name = "SEP", default_value = ".")]
+    key_separator: String,
+    /// 输出写到单独的目录中，保持输入的目录结构，不在源码目录旁生成文件
+    #[arg(short = 'o', long, alias = "output", value_name = "DIR")]
+    out_dir: Option<PathBuf>,
+    /// 替换后代码的文件名模板，可用变量 {stem} 与 {ext}
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        default_value = "{stem}_r.{ext}",
+        value_parser = parse_name_template
+    )]
+    out_ts: String,
+    /// 映射表的文件名模板（扩展名按 --format 替换）
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        default_value = "{stem}_s.json",
+        value_parser = parse_name_template
+    )]
+    out_json: String,
+    /// 替换后的代码直接覆盖原文件，映射表写在原文件旁边（此时忽略 --out-ts）
+    #[arg(long, conflicts_with_all = ["out_dir", "watch"])]
+    in_place: bool,
+    /// 压缩映射表：gzip 或 zstd，文件名后追加 .gz / .zst（如 battle_s.json.gz）；
+    /// restore、apply、merge 等读取时按扩展名自动解压
+    #[arg(long, value_name = "ALGO")]
+    compress: Option<Compression>,
+    /// 配合 --compress，替换后的代码也压缩（如 battle_r.ts.gz）
+    #[arg(long, requires = "compress", conflicts_with = "in_place")]
+    compress_code: bool,
+    /// 配合 --in-place，覆盖前把原文件备份为 <file><suffix>，如 .bak
+    #[arg(long, value_name = "SUFFIX", requires = "in_place", value_parser = parse_backup)]
+    backup: Option<String>,
+    /// 输出的 _r.ts 或映射表已存在时直接覆盖（默认报错中止，避免覆盖手动修改过的译文）
+    #[arg(long, visible_alias = "yes")]
+    force: bool,
+    /// 输入像是已经处理过的文件（带有链接注释，或字符串几乎都是 "0"、"1" 这样的纯数字）时仍然提取，
+    /// 默认报错中止：重复提取会破坏与原映射表的对应关系
+    #[arg(long)]
+    allow_reprocess: bool,
+    /// 不再警告被 + 拼接或 .join("") 拆成片段的句子
+    #[arg(long)]
+    no_fragment_warnings: bool,
+    /// 按指定的格式检查提取出的字符串，不合法的逐条警告；目前只有 icu（ICU MessageFormat 语法）
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    validate: Vec<ValidateFormat>,
+    /// 只解析与提取、不写出任何文件，打印将替换的字符串数、将写出的文件与前 10 个字符串
+    #[arg(long, conflicts_with = "watch")]
+    dry_run: bool,
+    /// 写出前先以 unified diff 的格式显示每个文件替换前后的改动（终端中带颜色，较长时经过分页器），
+    /// 在终端中确认后再写出；与 --dry-run 同用时只显示、不写出
+    #[arg(long, conflicts_with = "watch")]
+    preview: bool,
+    /// 替换后的代码写到标准输出（输入为 - 时总是如此）；映射表写到 --map-to 指定的文件，
+    /// 未指定时写到标准错误
+    #[arg(long)]
+    stdout: bool,
+    /// 管道模式下映射表的输出路径，如 /dev/fd/3
+    #[arg(long, value_name = "PATH")]
+    map_to: Option<PathBuf>,
+    /// 所有输入文件共用一个索引空间，只写一张合并映射表，每个条目记录来源文件
+    #[arg(long, value_name = "FILE")]
+    combined_map: Option<PathBuf>,
+    /// 映射表按条数分块：每块最多 N 条，写出 <name>.0001.json、<name>.0002.json ... 与索引 <name>.index.json
+    #[arg(long, value_name = "N", conflicts_with = "split_by")]
+    split_map: Option<NonZeroUsize>,
+    /// 合并映射表按来源文件分块，每个文件一块（文件名与索引同 --split-map）
+    #[arg(long, value_name = "BY", requires = "combined_map")]
+    split_by: Option<SplitBy>,
+    /// 同时写出查表模块 <name>_strings.ts；配合 --wrap-call 时替换后的文件自动导入查表函数
+    #[arg(long)]
+    emit_loader: bool,
+    /// 同时写出 sourcemap <name>_r.ts.map，把替换后的代码映射回源文件
+    #[arg(long)]
+    source_map: bool,
+    /// 输入本身是生成的代码时，读取旁边的 <file>.ts.map 并与输出的 sourcemap 串联（隐含 --source-map）
+    #[arg(long)]
+    input_source_map: bool,
+    /// 输出代码中去掉注释（默认保留）
+    #[arg(long)]
+    strip_comments: bool,
+    /// 只在原位置替换字面量，缩进、空行、引号与注释都与输入一致（此时忽略 --strip-comments）
+    #[arg(long)]
+    preserve_format: bool,
+}
+
+/// `restore` 的参数
+#[derive(Args)]
+struct RestoreArgs {
+    /// 替换后的文件
+    #[arg(value_name = "FILE_R")]
+    file: PathBuf,
+    /// 映射表（JSON，或填写好的 CSV/TSV 表格）
+    #[arg(value_name = "MAP")]
+    map: PathBuf,
+    /// 映射表格式（csv / tsv / msgpack / bincode），扩展名为 .csv/.tsv/.msgpack/.bin 时可省略
+    #[arg(long, value_name = "FORMAT")]
+    from: Option<MapFormat>,
+    /// 不检查替换后的文件与映射表是否对应（手动改过替换后的文件时使用）
+    #[arg(long)]
+    no_verify: bool,
+    /// 提取时使用了 --preserve-format：只在原位置还原字面量，其余内容不变
+    /// （映射表的 $meta 中有记录时可省略）
+    #[arg(long)]
+    preserve_format: bool,
+    #[command(flatten)]
+    style: StyleArgs,
+}
+
+/// `merge` 的参数
+#[derive(Args)]
+struct MergeArgs {
+    /// 重新提取的映射表
+    #[arg(value_name = "NEW_S")]
+    new: PathBuf,
+    /// 带有旧译文的映射表
+    #[arg(value_name = "OLD_TRANSLATED")]
+    old: PathBuf,
+    /// 模糊匹配的相似度阈值（0 到 1）：原文变化不大时沿用旧译文并标记为 fuzzy
+    #[arg(long, value_name = "RATIO", default_value = "0.8", value_parser = parse_ratio)]
+    fuzzy_threshold: f64,
+    /// 不做模糊匹配，原文变化的记录一律标记为 changed 或 added
+    #[arg(long)]
+    no_fuzzy: bool,
+}
+
+/// `diff` 的参数
+#[derive(Args)]
+struct DiffArgs {
+    /// 旧映射表（JSON，或按扩展名识别的 CSV/TSV）
+    #[arg(value_name = "OLD")]
+    old: PathBuf,
+    /// 新映射表
+    #[arg(value_name = "NEW")]
+    new: PathBuf,
+    /// 输出形式
+    #[arg(long, value_enum, default_value = "text")]
+    format: DiffFormat,
+}
+
+/// `dupes` 的参数
+#[derive(Args)]
+struct DupesArgs {
+    /// 映射表（JSON，或按扩展名识别的 CSV/TSV）
+    #[arg(value_name = "MAP")]
+    map: PathBuf,
+}
+
+/// `glossary` 的参数
+#[derive(Args)]
+struct GlossaryArgs {
+    /// 映射表（JSON，或按扩展名识别的 CSV/TSV），可以给出多个
+    #[arg(value_name = "MAP", required = true)]
+    maps: Vec<PathBuf>,
+    /// 输出的 CSV 文件
+    #[arg(short, long, value_name = "FILE", default_value = "glossary.csv")]
+    output: PathBuf,
+    /// 只列出至少出现 N 次的词语
+    #[arg(long, value_name = "N", default_value_t = 2)]
+    min_count: usize,
+    /// 词语最短的字数
+    #[arg(long, value_name = "N", default_value_t = 2)]
+    min_len: usize,
+    /// 词语最长的字数
+    #[arg(long, value_name = "N", default_value_t = 6)]
+    max_len: usize,
+}
+
+/// `renumber` 的参数
+#[derive(Args)]
+struct RenumberArgs {
+    /// 按旧源文件提取的映射表（也可以是带译文的映射表）
+    #[arg(value_name = "MAP")]
+    map: PathBuf,
+    /// 生成映射表时的源文件
+    #[arg(long, value_name = "OLD")]
+    from: PathBuf,
+    /// 字符串顺序变化后的源文件
+    #[arg(long, value_name = "NEW")]
+    to: PathBuf,
+    /// 迁移后的映射表，默认为映射表旁的 <name>_n.json
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+    /// key 迁移表（旧 key -> 新 key，已删除的为 null），默认为映射表旁的 <name>_keys.json
+    #[arg(long, value_name = "FILE")]
+    migration: Option<PathBuf>,
+    #[command(flatten)]
+    filter: FilterArgs,
+    #[command(flatten)]
+    keys: KeyArgs,
+}
+
+/// `diff-src` 的参数
+#[derive(Args)]
+struct DiffSrcArgs {
+    /// 旧源文件
+    #[arg(value_name = "OLD")]
+    old: PathBuf,
+    /// 新源文件
+    #[arg(value_name = "NEW")]
+    new: PathBuf,
+    /// 不考虑顺序，只比较两边各有哪些字符串（出现次数不同也算差异）
+    #[arg(long)]
+    unordered: bool,
+    /// 输出形式
+    #[arg(long, value_enum, default_value = "text")]
+    format: DiffFormat,
+    #[command(flatten)]
+    filter: FilterArgs,
+}
+
+/// `diff` / `diff-src` 的 `--format` 取值
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum DiffFormat {
+    /// 每条差异一行，以 + / - / ~ 开头
+    Text,
+    /// 一个 JSON 对象
+    Json,
+    /// 统一 diff 风格，以 - / + 开头
+    Unified,
+}
+
+/// `apply` 的参数
+#[derive(Args)]
+struct ApplyArgs {
+    /// 替换后的文件
+    #[arg(value_name = "FILE_R")]
+    file: PathBuf,
+    /// 译文映射表
+    #[arg(value_name = "TRANSLATED")]
+    map: PathBuf,
+    /// 映射表格式（csv / tsv / msgpack / bincode），扩展名为 .csv/.tsv/.msgpack/.bin 时可省略
+    #[arg(long, value_name = "FORMAT")]
+    from: Option<MapFormat>,
+    /// 输出文件，默认 <name>_t.ts
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+    /// 不检查替换后的文件与映射表是否对应（手动改过替换后的文件时使用）
+    #[arg(long)]
+    no_verify: bool,
+    /// 提取时使用了 --preserve-format：只在原位置还原字面量，其余内容不变
+    /// （映射表的 $meta 中有记录时可省略）
+    #[arg(long)]
+    preserve_format: bool,
+    #[command(flatten)]
+    style: StyleArgs,
+}
+
+/// `check-placeholders` 的参数
+#[derive(Args)]
+struct CheckArgs {
+    /// 原文映射表
+    #[arg(value_name = "SOURCE")]
+    source: PathBuf,
+    /// 译文映射表
+    #[arg(value_name = "TRANSLATED")]
+    translated: PathBuf,
+}
+
+/// `stats` 的参数
+#[derive(Args)]
+struct StatsArgs {
+    /// 输入的文件、目录或 glob 模式
+    #[arg(value_name = "输入", required_unless_present = "tsconfig")]
+    inputs: Vec<String>,
+    /// 排除匹配该 glob 模式的文件，可重复使用
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<Pattern>,
+    /// 遍历目录与展开 glob 时包含 .d.ts 声明文件（默认跳过）
+    #[arg(long)]
+    include_dts: bool,
+    /// 按 tsconfig.json 的 files / include / exclude 选择要处理的文件
+    #[arg(long, value_name = "FILE")]
+    tsconfig: Option<PathBuf>,
+    #[command(flatten)]
+    filter: FilterArgs,
+}
+
+/// `init` 的参数
+#[derive(Args)]
+struct InitArgs {
+    /// 配置文件已存在时直接覆盖
+    #[arg(long, visible_alias = "yes")]
+    force: bool,
+}
+
+/// 写入 `$meta` 的提取选项：影响 key 与还原方式的那些
+fn meta_options(opts: &ExtractOptions, schema: MapSchema) -> Map<String, Value> {
+    let mut options = Map::new();
+    options.insert(
+        "wrap_call".to_string(),
+        opts.wrap_call.clone().map_or(Value::Null, Value::String),
+    );
+    let key_mode = match opts.key_mode {
+        KeyMode::Index => "index",
+        KeyMode::Hash => "hash",
+    };
+    options.insert("key_mode".to_string(), Value::from(key_mode));
+    options.insert("dedupe".to_string(), Value::Bool(opts.dedupe));
+    options.insert("keep_raw".to_string(), Value::Bool(opts.keep_raw));
+    options.insert(
+        "preserve_format".to_string(),
+        Value::Bool(opts.preserve_format),
+    );
+    let schema = match schema {
+        MapSchema::V1 => "v1",
+        MapSchema::V2 => "v2",
+        MapSchema::Array => "array",
+    };
+    options.insert("map_format".to_string(), Value::from(schema));
+    options
+}
+
+/// 映射表的输出设置：写 `$meta` 时记下各源文件与替换后文件的哈希
+fn format_options(
+    cli: &CliArgs,
+    sources: impl IntoIterator<Item = (String, String)>,
+    code: impl IntoIterator<Item = (String, String)>,
+) -> FormatOptions {
+    let mut opts = cli.format_opts.clone();
+    if let Some(meta) = &mut opts.meta {
+        meta.sources.extend(sources);
+        meta.code.extend(code);
+    }
+    opts
+}
+
+/// 提取时的设置（由 [`ExtractArgs`] 整理而来）
+#[derive(Clone)]
+struct CliArgs {
+    /// 提取选项（`file_name` 在处理每个文件时再设置）
+    opts: ExtractOptions,
+    /// 处理完后继续监视文件变化
+    watch: bool,
+    /// `--jobs`：同时处理的文件数
+    jobs: usize,
+    /// `--since` 指定的提交（`--changed` 时为 `HEAD`）：只处理相对于它有改动的文件
+    since: Option<String>,
+    /// `--force-rebuild`：不跳过没有变化的文件
+    force_rebuild: bool,
+    /// 增量缓存文件（`--no-cache` 时为 `None`）
+    cache_file: Option<PathBuf>,
+    /// 取舍记录文件（`--no-decisions` 时为 `None`）
+    decisions_file: Option<PathBuf>,
+    /// `--exclude` 排除的 glob 模式
+    exclude: Vec<Pattern>,
+    /// 遍历目录时包含 `.d.ts` 声明文件（只列出字符串）
+    include_dts: bool,
+    /// `--tsconfig` 指定的 tsconfig.json
+    tsconfig: Option<PathBuf>,
+    /// `--encoding` 指定的源文件编码
+    encoding: SourceEncoding,
+    /// `--combined-map` 指定的合并映射表路径
+    combined_map: Option<PathBuf>,
+    /// `--split-map` / `--split-by`：映射表如何分块
+    split: Option<SplitMode>,
+    /// `--emit-loader`：同时写出查表模块 `<name>_strings.ts`
+    emit_loader: bool,
+    /// `--input-source-map`：读取输入旁边的 `<file>.map` 并与输出的 sourcemap 串联
+    input_source_map: bool,
+    /// `--format` 指定的映射表格式（去重，未指定时为 JSON）
+    formats: Vec<MapFormat>,
+    /// 各输出格式的附加设置（`--i18next-ns` 等）
+    format_opts: FormatOptions,
+    /// `--out-dir` 指定的输出目录
+    out_dir: Option<PathBuf>,
+    /// `--out-ts` / `--out-json` 指定的输出文件名模板
+    names: OutputNames,
+    /// `--in-place`：替换后的代码直接覆盖原文件
+    in_place: bool,
+    /// `--backup` 指定的备份文件后缀（如 `.bak`）
+    backup: Option<String>,
+    /// `--compress` 指定的压缩格式
+    compress: Option<Compression>,
+    /// `--compress-code`：替换后的代码也压缩
+    compress_code: bool,
+    /// `--force` / `--yes`：输出文件已存在时直接覆盖
+    force: bool,
+    /// `--allow-reprocess`：不检查输入是否已经处理过
+    allow_reprocess: bool,
+    /// 警告被拆成片段的句子（`--no-fragment-warnings` 时关闭）
+    fragment_warnings: bool,
+    /// `--validate icu`：按 ICU MessageFormat 的语法检查提取出的字符串
+    validate_icu: bool,
+    /// `--dry-run`：只解析与提取，不写出任何文件
+    dry_run: bool,
+    /// `--preview`：写出前先显示每个文件替换前后的差分（与 `--dry-run` 同用时只显示）
+    preview: bool,
+    /// `--stdout`：替换后的代码写到标准输出（输入为 `-` 时总是如此）
+    stdout: bool,
+    /// `--map-to` 指定的管道模式映射表输出路径
+    map_to: Option<PathBuf>,
+}
+
+impl From<ExtractArgs> for CliArgs {
+    fn from(args: ExtractArgs) -> Self {
+        let ExtractArgs {
+            inputs: _,
+            watch,
+            jobs,
+            changed,
+            since,
+            force_rebuild,
+            cache_file,
+            no_cache,
+            decisions_file,
+            no_decisions,
+            exclude,
+            include_dts,
+            tsconfig,
+            filter,
+            keys,
+            style,
+            output,
+        } = args;
+
+        let mut opts = ExtractOptions::default();
+        filter.apply(&mut opts);
+        keys.apply(&mut opts);
+        let encoding = style.encoding;
+        style.apply(&mut opts);
+        opts.source_map = output.source_map || output.input_source_map;
+        opts.strip_comments = output.strip_comments;
+        opts.preserve_format = output.preserve_format;
+
+        let mut formats = Vec::new();
+        for format in output.format {
+            if !formats.contains(&format) {
+                formats.push(format);
+            }
+        }
+        if formats.is_empty() {
+            formats.push(MapFormat::Json);
+        }
+
+        // 数组映射表的下标就是 key，只能用从 0 开始的十进制索引
+        if output.map_format == MapSchema::Array {
+            if opts.key_mode != KeyMode::Index
+                || opts.index_format != IndexFormat::Decimal
+                || opts.key_template.is_some()
+                || opts.namespace.is_some()
+                || opts.start_index != 0
+            {
+                arg_error(
+                    "--map-format array 要求 key 为从 0 开始的十进制索引，不能与 --key-mode hash、--index-format、--key-template、--namespace、--start-index 一起使用",
+                );
+            }
+            if output.with_meta || output.split_map.is_some() || output.split_by.is_some() {
+                arg_error("--map-format array 不能与 --with-meta、--split-map/--split-by 一起使用");
+            }
+        }
+        let meta = output
+            .with_meta
+            .then(|| MapMeta::new(meta_options(&opts, output.map_format)));
+
+        let jobs = jobs
+            .or_else(|| thread::available_parallelism().ok())
+            .map_or(1, NonZeroUsize::get);
+
+        CliArgs {
+            opts,
+            watch,
+            jobs,
+            since: since.or_else(|| changed.then(|| "HEAD".to_string())),
+            force_rebuild,
+            cache_file: (!no_cache).then_some(cache_file),
+            decisions_file: (!no_decisions).then_some(decisions_file),
+            exclude,
+            include_dts,
+            tsconfig,
+            encoding,
+            combined_map: output.combined_map,
+            split: match (output.split_map, output.split_by) {
+                (Some(size), _) => Some(SplitMode::Count(size)),
+                (None, Some(SplitBy::File)) => Some(SplitMode::File),
+                (None, None) => None,
+            },
+            emit_loader: output.emit_loader,
+            input_source_map: output.input_source_map,
+            formats,
+            format_opts: FormatOptions {
+                i18next_ns: output.i18next_ns,
+                key_separator: output.key_separator,
+                schema: output.map_format,
+                meta,
+                json: JsonStyle {
+                    escape_non_ascii: output.escape_non_ascii,
+                    trailing_newline: output.trailing_newline,
+                },
+            },
+            out_dir: output.out_dir,
+            names: OutputNames {
+                code: output.out_ts,
+                map: output.out_json,
+            },
+            in_place: output.in_place,
+            backup: output.backup,
+            compress: output.compress,
+            compress_code: output.compress_code,
+            force: output.force,
+            allow_reprocess: output.allow_reprocess,
+            fragment_warnings: !output.no_fragment_warnings,
+            validate_icu: output.validate.contains(&ValidateFormat::Icu),
+            dry_run: output.dry_run,
+            preview: output.preview,
+            stdout: output.stdout,
+            map_to: output.map_to,
+        }
+    }
+}
+
+/// 配置文件名：在当前目录下提取时自动读取
+const CONFIG_FILE: &str = "sb_dice.toml";
+
+/// 从配置文件读取的选项
+struct Config {
+    /// 插在命令行选项前面的参数，如 `--exclude=**/*.d.ts`
+    args: Vec<String>,
+    /// `inputs`：命令行上没有给出输入（与 `--tsconfig`）时使用
+    inputs: Vec<String>,
+    /// `tsconfig`：与 `inputs` 一样，命令行上没有给出输入时才使用
+    tsconfig: Option<PathBuf>,
+}
+
+/// 读取配置文件：`key = true` 对应开关 `--key`，字符串与整数对应 `--key=值`，数组逐项重复
+fn read_config(path: &Path) -> Result<Config, CliError> {
+    let text = read_input(path)?;
+    let error = |message: String| CliError::Config {
+        path: path.to_path_buf(),
+        message,
+    };
+    let table: toml::Table = text
+        .parse()
+        .map_err(|e: toml::de::Error| error(e.to_string().trim_end().to_string()))?;
+
+    let mut config = Config {
+        args: Vec::new(),
+        inputs: Vec::new(),
+        tsconfig: None,
+    };
+    for (key, value) in table {
+        let values = match value {
+            toml::Value::Array(items) => items,
+            other => vec![other],
+        };
+        if key == "inputs" {
+            for value in values {
+                match value {
+                    toml::Value::String(input) => config.inputs.push(input),
+                    other => return Err(error(format!("inputs 只能是字符串，实际为 {}", other))),
+                }
+            }
+            continue;
+        }
+        if key == "tsconfig" {
+            match values[..] {
+                [toml::Value::String(ref path)] => config.tsconfig = Some(PathBuf::from(path)),
+                _ => return Err(error("tsconfig 只能是一个字符串".to_string())),
+            }
+            continue;
+        }
+
+        let flag = format!("--{}", key.replace('_', "-"));
+        for value in values {
+            match value {
+                toml::Value::Boolean(true) => config.args.push(flag.clone()),
+                toml::Value::Boolean(false) => {}
+                toml::Value::String(s) => config.args.push(format!("{}={}", flag, s)),
+                toml::Value::Integer(n) => config.args.push(format!("{}={}", flag, n)),
+                other => {
+                    return Err(error(format!(
+                        "{} 只能是开关、字符串、整数或它们的数组，实际为 {}",
+                        key, other
+                    )));
+                }
+            }
+        }
+    }
+    Ok(config)
+}
+
+/// 解析命令行参数
+///
+/// 提取与 `check` 时读取配置文件（`--config`，默认为当前目录下存在的 `sb_dice.toml`），把其中的选项插在
+/// 命令行选项前面重新解析，命令行上的选项因此优先。
+fn parse_cli() -> Result<Cli, CliError> {
+    let argv: Vec<String> = env::args().collect();
+    let cli = Cli::try_parse_from(&argv).map_err(CliError::Args)?;
+    let extracting = matches!(
+        cli.command,
+        None | Some(Command::Extract(_) | Command::Check(_) | Command::Review(_))
+    );
+    let path = match &cli.config {
+        Some(path) => path.clone(),
+        None => PathBuf::from(CONFIG_FILE),
+    };
+    if !extracting || (cli.config.is_none() && !path.is_file()) {
+        return Ok(cli);
+    }
+    let config = read_config(&path)?;
+
+    // 插在子命令名之后；没有子命令时插在最前面
+    let at = match cli.command {
+        Some(_) => argv
+            .iter()
+            .position(|a| a == "extract" || a == "check" || a == "review")
+            .map_or(1, |i| i + 1),
+        None => 1,
+    };
+    let mut merged = argv[..at].to_vec();
+    merged.extend(config.args);
+    merged.extend_from_slice(&argv[at..]);
+
+    let mut cli = Cli::try_parse_from(merged).map_err(CliError::Args)?;
+    let extract = match &mut cli.command {
+        Some(Command::Extract(args) | Command::Check(args)) => args.as_mut(),
+        Some(Command::Review(args)) => &mut args.extract,
+        _ => &mut cli.extract,
+    };
+    if extract.inputs.is_empty() && extract.tsconfig.is_none() {
+        extract.inputs = config.inputs;
+        extract.tsconfig = config.tsconfig;
+    }
+    Ok(cli)
+}
+
+/// 打印参数错误并退出
+fn arg_error(msg: &str) -> ! {
+    CliError::Usage(msg.to_string()).exit()
+}
+
+/// 拆分逗号分隔的列表，忽略空项
+fn split_list(s: &str) -> impl Iterator<Item = String> + '_ {
+    s.split(',')
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(str::to_string)
+}
+
+/// 要处理的文件：展开输入与 `--tsconfig`，`--changed` / `--since` 时只保留 git 中有改动的文件
+fn select_files(inputs: &[String], cli: &CliArgs) -> Result<Vec<PathBuf>, CliError> {
+    let mut files = collect_inputs(
+        inputs,
+        cli.tsconfig.as_deref(),
+        &cli.exclude,
+        cli.include_dts,
+    )?;
+    if let Some(rev) = &cli.since {
+        let changed = git_changed_files(rev)?;
+        files.retain(|file| {
+            let keep = fs::canonicalize(file).is_ok_and(|f| changed.contains(&f));
+            if !keep {
+                report_skipped(file, "git 中没有改动");
+            }
+            keep
+        });
+    }
+    Ok(files)
+}
+
+/// 各输入的镜像根目录（见 [`output_dir`]）；tsconfig 选中的文件以其所在目录为根
+fn mirror_roots(inputs: &[String], cli: &CliArgs) -> Vec<PathBuf> {
+    inputs
+        .iter()
+        .map(|i| mirror_root(i))
+        .chain(cli.tsconfig.as_deref().map(|p| parent_dir(p).to_path_buf()))
+        .collect()
+}
+
+/// 提取字符串：`sb_dice [extract] [选项] <输入>...`
+fn run_extract(mut args: ExtractArgs) -> Result<(), CliError> {
+    let inputs = std::mem::take(&mut args.inputs);
+    if inputs.is_empty() && args.tsconfig.is_none() {
+        print_usage_and_exit();
+    }
+    let mut cli = CliArgs::from(args);
+    cli.opts.excluded = load_decisions(&cli)?.excluded();
+
+    // 管道模式：从标准输入读取或写到标准输出
+    if cli.stdout || inputs.iter().any(|i| i == "-") {
+        return match &inputs[..] {
+            [input] => run_stdio(input, &cli),
+            _ => arg_error("管道模式（- 或 --stdout）只能处理一个输入"),
+        };
+    }
+
+    let files = select_files(&inputs, &cli)?;
+    let mirror = mirror_roots(&inputs, &cli);
+    extract_files(&files, &mirror, &cli)?;
+
+    // 只给了一个文件时不需要汇总（试运行已经打印过汇总）
+    if !cli.dry_run
+        && (cli.tsconfig.is_some() || inputs.len() > 1 || !Path::new(&inputs[0]).is_file())
+    {
+        println!("完成：共处理 {} 个文件", files.len());
+    }
+
+    if cli.watch {
+        let roots: Vec<PathBuf> = inputs
+            .iter()
+            .map(|i| {
+                if has_glob_chars(i) && !Path::new(i).exists() {
+                    glob_root(i)
+                } else {
+                    PathBuf::from(i)
+                }
+            })
+            .chain(cli.tsconfig.as_deref().map(|p| parent_dir(p).to_path_buf()))
+            .collect();
+        // 之后重新生成的是本次运行写出的文件，不再检查覆盖
+        cli.force = true;
+        watch(&roots, &inputs, &mirror, &cli)?;
+    }
+    Ok(())
+}
+
+fn main() {
+    // 解析命令行参数；--help 与 --version 照常输出并以 0 退出
+    let cli = match parse_cli() {
+        Ok(cli) => cli,
+        Err(CliError::Args(err)) if !err.use_stderr() => err.exit(),
+        Err(err) => err.exit(),
+    };
+    JSON_DIAGNOSTICS.store(
+        cli.diagnostics == DiagnosticsFormat::Json,
+        Ordering::Relaxed,
+    );
+
+    // 所有错误都返回到这里统一输出
+    let result = match cli.command {
+        None => run_extract(cli.extract),
+        Some(Command::Extract(args)) => run_extract(*args),
+        Some(Command::Restore(args)) => run_restore(args),
+        Some(Command::Merge(args)) => run_merge(args),
+        Some(Command::Diff(args)) => run_diff(args),
+        Some(Command::DiffSrc(args)) => run_diff_src(*args),
+        Some(Command::Dupes(args)) => run_dupes(args),
+        Some(Command::Glossary(args)) => run_glossary(args),
+        Some(Command::Renumber(args)) => run_renumber(*args),
+        Some(Command::Apply(args)) => run_apply(args),
+        Some(Command::Check(args)) => run_check(*args),
+        Some(Command::Review(args)) => run_review(*args),
+        Some(Command::CheckPlaceholders(args)) => run_check_placeholders(args),
+        Some(Command::Stats(args)) => run_stats(*args),
+        Some(Command::Init(args)) => run_init(args),
+    };
+    if let Err(err) = result {
+        err.exit();
+    }
 }
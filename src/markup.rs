@@ -0,0 +1,245 @@
+//! 组件与页面中的 `<script>`（`.svelte`、`.html`）
+//!
+//! 只有 `<script>` 中的代码交给 swc：把其余部分（标签、文本、`<style>`）的每个字节都换成空格（换行保留），
+//! 得到与原文件字节偏移、行号完全相同的"代码"，整体按一个模块解析；替换总是按 span 在原文件上进行，
+//! 标签与样式原样保留。相邻两段脚本之间补一个 `;`，前一段末尾省略的分号不会让两段连在一起。
+//! 页面中引用外部文件（`src=`）或不是 JavaScript（如 `type="application/json"`、`text/template`）的 `<script>` 跳过。
+//!
+//! 另外收集标签之间的文本（见 [`Document::texts`]）：不含 `{...}` 表达式的纯文本可以
+//! 与脚本中的字符串一起替换，共用同一张映射表。
+
+use std::ops::Range;
+use std::path::Path;
+
+use crate::SourceSyntax;
+
+/// 组件文件的种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Markup {
+    /// Svelte 组件（`.svelte`）
+    Svelte,
+    /// 内嵌脚本的 HTML 页面（`.html` / `.htm`）
+    Html,
+}
+
+impl Markup {
+    /// 按扩展名判断
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "svelte" => Some(Markup::Svelte),
+            "html" | "htm" => Some(Markup::Html),
+            _ => None,
+        }
+    }
+}
+
+/// 一段 `<script>`
+#[derive(Debug, Clone)]
+pub struct Script {
+    /// 代码（开始标签与结束标签之间）的字节范围
+    pub code: Range<usize>,
+    /// `lang="ts"`
+    pub typescript: bool,
+}
+
+/// 扫描后的组件文件
+#[derive(Debug, Clone)]
+pub struct Document {
+    /// 文件的种类
+    pub kind: Markup,
+    /// 各段脚本，按出现顺序
+    pub scripts: Vec<Script>,
+    /// 标签之间的文本（已去掉两端空白）的字节范围
+    pub texts: Vec<Range<usize>>,
+}
+
+impl Document {
+    /// 扫描组件文件：找出 `<script>` 与标签之间的文本，跳过注释与 `<style>`
+    pub fn scan(src: &str, kind: Markup) -> Self {
+        let bytes = src.as_bytes();
+        // 只有 Svelte 的标签中可以有 `{...}` 表达式
+        let braces = kind == Markup::Svelte;
+        let mut doc = Document {
+            kind,
+            scripts: Vec::new(),
+            texts: Vec::new(),
+        };
+        let mut text_start = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] != b'<' {
+                i += 1;
+                continue;
+            }
+            let rest = &src[i..];
+            if rest.starts_with("<!--") {
+                doc.push_text(src, text_start..i);
+                i = find(src, i + 4, "-->").map_or(bytes.len(), |end| end + 3);
+                text_start = i;
+                continue;
+            }
+            // `<` 后面不是标签名（如文本中的 `a < b`）时当作文本
+            let is_tag = rest[1..]
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '/');
+            if !is_tag {
+                i += 1;
+                continue;
+            }
+            doc.push_text(src, text_start..i);
+            let tag = i..tag_end(bytes, i, braces);
+            let name = tag_name(&src[i + 1..tag.end]);
+            i = tag.end;
+            if name.eq_ignore_ascii_case("script") || name.eq_ignore_ascii_case("style") {
+                let close = format!("</{}", name.to_ascii_lowercase());
+                let end = find_ignore_case(src, i, &close).unwrap_or(bytes.len());
+                let attrs = &src[tag];
+                if name.eq_ignore_ascii_case("script") && is_inline_js(attrs) {
+                    doc.scripts.push(Script {
+                        code: i..end,
+                        typescript: matches!(
+                            attr(attrs, "lang").as_deref(),
+                            Some("ts" | "typescript")
+                        ),
+                    });
+                }
+                i = if end < bytes.len() {
+                    tag_end_from(bytes, end)
+                } else {
+                    end
+                };
+            }
+            text_start = i;
+        }
+        doc.push_text(src, text_start..bytes.len());
+        doc
+    }
+
+    /// 记录一段文本（去掉两端空白；只有空白的文本不记录）
+    fn push_text(&mut self, src: &str, range: Range<usize>) {
+        let text = &src[range.clone()];
+        let trimmed = text.trim();
+        if trimmed.is_empty() {
+            return;
+        }
+        let start = range.start + (text.len() - text.trim_start().len());
+        self.texts.push(start..start + trimmed.len());
+    }
+
+    /// 解析所用的语法：有一段脚本是 `lang="ts"` 时按 TypeScript，否则按 JavaScript
+    pub fn syntax(&self) -> SourceSyntax {
+        if self.scripts.iter().any(|s| s.typescript) {
+            SourceSyntax::Typescript
+        } else {
+            SourceSyntax::Javascript
+        }
+    }
+
+    /// 交给 swc 解析的代码：脚本以外的字节都换成空格（换行保留），每段脚本之后补一个 `;`
+    pub fn mask(&self, src: &str) -> String {
+        let mut out: Vec<u8> = src
+            .bytes()
+            .map(|b| if b == b'\n' || b == b'\r' { b } else { b' ' })
+            .collect();
+        for script in &self.scripts {
+            out[script.code.clone()].copy_from_slice(&src.as_bytes()[script.code.clone()]);
+            if let Some(b) = out.get_mut(script.code.end) {
+                *b = b';';
+            }
+        }
+        // 只把 ASCII 字节换成了 ASCII，多字节字符整个换成了空格，结果仍是合法的 UTF-8
+        String::from_utf8(out).unwrap_or_default()
+    }
+}
+
+/// 从 `from` 开始查找 `pat`
+fn find(src: &str, from: usize, pat: &str) -> Option<usize> {
+    src[from..].find(pat).map(|i| from + i)
+}
+
+/// 从 `from` 开始查找 `pat`（ASCII 不区分大小写，`pat` 为小写）
+fn find_ignore_case(src: &str, from: usize, pat: &str) -> Option<usize> {
+    let bytes = src.as_bytes();
+    (from..bytes.len().saturating_sub(pat.len() - 1))
+        .find(|&i| bytes[i..i + pat.len()].eq_ignore_ascii_case(pat.as_bytes()))
+}
+
+/// `<script>` 是否为内嵌的 JavaScript：没有 `src`，`type` 省略或为 JavaScript 的 MIME 类型、`module`
+fn is_inline_js(tag: &str) -> bool {
+    if attr(tag, "src").is_some() {
+        return false;
+    }
+    match attr(tag, "type") {
+        None => true,
+        Some(ty) => matches!(
+            ty.as_str(),
+            "" | "module"
+                | "text/javascript"
+                | "application/javascript"
+                | "text/ecmascript"
+                | "application/ecmascript"
+        ),
+    }
+}
+
+/// 标签结束（`>` 之后）的位置：跳过引号中的内容，`braces` 时还跳过 Svelte 的 `{...}` 表达式
+fn tag_end(bytes: &[u8], start: usize, braces: bool) -> usize {
+    let mut quote = None;
+    let mut depth = 0usize;
+    for (i, &b) in bytes.iter().enumerate().skip(start + 1) {
+        match (quote, b) {
+            (Some(q), _) if b == q => quote = None,
+            (Some(_), _) => {}
+            (None, b'"' | b'\'') => quote = Some(b),
+            (None, b'{') if braces => depth += 1,
+            (None, b'}') if braces => depth = depth.saturating_sub(1),
+            (None, b'>') if depth == 0 => return i + 1,
+            _ => {}
+        }
+    }
+    bytes.len()
+}
+
+/// 结束标签（如 `</script >`）之后的位置
+fn tag_end_from(bytes: &[u8], start: usize) -> usize {
+    bytes[start..]
+        .iter()
+        .position(|&b| b == b'>')
+        .map_or(bytes.len(), |i| start + i + 1)
+}
+
+/// 标签名（`script lang="ts">` 中的 `script`）
+fn tag_name(tag: &str) -> &str {
+    let end = tag
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .unwrap_or(tag.len());
+    &tag[..end]
+}
+
+/// 取开始标签中属性的值（`lang="ts"`、`lang=ts`），属性名不区分大小写
+fn attr(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(pos) = find(&lower, from, name) {
+        from = pos + name.len();
+        let before = lower[..pos].chars().next_back();
+        if !before.is_some_and(char::is_whitespace) {
+            continue;
+        }
+        let rest = lower[from..].trim_start();
+        let Some(value) = rest.strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let value = match value.chars().next() {
+            Some(q @ ('"' | '\'')) => value[1..].split(q).next().unwrap_or_default(),
+            _ => value
+                .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+                .next()
+                .unwrap_or_default(),
+        };
+        return Some(value.to_string());
+    }
+    None
+}
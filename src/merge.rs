@@ -0,0 +1,223 @@
+//! 合并映射表：把旧译文按原始字符串（或 key）带到重新提取的新映射表上。
+//!
+//! 译文文件中的记录形如 `{"text": "原始", "translation": "译文"}`；
+//! 纯字符串记录视为只有译文、没有原文，只能按 key 匹配。
+//! 合并结果沿用同样的格式，并对需要人工处理的记录加上 `"status"` 标记：
+//!   - `added`：新出现的字符串，没有可用的旧译文
+//!   - `changed`：同一个 key 的原文变了，旧译文仅供参考
+//!   - `fuzzy`：原文只有细微改动（标点、错别字），沿用了最相近的旧译文，需要人工确认，
+//!     旧原文记录在 `"previous"` 中（与 gettext 的 fuzzy 标记相同）
+//!
+//! 相似度按字符计算编辑距离：`1 - 距离 / 较长一方的字符数`，达到 [`MergeOptions::fuzzy`] 才算相近。
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::{Map, Value};
+
+use crate::entry_text;
+
+/// 合并选项
+#[derive(Debug, Clone, Default)]
+pub struct MergeOptions {
+    /// 模糊匹配的相似度阈值（0 到 1），`None` 时不做模糊匹配
+    pub fuzzy: Option<f64>,
+}
+
+/// 合并结果
+#[derive(Debug, Clone, Default)]
+pub struct MergeResult {
+    /// 合并后的映射表
+    pub merged: Map<String, Value>,
+    /// 新增的 key
+    pub added: Vec<String>,
+    /// 旧译文中已经没有对应字符串的 key
+    pub removed: Vec<String>,
+    /// 原文发生变化的 key
+    pub changed: Vec<String>,
+    /// 沿用了相近旧译文、需要确认的 key
+    pub fuzzy: Vec<String>,
+}
+
+/// 取旧记录中的译文
+pub(crate) fn translation_of(v: &Value) -> Option<&str> {
+    match v {
+        Value::String(s) => Some(s),
+        Value::Object(obj) => obj.get("translation").and_then(Value::as_str),
+        _ => None,
+    }
+}
+
+/// 取旧记录中的原文（纯字符串记录没有原文）
+fn original_of(v: &Value) -> Option<&str> {
+    match v {
+        Value::Object(_) => entry_text(v),
+        _ => None,
+    }
+}
+
+/// 两个字符串的相似度（0 到 1），按字符的编辑距离计算
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let substitute = diagonal + usize::from(x != y);
+            diagonal = row[j + 1];
+            row[j + 1] = substitute.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    1.0 - row[b.len()] as f64 / longest as f64
+}
+
+/// 在没有用过的旧记录中找原文与 `text` 最相近、相似度不低于 `threshold` 的一条
+fn closest<'a>(
+    text: &str,
+    candidates: &[(&'a str, &'a str)],
+    used: &HashSet<&str>,
+    threshold: f64,
+) -> Option<(&'a str, &'a str)> {
+    let len = text.chars().count();
+    let mut best: Option<(f64, (&str, &str))> = None;
+    for &(key, original) in candidates {
+        if used.contains(key) {
+            continue;
+        }
+        // 编辑距离不小于长度之差，长度相差太多的不可能达到阈值
+        let other = original.chars().count();
+        let (short, long) = (len.min(other), len.max(other));
+        if long > 0 && (short as f64) / (long as f64) < threshold {
+            continue;
+        }
+        let score = similarity(text, original);
+        if score >= threshold && best.is_none_or(|(b, _)| score > b) {
+            best = Some((score, (key, original)));
+        }
+    }
+    best.map(|(_, found)| found)
+}
+
+/// 把新记录转换为带译文与状态的对象
+fn merged_entry(new: &Value, translation: Option<&str>, status: Option<&str>) -> Value {
+    let mut obj = match new {
+        Value::Object(obj) => obj.clone(),
+        other => {
+            let mut obj = Map::new();
+            obj.insert("text".to_string(), other.clone());
+            obj
+        }
+    };
+    obj.insert(
+        "translation".to_string(),
+        Value::String(translation.unwrap_or_default().to_string()),
+    );
+    if let Some(status) = status {
+        obj.insert("status".to_string(), Value::String(status.to_string()));
+    }
+    Value::Object(obj)
+}
+
+/// 把模糊匹配到的记录标记为 `fuzzy`，并记下旧原文
+fn fuzzy_entry(new: &Value, translation: Option<&str>, previous: &str) -> Value {
+    let mut entry = merged_entry(new, translation, Some("fuzzy"));
+    if let Value::Object(obj) = &mut entry {
+        obj.insert("previous".to_string(), Value::String(previous.to_string()));
+    }
+    entry
+}
+
+/// 合并新映射表与旧译文（不做模糊匹配）
+///
+/// 匹配顺序：先按原文匹配（key 变了也能找回译文），再按 key 匹配。
+pub fn merge_mappings(new: &Map<String, Value>, old: &Map<String, Value>) -> MergeResult {
+    merge_mappings_with(new, old, &MergeOptions::default())
+}
+
+/// 按选项合并新映射表与旧译文
+///
+/// 开启模糊匹配时，同一个 key 的原文变化不大的记录标记为 `fuzzy` 而不是 `changed`；
+/// 精确匹配都找不到的新记录，再从没有用过的旧记录中找原文最相近的一条。
+pub fn merge_mappings_with(
+    new: &Map<String, Value>,
+    old: &Map<String, Value>,
+    opts: &MergeOptions,
+) -> MergeResult {
+    let mut result = MergeResult::default();
+
+    // 原文 -> 旧 key
+    let mut by_text: HashMap<&str, &str> = HashMap::new();
+    for (key, value) in old {
+        if let Some(text) = original_of(value) {
+            by_text.entry(text).or_insert(key);
+        }
+    }
+
+    let mut used: HashSet<&str> = HashSet::new();
+
+    for (key, value) in new {
+        let Some(text) = entry_text(value) else {
+            continue;
+        };
+
+        let entry = if let Some(&old_key) = by_text.get(text) {
+            used.insert(old_key);
+            merged_entry(value, translation_of(&old[old_key]), None)
+        } else if let Some(old_value) = old.get(key) {
+            used.insert(key);
+            match original_of(old_value) {
+                // 旧记录没有原文，只能相信 key
+                None => merged_entry(value, translation_of(old_value), None),
+                Some(previous) if opts.fuzzy.is_some_and(|t| similarity(text, previous) >= t) => {
+                    result.fuzzy.push(key.clone());
+                    fuzzy_entry(value, translation_of(old_value), previous)
+                }
+                Some(_) => {
+                    result.changed.push(key.clone());
+                    merged_entry(value, translation_of(old_value), Some("changed"))
+                }
+            }
+        } else {
+            result.added.push(key.clone());
+            merged_entry(value, None, Some("added"))
+        };
+
+        result.merged.insert(key.clone(), entry);
+    }
+
+    // 精确匹配全部完成后再做模糊匹配，避免抢走后面按 key 匹配的旧记录
+    if let Some(threshold) = opts.fuzzy {
+        let candidates: Vec<(&str, &str)> = old
+            .iter()
+            .filter_map(|(key, value)| Some((key.as_str(), original_of(value)?)))
+            .collect();
+        let mut still_added = Vec::new();
+        for key in std::mem::take(&mut result.added) {
+            let value = &new[&key];
+            let text = entry_text(value).unwrap_or_default();
+            match closest(text, &candidates, &used, threshold) {
+                Some((old_key, previous)) => {
+                    used.insert(old_key);
+                    let entry = fuzzy_entry(value, translation_of(&old[old_key]), previous);
+                    result.merged.insert(key.clone(), entry);
+                    result.fuzzy.push(key);
+                }
+                None => still_added.push(key),
+            }
+        }
+        result.added = still_added;
+    }
+
+    for key in old.keys() {
+        if !used.contains(key.as_str()) {
+            result.removed.push(key.clone());
+        }
+    }
+
+    result
+}
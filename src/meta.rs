@@ -0,0 +1,161 @@
+//! 映射表中的 `"$meta"` 记录：格式版本、生成它的工具版本、源文件的哈希与提取选项
+//!
+//! `restore` / `apply` 据此发现映射表与当前文件不对应（如 `--wrap-call` 不一致）或已经过期
+//! （源文件在提取后又改过），不再悄悄生成错误的结果。读取映射表时用 [`MapMeta::take`] 取出它，
+//! 其余记录照常处理；没有 `"$meta"` 的旧映射表不做检查。
+//!
+//! 替换后的文件与映射表互相记录对方的哈希：`$meta` 中记录替换后文件的内容哈希，
+//! 替换后的文件末尾写一行链接注释 `// sb-dice-map: <哈希>`，记录映射表 key 集合的哈希（见 [`keys_hash`]）。
+//! 链接注释写在末尾（`sourceMappingURL` 之前）而不是开头，不影响 sourcemap 的行号；
+//! 计算替换后文件的哈希时不含这一行。
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::{Map, Value};
+use xxhash_rust::xxh64::xxh64;
+
+/// 映射表中元信息记录的 key（`$` 开头，不会与提取出的 key 冲突）
+pub const META_KEY: &str = "$meta";
+
+/// 当前的映射表格式版本，格式有不兼容的变化时递增
+pub const META_VERSION: u64 = 1;
+
+/// 替换后文件中链接注释的开头
+pub const LINK_PREFIX: &str = "// sb-dice-map: ";
+
+/// 映射表的元信息
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapMeta {
+    /// 映射表格式版本（见 [`META_VERSION`]）
+    pub version: u64,
+    /// 生成映射表的工具与版本，如 `sb_dice 0.1.0`
+    pub tool: String,
+    /// 源文件（`/` 分隔的路径）-> 内容的哈希（见 [`content_hash`]）
+    pub sources: BTreeMap<String, String>,
+    /// 替换后的文件（`/` 分隔的路径）-> 不含链接注释的内容的哈希
+    pub code: BTreeMap<String, String>,
+    /// 提取时使用的选项，如 `{"wrap_call": "S", "dedupe": true}`
+    pub options: Map<String, Value>,
+}
+
+impl MapMeta {
+    /// 当前版本的元信息，源文件由调用方逐个加入
+    pub fn new(options: Map<String, Value>) -> Self {
+        Self {
+            version: META_VERSION,
+            tool: concat!("sb_dice ", env!("CARGO_PKG_VERSION")).to_string(),
+            sources: BTreeMap::new(),
+            code: BTreeMap::new(),
+            options,
+        }
+    }
+
+    /// 序列化为映射表中的值
+    pub fn to_value(&self) -> Value {
+        let hashes = |files: &BTreeMap<String, String>| {
+            files
+                .iter()
+                .map(|(file, hash)| (file.clone(), Value::String(hash.clone())))
+                .collect()
+        };
+        let mut obj = Map::new();
+        obj.insert("version".to_string(), Value::from(self.version));
+        obj.insert("tool".to_string(), Value::String(self.tool.clone()));
+        obj.insert("sources".to_string(), Value::Object(hashes(&self.sources)));
+        if !self.code.is_empty() {
+            obj.insert("code".to_string(), Value::Object(hashes(&self.code)));
+        }
+        obj.insert("options".to_string(), Value::Object(self.options.clone()));
+        Value::Object(obj)
+    }
+
+    /// 从映射表中的值解析
+    pub fn from_value(value: &Value) -> Result<Self, String> {
+        let obj = value.as_object().ok_or("$meta 必须是对象")?;
+        let version = obj
+            .get("version")
+            .and_then(Value::as_u64)
+            .ok_or("$meta 缺少格式版本 version")?;
+        let hashes = |name: &str| match obj.get(name) {
+            Some(Value::Object(files)) => files
+                .iter()
+                .map(|(file, hash)| match hash {
+                    Value::String(hash) => Ok((file.clone(), hash.clone())),
+                    _ => Err(format!("$meta.{} 中 {} 的哈希必须是字符串", name, file)),
+                })
+                .collect(),
+            None => Ok(BTreeMap::new()),
+            Some(_) => Err(format!("$meta.{} 必须是对象", name)),
+        };
+        let sources = hashes("sources")?;
+        let code = hashes("code")?;
+        Ok(Self {
+            version,
+            tool: obj
+                .get("tool")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            sources,
+            code,
+            options: obj
+                .get("options")
+                .and_then(Value::as_object)
+                .cloned()
+                .unwrap_or_default(),
+        })
+    }
+
+    /// 从映射表中取出并解析 `"$meta"`，没有时返回 `None`
+    pub fn take(map: &mut Map<String, Value>) -> Result<Option<Self>, String> {
+        map.remove(META_KEY)
+            .map(|value| Self::from_value(&value))
+            .transpose()
+    }
+
+    /// 提取时使用的 `--wrap-call`
+    pub fn wrap_call(&self) -> Option<&str> {
+        self.options.get("wrap_call").and_then(Value::as_str)
+    }
+
+    /// 提取时是否使用了 `--preserve-format`（较早的映射表没有记录，视为没有）
+    pub fn preserve_format(&self) -> bool {
+        self.options
+            .get("preserve_format")
+            .and_then(Value::as_bool)
+            .unwrap_or(false)
+    }
+}
+
+/// 文件内容的哈希：xxhash64，16 位十六进制
+pub fn content_hash(data: &[u8]) -> String {
+    format!("{:016x}", xxh64(data, 0))
+}
+
+/// 映射表 key 集合的哈希，写在替换后文件的链接注释中
+///
+/// 只看 key 不看内容：合并后的译文、CSV 表格与原来的映射表 key 相同，都能通过检查。
+pub fn keys_hash<'a>(keys: impl IntoIterator<Item = &'a str>) -> String {
+    let keys: BTreeSet<&str> = keys.into_iter().collect();
+    let joined: Vec<&str> = keys.into_iter().collect();
+    content_hash(joined.join("\n").as_bytes())
+}
+
+/// 链接注释这一行（含换行）
+pub fn link_comment(hash: &str) -> String {
+    format!("{}{}\n", LINK_PREFIX, hash)
+}
+
+/// 找出替换后文件中的链接注释，返回去掉这一行后的代码与其中记录的哈希（没有时原样返回代码）
+pub fn split_link(code: &str) -> (String, Option<String>) {
+    let found = code
+        .rmatch_indices(LINK_PREFIX)
+        .map(|(pos, _)| pos)
+        .find(|&pos| pos == 0 || code[..pos].ends_with('\n'));
+    let Some(pos) = found else {
+        return (code.to_string(), None);
+    };
+    let end = code[pos..].find('\n').map_or(code.len(), |i| pos + i + 1);
+    let hash = code[pos + LINK_PREFIX.len()..end].trim().to_string();
+    (format!("{}{}", &code[..pos], &code[end..]), Some(hash))
+}
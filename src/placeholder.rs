@@ -0,0 +1,94 @@
+//! 占位符一致性检查：译文中的占位符应当与原文一致
+//!
+//! 识别的占位符：
+//!   - `${...}`（模板字符串插值，配合 `--include-template-quasis` 时常见）
+//!   - `{{name}}`（i18next 插值）与 `{name}` / `{0}`（ICU / format 风格）
+//!   - `%s`、`%d`、`%1$s`、`%.2f` 等 printf 风格（`%%` 不算占位符）
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde_json::{Map, Value};
+
+use crate::entry_text;
+use crate::merge::translation_of;
+
+static PLACEHOLDER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"%%|\$\{[^}]*\}|\{\{[^{}]*\}\}|\{[^{}]*\}|%(?:\d+\$)?[-+ 0#]*\d*(?:\.\d+)?[sdifuxXoeEgGc@]",
+    )
+    .expect("占位符正则无效")
+});
+
+/// 取字符串中的所有占位符（按出现顺序）
+pub fn placeholders(s: &str) -> Vec<&str> {
+    PLACEHOLDER
+        .find_iter(s)
+        .map(|m| m.as_str())
+        .filter(|p| *p != "%%")
+        .collect()
+}
+
+/// 一条占位符不一致的记录
+#[derive(Debug, Clone)]
+pub struct PlaceholderIssue {
+    /// 映射表中的 key
+    pub key: String,
+    /// 原文中有、译文中缺少的占位符
+    pub missing: Vec<String>,
+    /// 译文中多出来的占位符
+    pub extra: Vec<String>,
+}
+
+/// 两组占位符的差集（按出现次数计算）
+fn difference(a: &[&str], b: &[&str]) -> Vec<String> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for p in b {
+        *counts.entry(*p).or_default() += 1;
+    }
+    let mut out = Vec::new();
+    for p in a {
+        match counts.get_mut(p) {
+            Some(n) if *n > 0 => *n -= 1,
+            _ => out.push(p.to_string()),
+        }
+    }
+    out
+}
+
+/// 检查译文表中的占位符是否与原文一致
+///
+/// `source` 为提取出的映射表（`_s.json`），`translated` 为译文表（`merge` 的输出格式）；
+/// 没有译文的记录跳过。
+pub fn check_placeholders(
+    source: &Map<String, Value>,
+    translated: &Map<String, Value>,
+) -> Vec<PlaceholderIssue> {
+    let mut issues = Vec::new();
+    for (key, value) in source {
+        let Some(original) = entry_text(value) else {
+            continue;
+        };
+        let Some(translation) = translated
+            .get(key)
+            .and_then(translation_of)
+            .filter(|t| !t.is_empty())
+        else {
+            continue;
+        };
+
+        let expected = placeholders(original);
+        let actual = placeholders(translation);
+        let missing = difference(&expected, &actual);
+        let extra = difference(&actual, &expected);
+        if !missing.is_empty() || !extra.is_empty() {
+            issues.push(PlaceholderIssue {
+                key: key.clone(),
+                missing,
+                extra,
+            });
+        }
+    }
+    issues
+}
@@ -0,0 +1,92 @@
+//! 映射表迁移（`renumber` 子命令）：源文件中的字符串顺序变了之后，按原文把旧 key 对应到新 key。
+//!
+//! 旧 key 与新 key 分别来自对旧、新源文件的提取结果。先按出现顺序比较两边的原文
+//! （同一个字符串出现多次时依次配对），剩下没有配对的再只按原文配对（整段代码被挪动的情况）。
+//! 得到的迁移表用来改写映射表的 key，也可以交给下游工具更新引用了旧 key 的补丁。
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde_json::{Map, Value};
+
+use crate::Entry;
+use crate::diff::{SeqEdit, diff_sequences, natural_cmp};
+
+/// 迁移结果
+#[derive(Debug, Clone, Default)]
+pub struct Renumbered {
+    /// 改用新 key 的映射表
+    pub mapping: Map<String, Value>,
+    /// 旧 key -> 新 key；新源文件中已经没有的字符串对应 `null`
+    pub migration: Map<String, Value>,
+    /// key 发生变化的记录数
+    pub moved: usize,
+    /// 新源文件中已经没有的字符串（旧 key）
+    pub removed: Vec<String>,
+    /// 新源文件中新出现、映射表中没有的字符串（新 key）
+    pub added: Vec<String>,
+    /// 映射表中有、旧源文件的提取结果中却没有的 key（映射表与旧源文件不对应）
+    pub unknown: Vec<String>,
+}
+
+/// 按旧、新源文件的提取结果，把映射表的 key 改为新 key
+///
+/// 记录的内容（译文、位置等附加信息）原样沿用。
+pub fn renumber_mapping(map: &Map<String, Value>, old: &[Entry], new: &[Entry]) -> Renumbered {
+    let old_texts: Vec<&str> = old.iter().map(|e| e.original.as_str()).collect();
+    let new_texts: Vec<&str> = new.iter().map(|e| e.original.as_str()).collect();
+
+    // 旧下标 -> 新下标
+    let mut pairs: Vec<Option<usize>> = vec![None; old.len()];
+    let mut unmatched_old = Vec::new();
+    let mut unmatched_new: HashMap<&str, VecDeque<usize>> = HashMap::new();
+    for edit in diff_sequences(&old_texts, &new_texts) {
+        match edit {
+            SeqEdit::Same(i, j) => pairs[i] = Some(j),
+            SeqEdit::Removed(i) => unmatched_old.push(i),
+            SeqEdit::Added(j) => unmatched_new.entry(new_texts[j]).or_default().push_back(j),
+        }
+    }
+    // 被挪到别处的字符串
+    for i in unmatched_old {
+        pairs[i] = unmatched_new
+            .get_mut(old_texts[i])
+            .and_then(VecDeque::pop_front);
+    }
+
+    let mut result = Renumbered::default();
+    let mut new_key: HashMap<&str, Option<&str>> = HashMap::new();
+    for (entry, pair) in old.iter().zip(&pairs) {
+        // 同一个 key 出现多次时（hash 模式下的相同字符串）以第一次出现为准
+        new_key
+            .entry(entry.key.as_str())
+            .or_insert(pair.map(|j| new[j].key.as_str()));
+    }
+
+    for (old_key, value) in map {
+        match new_key.get(old_key.as_str()) {
+            Some(Some(key)) => {
+                if old_key != key {
+                    result.moved += 1;
+                }
+                result.mapping.insert(key.to_string(), value.clone());
+                result
+                    .migration
+                    .insert(old_key.clone(), Value::String(key.to_string()));
+            }
+            Some(None) => {
+                result.removed.push(old_key.clone());
+                result.migration.insert(old_key.clone(), Value::Null);
+            }
+            None => result.unknown.push(old_key.clone()),
+        }
+    }
+    let mut seen = HashSet::new();
+    for entry in new {
+        if !result.mapping.contains_key(&entry.key) && seen.insert(entry.key.as_str()) {
+            result.added.push(entry.key.clone());
+        }
+    }
+    result.removed.sort_by(|a, b| natural_cmp(a, b));
+    result.unknown.sort_by(|a, b| natural_cmp(a, b));
+    result
+}
@@ -0,0 +1,56 @@
+//! 映射表分块（`--split-map` / `--split-by file`）
+//!
+//! 整张映射表太大时按条数或来源文件分成若干块 `strings.0001.json`、`strings.0002.json`……，
+//! 另写一个索引 `strings.index.json` 记录每块的文件、条数与 key 的范围：
+//! 游戏可以只加载用到的块，译者也可以按块分工。
+
+use std::num::NonZeroUsize;
+
+use serde_json::{Map, Value};
+
+use crate::Entry;
+
+/// 分块方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitMode {
+    /// 每块最多这么多条
+    Count(NonZeroUsize),
+    /// 每个来源文件一块（合并映射表中条目按文件连续排列）
+    File,
+}
+
+/// 把条目分块，保持原来的顺序；没有条目时没有块
+pub fn split_entries(entries: &[Entry], mode: SplitMode) -> Vec<&[Entry]> {
+    match mode {
+        SplitMode::Count(size) => entries.chunks(size.get()).collect(),
+        SplitMode::File => entries.chunk_by(|a, b| a.file == b.file).collect(),
+    }
+}
+
+/// 第 `n` 块（从 1 开始）的名字：`<base>.0001`
+pub fn chunk_name(base: &str, n: usize) -> String {
+    format!("{}.{:04}", base, n)
+}
+
+/// 索引中一块的记录：`files` 为这一块的各格式文件名，另记条数、第一个与最后一个 key，
+/// 以及来源文件（整块来自同一个文件时）
+pub fn chunk_record(files: Vec<String>, entries: &[Entry]) -> Value {
+    let mut obj = Map::new();
+    obj.insert(
+        "files".to_string(),
+        Value::Array(files.into_iter().map(Value::String).collect()),
+    );
+    obj.insert("count".to_string(), Value::from(entries.len()));
+    if let (Some(first), Some(last)) = (entries.first(), entries.last()) {
+        obj.insert("first".to_string(), Value::String(first.key.clone()));
+        obj.insert("last".to_string(), Value::String(last.key.clone()));
+        let source = first
+            .file
+            .as_ref()
+            .filter(|&file| entries.iter().all(|e| e.file.as_ref() == Some(file)));
+        if let Some(file) = source {
+            obj.insert("source".to_string(), Value::String(file.clone()));
+        }
+    }
+    Value::Object(obj)
+}
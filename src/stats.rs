@@ -0,0 +1,75 @@
+//! 字符串统计（`stats` 子命令）：在真正提取之前估算翻译工作量
+//!
+//! 按原文统计字符串个数、字符数（按 Unicode 字符计）、重复次数与最长的字符串，
+//! 并按语言粗分为中日韩、纯 ASCII 与其他三类。
+
+use std::collections::HashSet;
+
+use crate::Entry;
+use crate::text;
+
+/// 一类字符串的数量与字符数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tally {
+    /// 字符串个数
+    pub strings: usize,
+    /// 字符数
+    pub chars: usize,
+}
+
+impl Tally {
+    fn add(&mut self, chars: usize) {
+        self.strings += 1;
+        self.chars += chars;
+    }
+}
+
+/// 一组字符串的统计结果
+#[derive(Debug, Clone, Default)]
+pub struct StringStats {
+    /// 全部字符串
+    pub total: Tally,
+    /// 与前面某个字符串原文相同的字符串个数（去重后剩 `total.strings - duplicates` 个）
+    pub duplicates: usize,
+    /// 包含中日韩文字的字符串
+    pub cjk: Tally,
+    /// 只含 ASCII 字符的字符串
+    pub ascii: Tally,
+    /// 其余字符串（如只含全角标点、其他语言文字）
+    pub other: Tally,
+    /// 最长的字符串（字符数相同时取先出现的）
+    pub longest: Option<String>,
+}
+
+impl StringStats {
+    /// 统计一组条目；dedupe 模式下的条目按出现次数计入
+    pub fn from_entries<'a>(entries: impl IntoIterator<Item = &'a Entry>) -> Self {
+        let mut stats = Self::default();
+        let mut seen = HashSet::new();
+        let mut longest = 0;
+        for entry in entries {
+            let text = entry.original.as_str();
+            let chars = text.chars().count();
+            let count = entry.count.unwrap_or(1);
+
+            let class = if text::contains_cjk(text) {
+                &mut stats.cjk
+            } else if text.is_ascii() {
+                &mut stats.ascii
+            } else {
+                &mut stats.other
+            };
+            for _ in 0..count {
+                class.add(chars);
+                stats.total.add(chars);
+            }
+
+            stats.duplicates += count - usize::from(seen.insert(text));
+            if stats.longest.is_none() || chars > longest {
+                longest = chars;
+                stats.longest = Some(text.to_string());
+            }
+        }
+        stats
+    }
+}
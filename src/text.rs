@@ -0,0 +1,119 @@
+//! 文本相关的小工具
+
+/// 判断字符是否为中日韩文字（汉字、假名、谚文）
+///
+/// 不包括 CJK 标点与全角符号：只含标点的字符串没有需要翻译的内容。
+pub fn is_cjk_char(c: char) -> bool {
+    matches!(c as u32,
+        0x3400..=0x4DBF     // CJK 统一汉字扩展 A
+        | 0x4E00..=0x9FFF   // CJK 统一汉字
+        | 0xF900..=0xFAFF   // CJK 兼容汉字
+        | 0x20000..=0x2FA1F // CJK 统一汉字扩展 B 及之后
+        | 0x3040..=0x309F   // 平假名
+        | 0x30A0..=0x30FF   // 片假名
+        | 0x31F0..=0x31FF   // 片假名音标扩展
+        | 0xFF66..=0xFF9F   // 半角片假名
+        | 0x1100..=0x11FF   // 谚文字母
+        | 0x3130..=0x318F   // 谚文兼容字母
+        | 0xAC00..=0xD7AF   // 谚文音节
+    )
+}
+
+/// 判断字符串是否包含中日韩文字
+pub fn contains_cjk(s: &str) -> bool {
+    s.chars().any(is_cjk_char)
+}
+
+/// JSX 文本的实际内容（与 Babel / TypeScript 的处理一致）：除第一行外去掉行首、除最后一行外去掉行尾的
+/// 空格与制表符，丢弃空行，其余各行以一个空格连接
+pub fn collapse_jsx_text(s: &str) -> String {
+    let lines: Vec<&str> = s
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect();
+    let last = lines
+        .iter()
+        .rposition(|line| line.contains(|c| c != ' ' && c != '\t'));
+    let mut out = String::with_capacity(s.len());
+    for (i, line) in lines.iter().enumerate() {
+        let mut line = *line;
+        if i > 0 {
+            line = line.trim_start_matches([' ', '\t']);
+        }
+        if i + 1 < lines.len() {
+            line = line.trim_end_matches([' ', '\t']);
+        }
+        if line.is_empty() {
+            continue;
+        }
+        out.push_str(&line.replace('\t', " "));
+        if Some(i) != last {
+            out.push(' ');
+        }
+    }
+    out
+}
+
+/// `--trim` / `--collapse-whitespace` 处理的空白；全角空格（U+3000）常用作中文的段首缩进，不算在内
+fn is_loose_space(c: char) -> bool {
+    c.is_whitespace() && c != '\u{3000}'
+}
+
+/// 规范化字符串中的空白：`trim` 时去掉两端的空白，`collapse` 时把连续的空白（含换行）合并为一个空格
+pub fn normalize_whitespace(s: &str, trim: bool, collapse: bool) -> String {
+    let s = if trim {
+        s.trim_matches(is_loose_space)
+    } else {
+        s
+    };
+    if !collapse {
+        return s.to_string();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut in_space = false;
+    for c in s.chars() {
+        if is_loose_space(c) {
+            if !in_space {
+                out.push(' ');
+            }
+            in_space = true;
+        } else {
+            out.push(c);
+            in_space = false;
+        }
+    }
+    out
+}
+
+/// 把字符串写成 JS 字符串字面量（含两侧的引号）
+///
+/// `ascii_only` 时非 ASCII 字符写成 `\uXXXX`（按 UTF-16 代码单元）。
+pub fn quote_js(s: &str, quote: char, ascii_only: bool) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push(quote);
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c == quote => {
+                out.push('\\');
+                out.push(c);
+            }
+            // 控制字符写成 \xHH（不用 \0，避免与后面的数字连成八进制转义）
+            c if c.is_ascii_control() => out.push_str(&format!("\\x{:02x}", c as u32)),
+            // 行分隔符与段分隔符在旧引擎中不能出现在字符串里
+            '\u{2028}' | '\u{2029}' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c if ascii_only && !c.is_ascii() => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    out.push_str(&format!("\\u{:04x}", unit));
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out.push(quote);
+    out
+}
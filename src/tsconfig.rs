@@ -0,0 +1,148 @@
+//! 读取 tsconfig.json（`--tsconfig`）：要处理哪些文件由项目自己的 `files` / `include` / `exclude` 决定
+//!
+//! tsconfig 允许注释与末尾逗号，先用 [`strip_jsonc`] 去掉再按 JSON 解析。
+//! `extends` 只跟随相对路径（以 `.` 开头）的配置，npm 包形式的基础配置（如 `@tsconfig/node18`）跳过；
+//! 本文件没有给出的项沿用基础配置的，路径相对于给出它的配置文件所在目录。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+/// `extends` 最多跟随的层数，防止循环引用
+const MAX_EXTENDS: usize = 16;
+
+/// tsconfig.json 中与文件选择有关的项，路径都已经拼上所在配置文件的目录
+#[derive(Debug, Clone, Default)]
+pub struct TsConfig {
+    /// 配置文件所在目录（当前目录时为空路径），`include` 的默认值 `**/*` 相对于它
+    pub dir: PathBuf,
+    /// `files`：逐个列出的文件
+    pub files: Option<Vec<PathBuf>>,
+    /// `include`：文件或目录的通配模式
+    pub include: Option<Vec<PathBuf>>,
+    /// `exclude`：从 `include` 的结果中去掉的模式
+    pub exclude: Option<Vec<PathBuf>>,
+    /// `compilerOptions.outDir`：未给出 `exclude` 时也排除它
+    pub out_dir: Option<PathBuf>,
+}
+
+impl TsConfig {
+    /// 读取配置文件，并合并 `extends` 的基础配置
+    pub fn load(path: &Path) -> Result<Self, String> {
+        Self::load_nested(path, 0)
+    }
+
+    fn load_nested(path: &Path, depth: usize) -> Result<Self, String> {
+        let text =
+            fs::read_to_string(path).map_err(|e| format!("读取 {} 失败: {}", path.display(), e))?;
+        let value: Value = serde_json::from_str(&strip_jsonc(&text))
+            .map_err(|e| format!("{} 不是有效的 tsconfig: {}", path.display(), e))?;
+
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let paths = |key: &str| {
+            value.get(key).and_then(Value::as_array).map(|items| {
+                items
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(|p| dir.join(p))
+                    .collect::<Vec<_>>()
+            })
+        };
+        let mut config = TsConfig {
+            files: paths("files"),
+            include: paths("include"),
+            exclude: paths("exclude"),
+            out_dir: value
+                .pointer("/compilerOptions/outDir")
+                .and_then(Value::as_str)
+                .map(|p| dir.join(p)),
+            dir: dir.clone(),
+        };
+
+        // extends 可以是字符串或数组（TypeScript 5.0 起），后面的覆盖前面的
+        let bases: Vec<&str> = match value.get("extends") {
+            Some(Value::String(base)) => vec![base],
+            Some(Value::Array(items)) => items.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        for base in bases.into_iter().rev().filter(|b| b.starts_with('.')) {
+            if depth >= MAX_EXTENDS {
+                return Err(format!("{} 的 extends 嵌套过深", path.display()));
+            }
+            let mut base_path = dir.join(base);
+            if !base_path.is_file() {
+                base_path = dir.join(format!("{}.json", base));
+            }
+            let base = Self::load_nested(&base_path, depth + 1)?;
+            config.files = config.files.or(base.files);
+            config.include = config.include.or(base.include);
+            config.exclude = config.exclude.or(base.exclude);
+            config.out_dir = config.out_dir.or(base.out_dir);
+        }
+        Ok(config)
+    }
+}
+
+/// 去掉 JSONC 中的注释与末尾逗号（以及开头的 BOM），得到标准 JSON
+///
+/// 字符串中的 `//`、`/*` 与逗号保持不变。
+pub fn strip_jsonc(text: &str) -> String {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+
+    // 先去掉注释（块注释替换为一个空格，避免把两侧的内容连在一起）
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                plain.push(c);
+                while let Some(c) = chars.next() {
+                    plain.push(c);
+                    match c {
+                        '\\' => plain.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => while chars.next_if(|&c| c != '\n').is_some() {},
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = '\0';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+                plain.push(' ');
+            }
+            _ => plain.push(c),
+        }
+    }
+
+    // 再去掉 `}` / `]` 之前的逗号
+    let mut out = String::with_capacity(plain.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in plain.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' {
+            let next = plain[i + 1..].trim_start().chars().next();
+            if matches!(next, Some('}' | ']')) {
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    out
+}